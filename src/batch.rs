@@ -0,0 +1,558 @@
+use crate::encoder::ErrorCorrectionLevel;
+use crate::error::QRError;
+use crate::matrix::QRMatrix;
+use crate::render::{self, ImageFormat, RenderOptions};
+use crate::QRData;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchFormat {
+    Csv,
+    JsonLines,
+}
+
+impl BatchFormat {
+    /// Infers the record format from the extension of the input file.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let (_, extension) = path.rsplit_once('.')?;
+        match extension.to_ascii_lowercase().as_str() {
+            "csv" => Some(BatchFormat::Csv),
+            "jsonl" | "ndjson" => Some(BatchFormat::JsonLines),
+            _ => None,
+        }
+    }
+}
+
+/// One symbol to generate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchRecord {
+    /// Line of the input file the record starts on, for reporting.
+    pub line: usize,
+    pub content: String,
+    pub filename: String,
+    pub ec_level: Option<ErrorCorrectionLevel>,
+    pub version: Option<u8>,
+}
+
+/// A record that could not be read or generated.
+#[derive(Debug, Clone)]
+pub struct BatchFailure {
+    pub line: usize,
+    pub filename: Option<String>,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BatchReport {
+    pub written: Vec<PathBuf>,
+    pub failures: Vec<BatchFailure>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// Level used for records that do not name one.
+    pub ec_level: ErrorCorrectionLevel,
+    /// Format used for file names without a known image extension.
+    pub image_format: ImageFormat,
+    pub render: RenderOptions,
+    /// Worker threads; `0` uses the available parallelism.
+    pub threads: usize,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        BatchOptions {
+            ec_level: ErrorCorrectionLevel::M,
+            image_format: ImageFormat::Svg,
+            render: RenderOptions::default(),
+            threads: 0,
+        }
+    }
+}
+
+/// Parses every record of the input. Malformed records are returned as
+/// failures instead of stopping the whole batch.
+pub fn parse_records(input: &str, format: BatchFormat) -> (Vec<BatchRecord>, Vec<BatchFailure>) {
+    let rows = match format {
+        BatchFormat::Csv => parse_csv(input),
+        BatchFormat::JsonLines => parse_json_lines(input),
+    };
+
+    let mut records = Vec::new();
+    let mut failures = Vec::new();
+    // Line each file name was first used on; later records would overwrite it.
+    let mut claimed: HashMap<String, usize> = HashMap::new();
+    for (line, row) in rows {
+        let record = row
+            .and_then(|fields| record_from_fields(line, fields))
+            .and_then(|record| match claimed.get(&record.filename) {
+                Some(first) => Err(QRError::InvalidInput(format!(
+                    "File name '{}' is already used on line {}",
+                    record.filename, first
+                ))),
+                None => {
+                    claimed.insert(record.filename.clone(), line);
+                    Ok(record)
+                }
+            });
+        match record {
+            Ok(record) => records.push(record),
+            Err(e) => failures.push(BatchFailure {
+                line,
+                filename: None,
+                error: e.to_string(),
+            }),
+        }
+    }
+    (records, failures)
+}
+
+/// Generates every record into `output_dir` on a pool of worker threads.
+pub fn generate_batch(
+    records: &[BatchRecord],
+    output_dir: &Path,
+    options: &BatchOptions,
+) -> BatchReport {
+    let threads = match options.threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+    .min(records.len().max(1));
+
+    // Names that only differ by the default extension still collide, so
+    // claim the resolved names before any file is written.
+    let mut claimed: HashMap<String, usize> = HashMap::new();
+    let duplicates: Vec<Option<String>> = records
+        .iter()
+        .map(|record| {
+            let (filename, _) = output_name(record, options);
+            match claimed.get(&filename) {
+                Some(first) => Some(format!(
+                    "File name '{}' is already used on line {}",
+                    filename, first
+                )),
+                None => {
+                    claimed.insert(filename, record.line);
+                    None
+                }
+            }
+        })
+        .collect();
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(records.len()));
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(record) = records.get(index) else {
+                    break;
+                };
+                let result = match &duplicates[index] {
+                    Some(message) => Err(QRError::InvalidInput(message.clone())),
+                    None => generate_record(record, output_dir, options),
+                };
+                results.lock().unwrap().push((index, result));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+
+    let mut report = BatchReport::default();
+    for (index, result) in results {
+        match result {
+            Ok(path) => report.written.push(path),
+            Err(e) => report.failures.push(BatchFailure {
+                line: records[index].line,
+                filename: Some(records[index].filename.clone()),
+                error: e.to_string(),
+            }),
+        }
+    }
+    report
+}
+
+pub fn encode_record(
+    record: &BatchRecord,
+    default_ec_level: ErrorCorrectionLevel,
+) -> Result<QRMatrix, QRError> {
+    let ec_level = record.ec_level.unwrap_or(default_ec_level);
+    let mut qr_data = QRData::new();
+
+    qr_data.set_content(&record.content)?;
+    qr_data.set_ec_level(ec_level)?;
+    qr_data.set_version(record.version)?;
+    if qr_data.get_version().is_none() {
+        return Err(QRError::InvalidLength(format!(
+            "Content is too long for error correction level {:?}",
+            ec_level
+        )));
+    }
+    qr_data.generate(None)
+}
+
+/// The file name a record is written to, with the default extension added
+/// when it has no known one.
+fn output_name(record: &BatchRecord, options: &BatchOptions) -> (String, ImageFormat) {
+    match ImageFormat::from_path(&record.filename) {
+        Some(format) => (record.filename.clone(), format),
+        None => (
+            format!("{}.{}", record.filename, options.image_format.extension()),
+            options.image_format,
+        ),
+    }
+}
+
+fn generate_record(
+    record: &BatchRecord,
+    output_dir: &Path,
+    options: &BatchOptions,
+) -> Result<PathBuf, QRError> {
+    let (filename, format) = output_name(record, options);
+
+    options.render.check_contrast()?;
+    let matrix = encode_record(record, options.ec_level)?;
+    let path = output_dir.join(filename);
+    std::fs::write(&path, render::render(&matrix, format, &options.render))
+        .map_err(|e| QRError::EncodingError(format!("Cannot write {}: {}", path.display(), e)))?;
+    Ok(path)
+}
+
+fn record_from_fields(
+    line: usize,
+    mut fields: HashMap<String, String>,
+) -> Result<BatchRecord, QRError> {
+    let content = fields
+        .remove("content")
+        .ok_or_else(|| QRError::InvalidInput("Missing content".to_string()))?;
+    let filename = fields
+        .remove("filename")
+        .filter(|f| !f.is_empty())
+        .ok_or_else(|| QRError::InvalidInput("Missing filename".to_string()))?;
+
+    // Every symbol must land inside the output directory.
+    if filename.contains(['/', '\\']) || filename.starts_with('.') {
+        return Err(QRError::InvalidInput(format!(
+            "File name '{}' must not contain a path",
+            filename
+        )));
+    }
+
+    let ec_level = match fields.remove("ec_level").filter(|v| !v.is_empty()) {
+        Some(level) => Some(level.parse()?),
+        None => None,
+    };
+    let version = match fields.remove("version").filter(|v| !v.is_empty()) {
+        Some(version) => Some(
+            version
+                .parse::<u8>()
+                .map_err(|_| QRError::InvalidVersion(format!("Invalid version '{}'", version)))?,
+        ),
+        None => None,
+    };
+
+    Ok(BatchRecord {
+        line,
+        content,
+        filename,
+        ec_level,
+        version,
+    })
+}
+
+type Row = (usize, Result<HashMap<String, String>, QRError>);
+
+/// Parses RFC 4180 CSV with a header row naming the columns.
+fn parse_csv(input: &str) -> Vec<Row> {
+    let mut rows = Vec::new();
+    let mut header: Option<Vec<String>> = None;
+
+    for (line, fields) in split_csv(input) {
+        if fields.iter().all(|f| f.is_empty()) {
+            continue;
+        }
+
+        let Some(columns) = &header else {
+            header = Some(fields.iter().map(|f| f.trim().to_lowercase()).collect());
+            continue;
+        };
+
+        if fields.len() != columns.len() {
+            rows.push((
+                line,
+                Err(QRError::InvalidInput(format!(
+                    "Expected {} fields, found {}",
+                    columns.len(),
+                    fields.len()
+                ))),
+            ));
+            continue;
+        }
+        rows.push((line, Ok(columns.iter().cloned().zip(fields).collect())));
+    }
+    rows
+}
+
+/// Splits CSV text into records, honouring quoted fields that contain
+/// separators, doubled quotes or line breaks.
+fn split_csv(input: &str) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if field.is_empty() => in_quotes = true,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes && chars.peek() == Some(&'\n') => {}
+            '\n' if !in_quotes => {
+                fields.push(std::mem::take(&mut field));
+                records.push((record_line, std::mem::take(&mut fields)));
+                line += 1;
+                record_line = line;
+            }
+            _ => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        records.push((record_line, fields));
+    }
+    records
+}
+
+/// Parses one flat JSON object per line. Numbers, booleans and `null` are
+/// kept as their text so they can share the CSV field handling.
+fn parse_json_lines(input: &str) -> Vec<Row> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, text)| !text.trim().is_empty())
+        .map(|(index, text)| (index + 1, parse_json_object(text.trim())))
+        .collect()
+}
+
+fn parse_json_object(text: &str) -> Result<HashMap<String, String>, QRError> {
+    let mut parser = JsonParser {
+        chars: text.chars().collect(),
+        pos: 0,
+    };
+    let mut fields = HashMap::new();
+
+    parser.expect('{')?;
+    if !parser.consume('}') {
+        loop {
+            let key = parser.parse_string()?;
+            parser.expect(':')?;
+            if let Some(value) = parser.parse_value()? {
+                fields.insert(key, value);
+            }
+            if parser.consume('}') {
+                break;
+            }
+            parser.expect(',')?;
+        }
+    }
+
+    parser.skip_whitespace();
+    if parser.pos != parser.chars.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(fields)
+}
+
+struct JsonParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl JsonParser {
+    fn error(&self, message: &str) -> QRError {
+        QRError::InvalidInput(format!(
+            "Invalid JSON at column {}: {}",
+            self.pos + 1,
+            message
+        ))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn consume(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        if self.chars.get(self.pos) == Some(&expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), QRError> {
+        if self.consume(expected) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", expected)))
+        }
+    }
+
+    /// Parses a scalar value. `null` yields `None`.
+    fn parse_value(&mut self) -> Result<Option<String>, QRError> {
+        self.skip_whitespace();
+        match self.chars.get(self.pos) {
+            Some('"') => self.parse_string().map(Some),
+            Some(c) if c.is_ascii_alphanumeric() || *c == '-' => {
+                let start = self.pos;
+                while self
+                    .chars
+                    .get(self.pos)
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || "+-.".contains(*c))
+                {
+                    self.pos += 1;
+                }
+                let literal: String = self.chars[start..self.pos].iter().collect();
+                match literal.as_str() {
+                    "null" => Ok(None),
+                    "true" | "false" => Ok(Some(literal)),
+                    _ if is_json_number(&literal) => Ok(Some(literal)),
+                    _ => Err(self.error("unknown literal")),
+                }
+            }
+            _ => Err(self.error("expected a string, number or null")),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, QRError> {
+        self.expect('"')?;
+        let mut result = String::new();
+
+        loop {
+            let c = *self
+                .chars
+                .get(self.pos)
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += 1;
+
+            match c {
+                '"' => return Ok(result),
+                '\\' => {
+                    let escape = *self
+                        .chars
+                        .get(self.pos)
+                        .ok_or_else(|| self.error("unterminated escape"))?;
+                    self.pos += 1;
+                    match escape {
+                        '"' | '\\' | '/' => result.push(escape),
+                        'b' => result.push('\u{8}'),
+                        'f' => result.push('\u{c}'),
+                        'n' => result.push('\n'),
+                        'r' => result.push('\r'),
+                        't' => result.push('\t'),
+                        'u' => result.push(self.parse_unicode_escape()?),
+                        _ => return Err(self.error("invalid escape")),
+                    }
+                }
+                _ => result.push(c),
+            }
+        }
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, QRError> {
+        let digits: String = self.chars.iter().skip(self.pos).take(4).collect();
+        if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(self.error("invalid \\u escape"));
+        }
+        let value =
+            u32::from_str_radix(&digits, 16).map_err(|_| self.error("invalid \\u escape"))?;
+        self.pos += 4;
+        Ok(value)
+    }
+
+    fn parse_unicode_escape(&mut self) -> Result<char, QRError> {
+        let high = self.parse_hex4()?;
+
+        let code = if (0xD800..0xDC00).contains(&high) {
+            // A surrogate pair spells characters outside the basic plane.
+            if self.chars.get(self.pos) != Some(&'\\') || self.chars.get(self.pos + 1) != Some(&'u')
+            {
+                return Err(self.error("unpaired surrogate"));
+            }
+            self.pos += 2;
+            let low = self.parse_hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(self.error("unpaired surrogate"));
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+
+        char::from_u32(code).ok_or_else(|| self.error("invalid code point"))
+    }
+}
+
+/// Whether `literal` follows the JSON number grammar, which unlike
+/// `f64::from_str` has no `NaN`, `inf`, leading `+` or bare `.5`.
+fn is_json_number(literal: &str) -> bool {
+    fn digits(bytes: &[u8], mut pos: usize) -> usize {
+        while bytes.get(pos).is_some_and(u8::is_ascii_digit) {
+            pos += 1;
+        }
+        pos
+    }
+
+    let bytes = literal.as_bytes();
+    let mut pos = usize::from(bytes.first() == Some(&b'-'));
+    match bytes.get(pos) {
+        Some(b'0') => pos += 1,
+        Some(b'1'..=b'9') => pos = digits(bytes, pos),
+        _ => return false,
+    }
+    if bytes.get(pos) == Some(&b'.') {
+        let end = digits(bytes, pos + 1);
+        if end == pos + 1 {
+            return false;
+        }
+        pos = end;
+    }
+    if matches!(bytes.get(pos), Some(b'e' | b'E')) {
+        pos += 1;
+        if matches!(bytes.get(pos), Some(b'+' | b'-')) {
+            pos += 1;
+        }
+        let end = digits(bytes, pos);
+        if end == pos {
+            return false;
+        }
+        pos = end;
+    }
+    pos == bytes.len()
+}
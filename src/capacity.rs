@@ -0,0 +1,265 @@
+use crate::encoder::ErrorCorrectionLevel;
+use crate::error::EncodeError;
+use crate::versions::DATA_CODEWORDS;
+use crate::InputMode;
+#[cfg(feature = "alloc")]
+use crate::{error::QRError, input::QRInput};
+#[cfg(feature = "alloc")]
+use alloc::{format, vec::Vec};
+
+/// Error correction levels from lowest to highest.
+pub const LEVELS: [ErrorCorrectionLevel; 4] = [
+    ErrorCorrectionLevel::L,
+    ErrorCorrectionLevel::M,
+    ErrorCorrectionLevel::Q,
+    ErrorCorrectionLevel::H,
+];
+
+/// Bits of a mode indicator.
+const MODE_BITS: usize = 4;
+
+/// Encoding modes a segment can use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentMode {
+    Numeric,
+    Alphanumeric,
+    Byte,
+    Kanji,
+}
+
+impl SegmentMode {
+    pub const ALL: [SegmentMode; 4] = [
+        SegmentMode::Numeric,
+        SegmentMode::Alphanumeric,
+        SegmentMode::Byte,
+        SegmentMode::Kanji,
+    ];
+
+    /// The 4-bit mode indicator.
+    pub const fn indicator(self) -> u32 {
+        match self {
+            SegmentMode::Numeric => 0b0001,
+            SegmentMode::Alphanumeric => 0b0010,
+            SegmentMode::Byte => 0b0100,
+            SegmentMode::Kanji => 0b1000,
+        }
+    }
+
+    /// Width of the character count indicator at the given version.
+    pub const fn count_bits(self, version: u8) -> usize {
+        let group = match version {
+            1..=9 => 0,
+            10..=26 => 1,
+            _ => 2,
+        };
+        match self {
+            SegmentMode::Numeric => [10, 12, 14][group],
+            SegmentMode::Alphanumeric => [9, 11, 13][group],
+            SegmentMode::Byte => [8, 16, 16][group],
+            SegmentMode::Kanji => [8, 10, 12][group],
+        }
+    }
+
+    /// Bits for `length` characters (bytes in byte mode) after the header.
+    pub const fn data_bits(self, length: usize) -> usize {
+        match self {
+            SegmentMode::Numeric => length / 3 * 10 + [0, 4, 7][length % 3],
+            SegmentMode::Alphanumeric => length / 2 * 11 + length % 2 * 6,
+            SegmentMode::Byte => length * 8,
+            SegmentMode::Kanji => length * 13,
+        }
+    }
+
+    /// Total bits of one segment: mode indicator, count and data.
+    pub const fn segment_bits(self, length: usize, version: u8) -> usize {
+        MODE_BITS + self.count_bits(version) + self.data_bits(length)
+    }
+
+    /// The most characters a count indicator can announce at this version.
+    pub const fn max_count(self, version: u8) -> usize {
+        (1 << self.count_bits(version)) - 1
+    }
+}
+
+impl From<InputMode> for SegmentMode {
+    fn from(mode: InputMode) -> Self {
+        match mode {
+            InputMode::Numeric => SegmentMode::Numeric,
+            InputMode::Alphanumeric => SegmentMode::Alphanumeric,
+            InputMode::Byte => SegmentMode::Byte,
+        }
+    }
+}
+
+/// Bits taken by an ECI header announcing `assignment`.
+pub fn eci_bits(assignment: u32) -> Result<usize, EncodeError> {
+    let designator = match assignment {
+        0..=127 => 8,
+        128..=16383 => 16,
+        16384..=999_999 => 24,
+        _ => return Err(EncodeError::InvalidEci(assignment)),
+    };
+    Ok(MODE_BITS + designator)
+}
+
+/// Data bits a symbol holds at this version and level.
+pub fn data_capacity_bits(version: u8, ec_level: ErrorCorrectionLevel) -> usize {
+    DATA_CODEWORDS[version as usize - 1][ec_level as usize] as usize * 8
+}
+
+/// The segments, and optional ECI header, of planned content.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContentPlan {
+    pub segments: Vec<(SegmentMode, usize)>,
+    pub eci: Option<u32>,
+}
+
+#[cfg(feature = "alloc")]
+impl ContentPlan {
+    pub fn new() -> Self {
+        ContentPlan::default()
+    }
+
+    /// Adds a segment of `length` characters (bytes in byte mode).
+    pub fn segment(mut self, mode: SegmentMode, length: usize) -> Self {
+        self.segments.push((mode, length));
+        self
+    }
+
+    pub fn eci(mut self, assignment: u32) -> Self {
+        self.eci = Some(assignment);
+        self
+    }
+
+    /// The plan for `content` as the encoder would store it: one segment in
+    /// the automatically detected mode.
+    pub fn for_content(content: &str) -> Result<Self, QRError> {
+        let mut input = QRInput::new();
+        let mode = input.set_content(content)?;
+        Ok(ContentPlan::new().segment(mode.into(), input.get_character_count()))
+    }
+
+    /// Bits used at this version, or `None` when a segment is longer than
+    /// its count indicator allows.
+    pub fn bits(&self, version: u8) -> Result<Option<usize>, QRError> {
+        let mut bits = match self.eci {
+            Some(assignment) => eci_bits(assignment)?,
+            None => 0,
+        };
+        for &(mode, length) in &self.segments {
+            if length > mode.max_count(version) {
+                return Ok(None);
+            }
+            bits += mode.segment_bits(length, version);
+        }
+        Ok(Some(bits))
+    }
+}
+
+/// A version and level that holds the planned content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fit {
+    pub version: u8,
+    pub ec_level: ErrorCorrectionLevel,
+    /// Modules per side.
+    pub size: usize,
+    pub used_bits: usize,
+    pub capacity_bits: usize,
+}
+
+impl Fit {
+    pub fn remaining_bits(&self) -> usize {
+        self.capacity_bits - self.used_bits
+    }
+}
+
+/// Every version and level combination the plan fits, smallest version
+/// first.
+#[cfg(feature = "alloc")]
+pub fn fits(plan: &ContentPlan) -> Result<Vec<Fit>, QRError> {
+    let mut fits = Vec::new();
+    for version in 1..=40u8 {
+        let Some(used_bits) = plan.bits(version)? else {
+            continue;
+        };
+        for ec_level in LEVELS {
+            let capacity_bits = data_capacity_bits(version, ec_level);
+            if used_bits <= capacity_bits {
+                fits.push(Fit {
+                    version,
+                    ec_level,
+                    size: version as usize * 4 + 17,
+                    used_bits,
+                    capacity_bits,
+                });
+            }
+        }
+    }
+    Ok(fits)
+}
+
+/// The smallest version holding the plan at this level.
+#[cfg(feature = "alloc")]
+pub fn smallest_version(
+    plan: &ContentPlan,
+    ec_level: ErrorCorrectionLevel,
+) -> Result<Option<u8>, QRError> {
+    Ok(fits(plan)?
+        .into_iter()
+        .find(|fit| fit.ec_level == ec_level)
+        .map(|fit| fit.version))
+}
+
+/// The most characters (bytes in byte mode) one more segment of `mode` can
+/// hold at this version and level, after whatever `plan` already uses.
+#[cfg(feature = "alloc")]
+pub fn max_length(
+    version: u8,
+    ec_level: ErrorCorrectionLevel,
+    mode: SegmentMode,
+    plan: &ContentPlan,
+) -> Result<usize, QRError> {
+    if !(1..=40).contains(&version) {
+        return Err(QRError::InvalidVersion(format!(
+            "Version {} is outside 1-40",
+            version
+        )));
+    }
+    let Some(used) = plan.bits(version)? else {
+        return Ok(0);
+    };
+    let capacity = data_capacity_bits(version, ec_level);
+    if used > capacity {
+        return Ok(0);
+    }
+    Ok(max_characters(mode, version, capacity - used))
+}
+
+/// The most characters one segment of `mode` holds in `bits`, counting its
+/// mode indicator and character count.
+pub(crate) const fn max_characters(mode: SegmentMode, version: u8, bits: usize) -> usize {
+    let header = MODE_BITS + mode.count_bits(version);
+    if header > bits {
+        return 0;
+    }
+
+    let free = bits - header;
+    // Start from the average cost per character and step down to the exact
+    // limit, since numeric and alphanumeric groups round up.
+    let mut length = match mode {
+        SegmentMode::Numeric => free * 3 / 10 + 1,
+        SegmentMode::Alphanumeric => free * 2 / 11 + 1,
+        SegmentMode::Byte => free / 8,
+        SegmentMode::Kanji => free / 13,
+    };
+    while length > 0 && mode.data_bits(length) > free {
+        length -= 1;
+    }
+    let max_count = mode.max_count(version);
+    if length > max_count {
+        max_count
+    } else {
+        length
+    }
+}
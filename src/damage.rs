@@ -0,0 +1,291 @@
+use crate::decoder::{decode_matrix, Decoded};
+use crate::error::QRError;
+use crate::error_correction::BlockLayout;
+use crate::matrix::QRMatrix;
+use crate::random::Rng;
+
+/// Trials per severity in [`robustness`].
+const TRIALS_PER_SEVERITY: usize = 4;
+
+/// Severities of the [`robustness`] battery: the share of modules flipped,
+/// and blot radius, stripe width and corner size as fractions of the
+/// symbol width.
+const FLIP_FRACTIONS: [f64; 5] = [0.01, 0.02, 0.04, 0.08, 0.12];
+const BLOT_RADII: [f64; 4] = [0.05, 0.1, 0.15, 0.2];
+const STRIPE_WIDTHS: [f64; 4] = [0.05, 0.1, 0.15, 0.2];
+const CORNER_SIZES: [f64; 4] = [0.1, 0.2, 0.3, 0.4];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Physical damage applied to a finished symbol. Coordinates and sizes are
+/// in modules.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Damage {
+    /// Inverts this share of all modules, chosen at random.
+    Flips { fraction: f64 },
+    /// An ink blot: every module within `radius` of the centre turns dark.
+    Blot { x: f64, y: f64, radius: f64 },
+    /// A scuffed band `width` modules wide starting at `offset` that turns
+    /// light.
+    Stripe {
+        vertical: bool,
+        offset: usize,
+        width: usize,
+    },
+    /// A torn-off corner: the triangle of modules within `size` steps of the
+    /// corner turns light.
+    MissingCorner { corner: Corner, size: usize },
+}
+
+impl Damage {
+    /// Applies the damage and returns how many modules changed colour.
+    pub fn apply(&self, matrix: &mut QRMatrix, rng: &mut Rng) -> usize {
+        let size = matrix.get_size();
+        let mut changed = 0;
+        let mut set = |matrix: &mut QRMatrix, x: usize, y: usize, dark: bool| {
+            if matrix.is_dark(x, y) != dark {
+                matrix.set_module(x, y, dark);
+                changed += 1;
+            }
+        };
+
+        match *self {
+            Damage::Flips { fraction } => {
+                let count = (fraction.clamp(0.0, 1.0) * (size * size) as f64).round() as usize;
+                for index in rng.choose(size * size, count) {
+                    let (x, y) = (index % size, index / size);
+                    let dark = matrix.is_dark(x, y);
+                    set(matrix, x, y, !dark);
+                }
+            }
+            Damage::Blot {
+                x: cx,
+                y: cy,
+                radius,
+            } => {
+                for y in 0..size {
+                    for x in 0..size {
+                        let distance = (x as f64 + 0.5 - cx).hypot(y as f64 + 0.5 - cy);
+                        if distance <= radius {
+                            set(matrix, x, y, true);
+                        }
+                    }
+                }
+            }
+            Damage::Stripe {
+                vertical,
+                offset,
+                width,
+            } => {
+                for a in offset.min(size)..offset.saturating_add(width).min(size) {
+                    for b in 0..size {
+                        let (x, y) = if vertical { (a, b) } else { (b, a) };
+                        set(matrix, x, y, false);
+                    }
+                }
+            }
+            Damage::MissingCorner { corner, size: cut } => {
+                for y in 0..size {
+                    for x in 0..size {
+                        let dx = match corner {
+                            Corner::TopLeft | Corner::BottomLeft => x,
+                            _ => size - 1 - x,
+                        };
+                        let dy = match corner {
+                            Corner::TopLeft | Corner::TopRight => y,
+                            _ => size - 1 - y,
+                        };
+                        if dx + dy < cut {
+                            set(matrix, x, y, false);
+                        }
+                    }
+                }
+            }
+        }
+        changed
+    }
+}
+
+/// Outcome of decoding one damaged symbol.
+#[derive(Debug, Clone)]
+pub struct DamageReport {
+    /// The symbol after damage.
+    pub matrix: QRMatrix,
+    pub changed_modules: usize,
+    /// Whether the symbol decoded to the original content.
+    pub success: bool,
+    pub decoded: Option<Decoded>,
+    /// Why decoding failed, or that it produced different content.
+    pub error: Option<String>,
+    /// EC codewords per block still unused after correction; empty when
+    /// decoding failed.
+    pub remaining_budget: Vec<usize>,
+    pub ecc_per_block: usize,
+}
+
+impl DamageReport {
+    /// Smallest remaining budget of any block as a share of the EC
+    /// codewords, 0 when decoding failed.
+    pub fn margin(&self) -> f64 {
+        match self.remaining_budget.iter().min() {
+            Some(&least) if self.success => least as f64 / self.ecc_per_block as f64,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Damages a copy of `matrix` and tries to decode it. `seed` drives the
+/// random choices, so the same call always gives the same report.
+///
+/// This works on modules: it shows what error correction can absorb, not
+/// whether a camera still finds the finder patterns.
+pub fn simulate_damage(
+    matrix: &QRMatrix,
+    damage: &[Damage],
+    seed: u64,
+) -> Result<DamageReport, QRError> {
+    let original = decode_matrix(matrix)?;
+    let layout = BlockLayout::new(original.version, original.ec_level)?;
+
+    let mut rng = Rng::new(seed);
+    let mut damaged = matrix.clone();
+    let changed_modules = damage.iter().map(|d| d.apply(&mut damaged, &mut rng)).sum();
+
+    let (success, decoded, error) = match decode_matrix(&damaged) {
+        Ok(decoded) if decoded.content == original.content => (true, Some(decoded), None),
+        Ok(decoded) => (
+            false,
+            Some(decoded),
+            Some("Decoded to different content".to_string()),
+        ),
+        Err(e) => (false, None, Some(e.to_string())),
+    };
+    let remaining_budget = match &decoded {
+        Some(decoded) if success => decoded
+            .corrections
+            .iter()
+            .map(|c| c.remaining_budget(layout.ecc_per_block))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Ok(DamageReport {
+        matrix: damaged,
+        changed_modules,
+        success,
+        decoded,
+        error,
+        remaining_budget,
+        ecc_per_block: layout.ecc_per_block,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamageKind {
+    Flips,
+    Blot,
+    Stripe,
+    MissingCorner,
+}
+
+/// How one kind of damage fared across the battery.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KindResult {
+    pub kind: DamageKind,
+    /// Largest severity survived by every trial, counting up from the
+    /// mildest; `None` if even that failed. Same units as the battery.
+    pub max_survived: Option<f64>,
+    pub trials: usize,
+    pub survived: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RobustnessReport {
+    pub kinds: Vec<KindResult>,
+    /// Mean over all trials of the decoding margin, as 0-100. Zero for a
+    /// failed trial, so both failures and thin margins pull it down.
+    pub score: f64,
+}
+
+/// Runs a fixed battery of damage at increasing severity, scaled to the
+/// symbol size, so scores of one content at different error correction
+/// levels can be compared directly.
+pub fn robustness(matrix: &QRMatrix, seed: u64) -> Result<RobustnessReport, QRError> {
+    let size = matrix.get_size();
+    let mut rng = Rng::new(seed);
+    let mut kinds = Vec::new();
+    let mut margin_sum = 0.0;
+    let mut total_trials = 0;
+
+    let battery: [(DamageKind, &[f64]); 4] = [
+        (DamageKind::Flips, &FLIP_FRACTIONS),
+        (DamageKind::Blot, &BLOT_RADII),
+        (DamageKind::Stripe, &STRIPE_WIDTHS),
+        (DamageKind::MissingCorner, &CORNER_SIZES),
+    ];
+    for (kind, severities) in battery {
+        let mut result = KindResult {
+            kind,
+            max_survived: None,
+            trials: 0,
+            survived: 0,
+        };
+        let mut all_survived_so_far = true;
+
+        for &severity in severities {
+            let mut all_survived = true;
+            for _ in 0..TRIALS_PER_SEVERITY {
+                let damage = battery_damage(kind, severity, size, &mut rng);
+                let report = simulate_damage(matrix, &[damage], rng.next_u64())?;
+                result.trials += 1;
+                result.survived += report.success as usize;
+                all_survived &= report.success;
+                margin_sum += report.margin();
+                total_trials += 1;
+            }
+            all_survived_so_far &= all_survived;
+            if all_survived_so_far {
+                result.max_survived = Some(severity);
+            }
+        }
+        kinds.push(result);
+    }
+
+    Ok(RobustnessReport {
+        kinds,
+        score: margin_sum / total_trials as f64 * 100.0,
+    })
+}
+
+/// One randomly placed instance of `kind` at `severity`.
+fn battery_damage(kind: DamageKind, severity: f64, size: usize, rng: &mut Rng) -> Damage {
+    let modules = ((severity * size as f64).round() as usize).max(1);
+    match kind {
+        DamageKind::Flips => Damage::Flips { fraction: severity },
+        DamageKind::Blot => Damage::Blot {
+            x: rng.next_f64() * size as f64,
+            y: rng.next_f64() * size as f64,
+            radius: severity * size as f64,
+        },
+        DamageKind::Stripe => Damage::Stripe {
+            vertical: rng.below(2) == 1,
+            offset: rng.below(size - modules + 1),
+            width: modules,
+        },
+        DamageKind::MissingCorner => Damage::MissingCorner {
+            corner: [
+                Corner::TopLeft,
+                Corner::TopRight,
+                Corner::BottomLeft,
+                Corner::BottomRight,
+            ][rng.below(4)],
+            size: modules,
+        },
+    }
+}
@@ -0,0 +1,303 @@
+use crate::encoder::ErrorCorrectionLevel;
+use crate::error::QRError;
+use crate::error_correction::{correct_block, interleave_order, BlockLayout, Correction};
+use crate::input::ALPHANUMERIC_CHARS;
+use crate::matrix::{format_bit_positions, format_information, mask_applies, QRMatrix};
+use crate::InputMode;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+const LEVELS: [ErrorCorrectionLevel; 4] = [
+    ErrorCorrectionLevel::L,
+    ErrorCorrectionLevel::M,
+    ErrorCorrectionLevel::Q,
+    ErrorCorrectionLevel::H,
+];
+
+/// The format information can be recovered with up to three bit errors.
+const MAX_FORMAT_ERRORS: u32 = 3;
+
+/// Result of decoding a symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    pub version: u8,
+    pub ec_level: ErrorCorrectionLevel,
+    pub mask: u8,
+    /// Decoded payload bytes. Kanji segments are returned as Shift JIS.
+    pub content: Vec<u8>,
+    /// ECI assignment numbers in the order they appeared.
+    pub eci: Vec<u32>,
+    /// Repairs made in each block, in block order.
+    pub corrections: Vec<Correction>,
+}
+
+impl Decoded {
+    /// The content as text, replacing invalid UTF-8.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.content).into_owned()
+    }
+}
+
+/// Decodes a symbol from its modules, reading the level and mask from the
+/// format information rather than trusting the matrix fields.
+pub fn decode_matrix(matrix: &QRMatrix) -> Result<Decoded, QRError> {
+    decode_with_erasures(matrix, &[])
+}
+
+/// Decodes a symbol where the modules at `erased` positions `(x, y)` are
+/// known to be unreliable, e.g. because a logo covers them. Codewords
+/// touching those modules are treated as erasures, which costs half as much
+/// error correction as an unknown error.
+pub fn decode_with_erasures(
+    matrix: &QRMatrix,
+    erased: &[(usize, usize)],
+) -> Result<Decoded, QRError> {
+    let version = matrix.get_version();
+    let (ec_level, mask) = read_format(matrix)?;
+    let layout = BlockLayout::new(version, ec_level)?;
+    let (mut blocks, erasures) = read_blocks(matrix, &layout, mask, erased);
+
+    let mut data = Vec::with_capacity(layout.data_codewords());
+    let mut corrections = Vec::with_capacity(layout.num_blocks);
+    for (b, block) in blocks.iter_mut().enumerate() {
+        let correction =
+            correct_block(block, layout.ecc_per_block, &erasures[b]).map_err(|e| match e {
+                QRError::DecodingError(msg) => {
+                    QRError::DecodingError(format!("Block {}: {}", b, msg))
+                }
+                e => e,
+            })?;
+        corrections.push(correction);
+        data.extend_from_slice(&block[..layout.data_len(b)]);
+    }
+
+    let (content, eci) = parse_segments(&data, version)?;
+    Ok(Decoded {
+        version,
+        ec_level,
+        mask,
+        content,
+        eci,
+        corrections,
+    })
+}
+
+/// Number of codewords in each block touched by the `erased` modules.
+pub fn erasures_per_block(
+    matrix: &QRMatrix,
+    erased: &[(usize, usize)],
+) -> Result<Vec<usize>, QRError> {
+    let (ec_level, mask) = read_format(matrix)?;
+    let layout = BlockLayout::new(matrix.get_version(), ec_level)?;
+    let (_, erasures) = read_blocks(matrix, &layout, mask, erased);
+
+    Ok(erasures.iter().map(|e| e.len()).collect())
+}
+
+/// Unmasks the data area and splits the codewords back into their blocks,
+/// along with the in-block indices of codewords touching erased modules.
+fn read_blocks(
+    matrix: &QRMatrix,
+    layout: &BlockLayout,
+    mask: u8,
+    erased: &[(usize, usize)],
+) -> (Vec<Vec<u8>>, Vec<Vec<usize>>) {
+    let size = matrix.get_size();
+    let mut erased_modules = vec![false; size * size];
+    for &(x, y) in erased {
+        if x < size && y < size {
+            erased_modules[y * size + x] = true;
+        }
+    }
+
+    let total = layout.total_codewords();
+    let mut codewords = vec![0u8; total];
+    let mut erased_codewords = vec![false; total];
+    for (i, (x, y)) in matrix.data_positions().into_iter().enumerate() {
+        if i / 8 >= total {
+            break;
+        }
+        if matrix.is_dark(x, y) != mask_applies(mask, x, y) {
+            codewords[i / 8] |= 0x80 >> (i % 8);
+        }
+        erased_codewords[i / 8] |= erased_modules[y * size + x];
+    }
+
+    let mut blocks: Vec<Vec<u8>> = (0..layout.num_blocks)
+        .map(|b| Vec::with_capacity(layout.data_len(b) + layout.ecc_per_block))
+        .collect();
+    let mut erasures: Vec<Vec<usize>> = vec![Vec::new(); layout.num_blocks];
+    for (i, (block, index)) in interleave_order(layout).into_iter().enumerate() {
+        blocks[block].push(codewords[i]);
+        if erased_codewords[i] {
+            erasures[block].push(index);
+        }
+    }
+    (blocks, erasures)
+}
+
+/// Reads the error correction level and mask from whichever copy of the
+/// format information is closest to a valid code word.
+pub fn read_format(matrix: &QRMatrix) -> Result<(ErrorCorrectionLevel, u8), QRError> {
+    let size = matrix.get_size();
+    let mut copies = [0u32; 2];
+    for i in 0..15 {
+        let (a, b) = format_bit_positions(size, i);
+        copies[0] |= (matrix.is_dark(a.0, a.1) as u32) << i;
+        copies[1] |= (matrix.is_dark(b.0, b.1) as u32) << i;
+    }
+
+    let mut best = None;
+    let mut best_distance = u32::MAX;
+    for level in LEVELS {
+        for mask in 0..8 {
+            let expected = format_information(level, mask);
+            let distance = copies
+                .iter()
+                .map(|copy| (copy ^ expected).count_ones())
+                .min()
+                .unwrap_or(u32::MAX);
+            if distance < best_distance {
+                best_distance = distance;
+                best = Some((level, mask));
+            }
+        }
+    }
+
+    match best {
+        Some(format) if best_distance <= MAX_FORMAT_ERRORS => Ok(format),
+        _ => Err(QRError::DecodingError(
+            "Format information is unreadable".to_string(),
+        )),
+    }
+}
+
+/// Reads big-endian bit fields from the data codewords.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn remaining(&self) -> usize {
+        self.data.len() * 8 - self.position
+    }
+
+    fn read(&mut self, bits: usize) -> Result<u32, QRError> {
+        if bits > self.remaining() {
+            return Err(QRError::DecodingError(
+                "Segment runs past the end of the data".to_string(),
+            ));
+        }
+
+        let mut value = 0;
+        for _ in 0..bits {
+            let bit = (self.data[self.position / 8] >> (7 - self.position % 8)) & 1;
+            value = (value << 1) | bit as u32;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+}
+
+/// Decodes the segments of the data bit stream up to the terminator.
+fn parse_segments(data: &[u8], version: u8) -> Result<(Vec<u8>, Vec<u32>), QRError> {
+    let mut reader = BitReader { data, position: 0 };
+    let mut content = Vec::new();
+    let mut eci = Vec::new();
+
+    // A missing or shortened terminator is allowed when the data is full.
+    while reader.remaining() >= 4 {
+        let mode = reader.read(4)?;
+        match mode {
+            0b0000 => break,
+            0b0001 => {
+                let count = reader.read(InputMode::Numeric.character_count_bits(version))?;
+                let mut left = count as usize;
+                while left > 0 {
+                    let digits = left.min(3);
+                    let value = reader.read([0, 4, 7, 10][digits])?;
+                    if value >= 10u32.pow(digits as u32) {
+                        return Err(QRError::DecodingError(format!(
+                            "Invalid numeric group {}",
+                            value
+                        )));
+                    }
+                    content.extend(format!("{:0width$}", value, width = digits).bytes());
+                    left -= digits;
+                }
+            }
+            0b0010 => {
+                let count = reader.read(InputMode::Alphanumeric.character_count_bits(version))?;
+                let mut left = count as usize;
+                while left > 0 {
+                    let indices = if left >= 2 {
+                        let value = reader.read(11)?;
+                        vec![value / 45, value % 45]
+                    } else {
+                        vec![reader.read(6)?]
+                    };
+                    for &index in &indices {
+                        let c = ALPHANUMERIC_CHARS
+                            .as_bytes()
+                            .get(index as usize)
+                            .ok_or_else(|| {
+                                QRError::DecodingError(format!(
+                                    "Invalid alphanumeric value {}",
+                                    index
+                                ))
+                            })?;
+                        content.push(*c);
+                    }
+                    left -= indices.len();
+                }
+            }
+            0b0100 => {
+                let count = reader.read(InputMode::Byte.character_count_bits(version))?;
+                for _ in 0..count {
+                    content.push(reader.read(8)? as u8);
+                }
+            }
+            0b1000 => {
+                let count_bits = match version {
+                    1..=9 => 8,
+                    10..=26 => 10,
+                    _ => 12,
+                };
+                let count = reader.read(count_bits)?;
+                for _ in 0..count {
+                    let value = reader.read(13)?;
+                    let packed = (value / 0xC0) << 8 | (value % 0xC0);
+                    let sjis = if packed < 0x1F00 {
+                        packed + 0x8140
+                    } else {
+                        packed + 0xC140
+                    };
+                    content.extend_from_slice(&[(sjis >> 8) as u8, sjis as u8]);
+                }
+            }
+            0b0111 => {
+                let first = reader.read(8)?;
+                let assignment = if first & 0x80 == 0 {
+                    first
+                } else if first & 0xC0 == 0x80 {
+                    (first & 0x3F) << 8 | reader.read(8)?
+                } else if first & 0xE0 == 0xC0 {
+                    (first & 0x1F) << 16 | reader.read(16)?
+                } else {
+                    return Err(QRError::DecodingError("Invalid ECI designator".to_string()));
+                };
+                eci.push(assignment);
+            }
+            _ => {
+                return Err(QRError::DecodingError(format!(
+                    "Unsupported mode indicator {:04b}",
+                    mode
+                )))
+            }
+        }
+    }
+    Ok((content, eci))
+}
@@ -0,0 +1,688 @@
+use crate::decoder::{decode_matrix, read_format, Decoded};
+use crate::encoder::ErrorCorrectionLevel;
+use crate::error::QRError;
+use crate::matrix::QRMatrix;
+
+/// Side of the square blocks the hybrid binariser computes thresholds for.
+const BLOCK_SIZE: usize = 8;
+
+/// Images smaller than this many blocks on a side use one global threshold.
+const MIN_BLOCKS: usize = 5;
+
+/// Blocks whose contrast is below this are treated as flat background.
+const MIN_BLOCK_CONTRAST: u8 = 24;
+
+/// How many of the best finder triples are tried before giving up.
+const MAX_TRIPLES: usize = 6;
+
+/// Matching samples out of 25 needed to accept an alignment pattern.
+const MIN_ALIGNMENT_SCORE: usize = 22;
+
+/// An 8-bit grayscale image, 0 black and 255 white.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrayImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl GrayImage {
+    /// `pixels` holds one luminance value per pixel in row-major order.
+    pub fn new(width: usize, height: usize, pixels: Vec<u8>) -> Result<Self, QRError> {
+        if width == 0 || height == 0 || pixels.len() != width * height {
+            return Err(QRError::InvalidLength(format!(
+                "A {}x{} image needs {} pixels, got {}",
+                width,
+                height,
+                width * height,
+                pixels.len()
+            )));
+        }
+        Ok(GrayImage {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y * self.width + x]
+    }
+}
+
+/// Black and white version of an image.
+struct Binary {
+    width: usize,
+    height: usize,
+    dark: Vec<bool>,
+}
+
+impl Binary {
+    fn contains(&self, x: i64, y: i64) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height
+    }
+
+    /// Pixels outside the image count as light.
+    fn is_dark(&self, x: i64, y: i64) -> bool {
+        self.contains(x, y) && self.dark[y as usize * self.width + x as usize]
+    }
+
+    fn is_dark_at(&self, point: Point) -> bool {
+        self.is_dark(point.x.floor() as i64, point.y.floor() as i64)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+impl Point {
+    fn distance(self, other: Point) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+/// A finder pattern centre and its estimated module size in pixels.
+#[derive(Debug, Clone, Copy)]
+struct FinderPattern {
+    centre: Point,
+    module_size: f64,
+    /// Number of scan lines that found this pattern.
+    count: usize,
+}
+
+/// Finds and decodes a QR code in a grayscale image.
+///
+/// The image is binarised with local thresholds, finder patterns are located
+/// by their 1:1:3:1:1 run ratio, and the module grid is sampled through a
+/// perspective transform anchored on the finders and, from version 2, the
+/// bottom-right alignment pattern. Mirrored symbols are read as well.
+pub fn decode_image(image: &GrayImage) -> Result<Decoded, QRError> {
+    let binary = binarize(image);
+    let finders = find_finder_patterns(&binary);
+    let triples = finder_triples(&finders);
+    if triples.is_empty() {
+        return Err(QRError::DecodingError(
+            "No finder patterns found".to_string(),
+        ));
+    }
+
+    let mut last_error = None;
+    for [top_left, top_right, bottom_left] in triples {
+        for dimension in candidate_dimensions(top_left, top_right, bottom_left) {
+            let modules = sample_grid(&binary, top_left, top_right, bottom_left, dimension);
+            for modules in [modules.clone(), transpose(&modules, dimension)] {
+                match decode_modules(dimension, modules) {
+                    Ok(decoded) => return Ok(decoded),
+                    Err(e) => last_error = Some(e),
+                }
+            }
+        }
+    }
+    Err(last_error.unwrap_or_else(|| QRError::DecodingError("No symbol could be read".to_string())))
+}
+
+fn decode_modules(dimension: usize, modules: Vec<bool>) -> Result<Decoded, QRError> {
+    let version = ((dimension - 17) / 4) as u8;
+    let probe = QRMatrix::from_modules(version, ErrorCorrectionLevel::L, None, modules.clone())?;
+    let (ec_level, mask) = read_format(&probe)?;
+    decode_matrix(&QRMatrix::from_modules(
+        version,
+        ec_level,
+        Some(mask),
+        modules,
+    )?)
+}
+
+fn transpose(modules: &[bool], dimension: usize) -> Vec<bool> {
+    (0..dimension * dimension)
+        .map(|i| modules[(i % dimension) * dimension + i / dimension])
+        .collect()
+}
+
+/// Hybrid binarisation: each pixel is compared with the mean of the 5x5
+/// blocks around it, which copes with uneven lighting. Small images fall
+/// back to a single Otsu threshold.
+fn binarize(image: &GrayImage) -> Binary {
+    let (width, height) = (image.width, image.height);
+    if width < BLOCK_SIZE * MIN_BLOCKS || height < BLOCK_SIZE * MIN_BLOCKS {
+        let threshold = otsu_threshold(&image.pixels);
+        return Binary {
+            width,
+            height,
+            dark: image.pixels.iter().map(|&p| p <= threshold).collect(),
+        };
+    }
+
+    let blocks_x = width.div_ceil(BLOCK_SIZE);
+    let blocks_y = height.div_ceil(BLOCK_SIZE);
+    // The last row and column of blocks are shifted to stay inside the image.
+    let block_origin = |block: usize, extent: usize| (block * BLOCK_SIZE).min(extent - BLOCK_SIZE);
+
+    let mut averages = vec![0u32; blocks_x * blocks_y];
+    for by in 0..blocks_y {
+        for bx in 0..blocks_x {
+            let (left, top) = (block_origin(bx, width), block_origin(by, height));
+            let (mut sum, mut min, mut max) = (0u32, u8::MAX, u8::MIN);
+            for y in top..top + BLOCK_SIZE {
+                for x in left..left + BLOCK_SIZE {
+                    let p = image.get(x, y);
+                    sum += p as u32;
+                    min = min.min(p);
+                    max = max.max(p);
+                }
+            }
+
+            let mut average = sum / (BLOCK_SIZE * BLOCK_SIZE) as u32;
+            if max - min <= MIN_BLOCK_CONTRAST {
+                // A flat block is assumed to be background unless its
+                // neighbours say it lies inside a dark area.
+                average = min as u32 / 2;
+                if bx > 0 && by > 0 {
+                    let neighbours = (averages[(by - 1) * blocks_x + bx]
+                        + 2 * averages[by * blocks_x + bx - 1]
+                        + averages[(by - 1) * blocks_x + bx - 1])
+                        / 4;
+                    if (min as u32) < neighbours {
+                        average = neighbours;
+                    }
+                }
+            }
+            averages[by * blocks_x + bx] = average;
+        }
+    }
+
+    let mut dark = vec![false; width * height];
+    for by in 0..blocks_y {
+        for bx in 0..blocks_x {
+            let (x0, x1) = (bx.saturating_sub(2), (bx + 2).min(blocks_x - 1));
+            let (y0, y1) = (by.saturating_sub(2), (by + 2).min(blocks_y - 1));
+            let mut sum = 0;
+            for ny in y0..=y1 {
+                for nx in x0..=x1 {
+                    sum += averages[ny * blocks_x + nx];
+                }
+            }
+            let threshold = sum / ((x1 - x0 + 1) * (y1 - y0 + 1)) as u32;
+
+            let (left, top) = (block_origin(bx, width), block_origin(by, height));
+            for y in top..top + BLOCK_SIZE {
+                for x in left..left + BLOCK_SIZE {
+                    dark[y * width + x] = image.get(x, y) as u32 <= threshold;
+                }
+            }
+        }
+    }
+    Binary {
+        width,
+        height,
+        dark,
+    }
+}
+
+/// Threshold that best separates the histogram into two classes.
+fn otsu_threshold(pixels: &[u8]) -> u8 {
+    let mut histogram = [0u64; 256];
+    for &p in pixels {
+        histogram[p as usize] += 1;
+    }
+
+    let total = pixels.len() as f64;
+    let sum: f64 = histogram
+        .iter()
+        .enumerate()
+        .map(|(v, &n)| v as f64 * n as f64)
+        .sum();
+    let (mut below, mut below_sum) = (0.0, 0.0);
+    let (mut best, mut best_variance) = (127u8, -1.0);
+    for (value, &n) in histogram.iter().enumerate() {
+        below += n as f64;
+        below_sum += value as f64 * n as f64;
+        let above = total - below;
+        if below == 0.0 || above == 0.0 {
+            continue;
+        }
+        let difference = below_sum / below - (sum - below_sum) / above;
+        let variance = below * above * difference * difference;
+        if variance > best_variance {
+            best_variance = variance;
+            best = value as u8;
+        }
+    }
+    best
+}
+
+/// Whether five run lengths are close enough to 1:1:3:1:1.
+fn is_finder_ratio(counts: &[usize; 5]) -> bool {
+    let total: usize = counts.iter().sum();
+    if total < 7 || counts.contains(&0) {
+        return false;
+    }
+    let module = total as f64 / 7.0;
+    let tolerance = module / 2.0;
+    counts
+        .iter()
+        .zip([1.0, 1.0, 3.0, 1.0, 1.0])
+        .all(|(&count, expected)| (count as f64 - module * expected).abs() < tolerance * expected)
+}
+
+/// Scans every row for dark-light-dark-light-dark runs in finder proportions,
+/// confirms each hit across the column and the row again, and merges hits
+/// belonging to the same pattern.
+fn find_finder_patterns(binary: &Binary) -> Vec<FinderPattern> {
+    let mut patterns: Vec<FinderPattern> = Vec::new();
+
+    for y in 0..binary.height {
+        let mut runs: Vec<(bool, usize, usize)> = Vec::new();
+        for x in 0..binary.width {
+            let dark = binary.is_dark(x as i64, y as i64);
+            match runs.last_mut() {
+                Some((colour, _, length)) if *colour == dark => *length += 1,
+                _ => runs.push((dark, x, 1)),
+            }
+        }
+
+        for window in runs.windows(5) {
+            if !window[0].0 {
+                continue;
+            }
+            let counts = [
+                window[0].2,
+                window[1].2,
+                window[2].2,
+                window[3].2,
+                window[4].2,
+            ];
+            if !is_finder_ratio(&counts) {
+                continue;
+            }
+
+            let row_total: usize = counts.iter().sum();
+            let centre_x = window[2].1 as f64 + window[2].2 as f64 / 2.0;
+            let Some((centre_y, column_total)) =
+                cross_check(binary, centre_x as i64, y as i64, (0, 1), row_total)
+            else {
+                continue;
+            };
+            let Some((centre_x, row_total)) =
+                cross_check(binary, centre_x as i64, centre_y as i64, (1, 0), row_total)
+            else {
+                continue;
+            };
+
+            let centre = Point {
+                x: centre_x,
+                y: centre_y,
+            };
+            let module_size = (row_total + column_total) as f64 / 14.0;
+            match patterns.iter_mut().find(|p| {
+                p.centre.distance(centre) <= p.module_size * 2.0
+                    && (p.module_size - module_size).abs() <= p.module_size.max(module_size) / 2.0
+            }) {
+                Some(pattern) => {
+                    let n = pattern.count as f64;
+                    pattern.centre = Point {
+                        x: (pattern.centre.x * n + centre.x) / (n + 1.0),
+                        y: (pattern.centre.y * n + centre.y) / (n + 1.0),
+                    };
+                    pattern.module_size = (pattern.module_size * n + module_size) / (n + 1.0);
+                    pattern.count += 1;
+                }
+                None => patterns.push(FinderPattern {
+                    centre,
+                    module_size,
+                    count: 1,
+                }),
+            }
+        }
+    }
+
+    patterns.sort_by_key(|p| std::cmp::Reverse(p.count));
+    patterns
+}
+
+/// Walks from a dark pixel in both directions along `step`, expecting the
+/// finder's runs. Returns the centre of the middle run along that axis and
+/// the total run length, if the proportions hold and the length is similar
+/// to `expected_total`.
+fn cross_check(
+    binary: &Binary,
+    x: i64,
+    y: i64,
+    step: (i64, i64),
+    expected_total: usize,
+) -> Option<(f64, usize)> {
+    if !binary.is_dark(x, y) {
+        return None;
+    }
+    let at = |i: i64| (x + step.0 * i, y + step.1 * i);
+    let limit = expected_total as i64 * 2;
+
+    let mut counts = [0usize; 5];
+    // Backwards: the middle run (including the start), then light, then dark.
+    let mut i = 0;
+    for (slot, dark) in [(2, true), (1, false), (0, true)] {
+        while i > -limit && binary.contains(at(i).0, at(i).1) {
+            let (px, py) = at(i);
+            if binary.is_dark(px, py) != dark {
+                break;
+            }
+            counts[slot] += 1;
+            i -= 1;
+        }
+    }
+    let mut j = 1;
+    for (slot, dark) in [(2, true), (3, false), (4, true)] {
+        while j < limit && binary.contains(at(j).0, at(j).1) {
+            let (px, py) = at(j);
+            if binary.is_dark(px, py) != dark {
+                break;
+            }
+            counts[slot] += 1;
+            j += 1;
+        }
+    }
+
+    let total: usize = counts.iter().sum();
+    let similar = total.abs_diff(expected_total) * 2 < expected_total;
+    if !is_finder_ratio(&counts) || !similar {
+        return None;
+    }
+    let centre_end = j - (counts[4] + counts[3]) as i64;
+    let centre = centre_end as f64 - counts[2] as f64 / 2.0;
+    let origin = if step.0 != 0 { x } else { y };
+    Some((origin as f64 + centre, total))
+}
+
+/// Combinations of three finder patterns ordered top-left, top-right,
+/// bottom-left, best right isosceles triangle first.
+fn finder_triples(patterns: &[FinderPattern]) -> Vec<[FinderPattern; 3]> {
+    let candidates = &patterns[..patterns.len().min(8)];
+    let mut triples = Vec::new();
+
+    for i in 0..candidates.len() {
+        for j in i + 1..candidates.len() {
+            for k in j + 1..candidates.len() {
+                let group = [candidates[i], candidates[j], candidates[k]];
+                let sizes = group.map(|p| p.module_size);
+                let (min, max) = (
+                    sizes.iter().cloned().fold(f64::MAX, f64::min),
+                    sizes.iter().cloned().fold(0.0, f64::max),
+                );
+                if max > min * 2.0 {
+                    continue;
+                }
+
+                let ordered = order_finders(group);
+                let [top_left, top_right, bottom_left] = ordered;
+                let a = top_left.centre.distance(top_right.centre);
+                let b = top_left.centre.distance(bottom_left.centre);
+                let c = top_right.centre.distance(bottom_left.centre);
+                if a.min(b) < max * 7.0 {
+                    continue;
+                }
+                let score = (a - b).abs() / a.max(b) + (c - a.hypot(b)).abs() / c;
+                triples.push((score, ordered));
+            }
+        }
+    }
+
+    triples.sort_by(|a, b| a.0.total_cmp(&b.0));
+    triples
+        .into_iter()
+        .take(MAX_TRIPLES)
+        .map(|(_, triple)| triple)
+        .collect()
+}
+
+/// The pattern opposite the longest side is top-left; the other two are
+/// told apart by the winding direction.
+fn order_finders(group: [FinderPattern; 3]) -> [FinderPattern; 3] {
+    let side = |a: usize, b: usize| group[a].centre.distance(group[b].centre);
+    let sides = [side(1, 2), side(0, 2), side(0, 1)];
+    let corner = (0..3)
+        .max_by(|&a, &b| sides[a].total_cmp(&sides[b]))
+        .unwrap_or(0);
+
+    let top_left = group[corner];
+    let mut top_right = group[(corner + 1) % 3];
+    let mut bottom_left = group[(corner + 2) % 3];
+    let cross = (top_right.centre.x - top_left.centre.x)
+        * (bottom_left.centre.y - top_left.centre.y)
+        - (top_right.centre.y - top_left.centre.y) * (bottom_left.centre.x - top_left.centre.x);
+    if cross < 0.0 {
+        std::mem::swap(&mut top_right, &mut bottom_left);
+    }
+    [top_left, top_right, bottom_left]
+}
+
+/// Symbol sizes to try: the estimate from the finder spacing rounded to a
+/// valid size, then its neighbours.
+fn candidate_dimensions(
+    top_left: FinderPattern,
+    top_right: FinderPattern,
+    bottom_left: FinderPattern,
+) -> Vec<usize> {
+    let module_size =
+        (top_left.module_size + top_right.module_size + bottom_left.module_size) / 3.0;
+    let across = top_left.centre.distance(top_right.centre) / module_size;
+    let down = top_left.centre.distance(bottom_left.centre) / module_size;
+    let estimate = (across + down) / 2.0 + 7.0;
+    let version = ((estimate - 17.0) / 4.0).round().clamp(1.0, 40.0) as usize;
+
+    [version, version + 1, version.saturating_sub(1)]
+        .into_iter()
+        .filter(|v| (1..=40).contains(v))
+        .map(|v| v * 4 + 17)
+        .collect()
+}
+
+/// A projective mapping applied as `[x y 1] * m`.
+#[derive(Debug, Clone, Copy)]
+struct Perspective {
+    m: [[f64; 3]; 3],
+}
+
+impl Perspective {
+    /// Maps the unit square's corners (0,0), (1,0), (1,1), (0,1) onto `quad`.
+    fn square_to_quad(quad: [Point; 4]) -> Self {
+        let [p0, p1, p2, p3] = quad;
+        let dx3 = p0.x - p1.x + p2.x - p3.x;
+        let dy3 = p0.y - p1.y + p2.y - p3.y;
+        let (dx1, dx2) = (p1.x - p2.x, p3.x - p2.x);
+        let (dy1, dy2) = (p1.y - p2.y, p3.y - p2.y);
+        let denominator = dx1 * dy2 - dx2 * dy1;
+        let a13 = (dx3 * dy2 - dx2 * dy3) / denominator;
+        let a23 = (dx1 * dy3 - dx3 * dy1) / denominator;
+
+        Perspective {
+            m: [
+                [p1.x - p0.x + a13 * p1.x, p1.y - p0.y + a13 * p1.y, a13],
+                [p3.x - p0.x + a23 * p3.x, p3.y - p0.y + a23 * p3.y, a23],
+                [p0.x, p0.y, 1.0],
+            ],
+        }
+    }
+
+    /// Maps `from` onto `to`, corner by corner.
+    fn quad_to_quad(from: [Point; 4], to: [Point; 4]) -> Self {
+        Perspective::square_to_quad(from)
+            .adjugate()
+            .then(Perspective::square_to_quad(to))
+    }
+
+    /// Inverse up to scale, which is all a projective mapping needs.
+    fn adjugate(self) -> Self {
+        let m = self.m;
+        let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| {
+            m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+        };
+        Perspective {
+            m: [
+                [
+                    cofactor(1, 2, 1, 2),
+                    -cofactor(0, 2, 1, 2),
+                    cofactor(0, 1, 1, 2),
+                ],
+                [
+                    -cofactor(1, 2, 0, 2),
+                    cofactor(0, 2, 0, 2),
+                    -cofactor(0, 1, 0, 2),
+                ],
+                [
+                    cofactor(1, 2, 0, 1),
+                    -cofactor(0, 2, 0, 1),
+                    cofactor(0, 1, 0, 1),
+                ],
+            ],
+        }
+    }
+
+    /// Applies `self`, then `next`.
+    fn then(self, next: Perspective) -> Self {
+        let mut m = [[0.0; 3]; 3];
+        for (r, row) in m.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                *value = (0..3).map(|k| self.m[r][k] * next.m[k][c]).sum();
+            }
+        }
+        Perspective { m }
+    }
+
+    fn apply(&self, point: Point) -> Point {
+        let m = &self.m;
+        let w = point.x * m[0][2] + point.y * m[1][2] + m[2][2];
+        Point {
+            x: (point.x * m[0][0] + point.y * m[1][0] + m[2][0]) / w,
+            y: (point.x * m[0][1] + point.y * m[1][1] + m[2][1]) / w,
+        }
+    }
+}
+
+/// Reads the module grid of a symbol with `dimension` modules per side.
+fn sample_grid(
+    binary: &Binary,
+    top_left: FinderPattern,
+    top_right: FinderPattern,
+    bottom_left: FinderPattern,
+    dimension: usize,
+) -> Vec<bool> {
+    let (tl, tr, bl) = (top_left.centre, top_right.centre, bottom_left.centre);
+    let far = dimension as f64 - 3.5;
+
+    // Without an alignment pattern the fourth corner completes the
+    // parallelogram, which ignores perspective but suits version 1.
+    let (corner_module, corner_image) = match find_alignment(binary, tl, tr, bl, dimension) {
+        Some(alignment) => (far - 3.0, alignment),
+        None => (
+            far,
+            Point {
+                x: tr.x + bl.x - tl.x,
+                y: tr.y + bl.y - tl.y,
+            },
+        ),
+    };
+    let transform = Perspective::quad_to_quad(
+        [
+            Point { x: 3.5, y: 3.5 },
+            Point { x: far, y: 3.5 },
+            Point {
+                x: corner_module,
+                y: corner_module,
+            },
+            Point { x: 3.5, y: far },
+        ],
+        [tl, tr, corner_image, bl],
+    );
+
+    (0..dimension * dimension)
+        .map(|i| {
+            let module = Point {
+                x: (i % dimension) as f64 + 0.5,
+                y: (i / dimension) as f64 + 0.5,
+            };
+            binary.is_dark_at(transform.apply(module))
+        })
+        .collect()
+}
+
+/// Locates the bottom-right alignment pattern near where the finders place
+/// it, by matching its 5x5 template on the module grid the finders imply.
+fn find_alignment(
+    binary: &Binary,
+    tl: Point,
+    tr: Point,
+    bl: Point,
+    dimension: usize,
+) -> Option<Point> {
+    if dimension < 25 {
+        return None;
+    }
+    let span = dimension as f64 - 7.0;
+    let across = Point {
+        x: (tr.x - tl.x) / span,
+        y: (tr.y - tl.y) / span,
+    };
+    let down = Point {
+        x: (bl.x - tl.x) / span,
+        y: (bl.y - tl.y) / span,
+    };
+    let estimate = Point {
+        x: tl.x + (across.x + down.x) * (span - 3.0),
+        y: tl.y + (across.y + down.y) * (span - 3.0),
+    };
+    let module = across.x.hypot(across.y).max(down.x.hypot(down.y));
+    let radius = (module * 5.0).ceil() as i64;
+
+    let score_at = |x: f64, y: f64| {
+        let mut matches = 0;
+        for j in -2i32..=2 {
+            for i in -2i32..=2 {
+                let point = Point {
+                    x: x + across.x * i as f64 + down.x * j as f64,
+                    y: y + across.y * i as f64 + down.y * j as f64,
+                };
+                let expected = i.abs().max(j.abs()) != 1;
+                if binary.is_dark_at(point) == expected {
+                    matches += 1;
+                }
+            }
+        }
+        matches
+    };
+
+    // The centre is the mean of every position with the best score.
+    let (mut best, mut sum, mut hits) = (0, Point { x: 0.0, y: 0.0 }, 0.0);
+    for dy in -radius..=radius {
+        for dx in -radius..=radius {
+            let (x, y) = (
+                estimate.x.floor() + dx as f64 + 0.5,
+                estimate.y.floor() + dy as f64 + 0.5,
+            );
+            let score = score_at(x, y);
+            if score > best {
+                (best, sum, hits) = (score, Point { x: 0.0, y: 0.0 }, 0.0);
+            }
+            if score == best {
+                sum.x += x;
+                sum.y += y;
+                hits += 1.0;
+            }
+        }
+    }
+
+    (best >= MIN_ALIGNMENT_SCORE).then(|| Point {
+        x: sum.x / hits,
+        y: sum.y / hits,
+    })
+}
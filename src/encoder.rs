@@ -1,9 +1,22 @@
-use crate::versions::VERSION_CAPACITIES;
-use crate::{error::QRError, InputMode, QRInput};
-use std::fmt::format;
-use std::thread::current;
-
-#[derive(Debug, Clone, Copy)]
+#[cfg(feature = "alloc")]
+use crate::{
+    error::QRError,
+    error_correction::add_error_correction,
+    matrix::QRMatrix,
+    segment::{write_pad_codewords, write_terminator, BitBuffer, BitWriter, Segment},
+    versions::{DATA_CODEWORDS, VERSION_CAPACITIES},
+    InputMode, QRInput,
+};
+#[cfg(feature = "alloc")]
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+#[cfg(feature = "alloc")]
+use core::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ErrorCorrectionLevel {
     L,
     M,
@@ -11,63 +24,53 @@ pub enum ErrorCorrectionLevel {
     H,
 }
 
+impl ErrorCorrectionLevel {
+    /// The two bits identifying the level inside the format information.
+    pub fn format_bits(self) -> u32 {
+        match self {
+            ErrorCorrectionLevel::L => 0b01,
+            ErrorCorrectionLevel::M => 0b00,
+            ErrorCorrectionLevel::Q => 0b11,
+            ErrorCorrectionLevel::H => 0b10,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl FromStr for ErrorCorrectionLevel {
+    type Err = QRError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "L" => Ok(ErrorCorrectionLevel::L),
+            "M" => Ok(ErrorCorrectionLevel::M),
+            "Q" => Ok(ErrorCorrectionLevel::Q),
+            "H" => Ok(ErrorCorrectionLevel::H),
+            _ => Err(QRError::InvalidInput(format!(
+                "Unknown error correction level '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
 #[derive(Debug, Clone)]
 pub struct QRData {
     input: QRInput,
     ec_level: ErrorCorrectionLevel,
     version: Option<u8>,
+    fixed_version: Option<u8>,
 }
 
-const DATA_CODEWORDS: [[u16; 4]; 40] = [
-    // [L,    M,    Q,    H   ]
-    [19, 16, 13, 9],
-    [34, 28, 22, 16],
-    [55, 44, 34, 26],
-    [80, 64, 48, 36],
-    [108, 86, 62, 46],
-    [136, 108, 76, 60],
-    [156, 124, 88, 66],
-    [194, 154, 110, 86],
-    [232, 182, 132, 100],
-    [274, 216, 154, 122],
-    [324, 254, 180, 140],
-    [370, 290, 206, 158],
-    [428, 334, 244, 180],
-    [461, 365, 261, 197],
-    [523, 415, 295, 223],
-    [589, 453, 325, 253],
-    [647, 507, 367, 283],
-    [721, 563, 397, 313],
-    [795, 627, 445, 341],
-    [861, 669, 485, 385],
-    [932, 714, 512, 406],
-    [1006, 782, 568, 442],
-    [1094, 860, 614, 464],
-    [1174, 914, 664, 514],
-    [1276, 1000, 718, 538],
-    [1370, 1062, 754, 596],
-    [1468, 1128, 808, 628],
-    [1531, 1193, 871, 661],
-    [1631, 1267, 911, 701],
-    [1735, 1373, 985, 745],
-    [1843, 1455, 1033, 793],
-    [1955, 1541, 1115, 845],
-    [2071, 1631, 1171, 901],
-    [2191, 1725, 1231, 961],
-    [2306, 1812, 1286, 986],
-    [2434, 1914, 1354, 1054],
-    [2566, 1992, 1426, 1096],
-    [2702, 2102, 1502, 1142],
-    [2812, 2216, 1582, 1222],
-    [2956, 2334, 1666, 1276],
-];
-
+#[cfg(feature = "alloc")]
 impl QRData {
     pub fn new() -> Self {
         QRData {
             input: QRInput::new(),
             ec_level: ErrorCorrectionLevel::M,
             version: None,
+            fixed_version: None,
         }
     }
 
@@ -82,6 +85,13 @@ impl QRData {
         Ok(mode)
     }
 
+    pub fn set_mode(&mut self, mode: InputMode) -> Result<(), QRError> {
+        self.input.set_mode(mode)?;
+
+        self.determine_version()?;
+        Ok(())
+    }
+
     pub fn get_ec_level(&self) -> ErrorCorrectionLevel {
         self.ec_level
     }
@@ -116,21 +126,50 @@ impl QRData {
         self.version
     }
 
-    pub fn determine_version(&mut self) -> Result<Option<u8>, QRError> {
+    /// Pins the symbol to a specific version instead of the smallest one that
+    /// fits. `None` restores automatic selection.
+    pub fn set_version(&mut self, version: Option<u8>) -> Result<Option<u8>, QRError> {
+        if let Some(v) = version {
+            if !(1..=40).contains(&v) {
+                return Err(QRError::InvalidVersion(format!(
+                    "Version {} is outside 1-40",
+                    v
+                )));
+            }
+        }
+
+        self.fixed_version = version;
+        self.determine_version()
+    }
+
+    fn fits_version(&self, version: u8) -> bool {
         let mode = self.input.get_mode();
         let length = self.input.get_content().len();
+        let capacity =
+            &VERSION_CAPACITIES[(version - 1) as usize].capacity_by_ec[self.ec_level as usize];
 
-        for (version_index, version_info) in VERSION_CAPACITIES.iter().enumerate() {
-            let capacity = &version_info.capacity_by_ec[self.ec_level as usize];
+        match mode {
+            InputMode::Numeric => length <= capacity.numeric,
+            InputMode::Alphanumeric => length <= capacity.alphanumeric,
+            InputMode::Byte => length <= capacity.byte,
+        }
+    }
 
-            let fits = match mode {
-                InputMode::Numeric => length <= capacity.numeric,
-                InputMode::Alphanumeric => length <= capacity.alphanumeric,
-                InputMode::Byte => length <= capacity.byte,
-            };
+    pub fn determine_version(&mut self) -> Result<Option<u8>, QRError> {
+        if let Some(version) = self.fixed_version {
+            if self.input.get_content().is_empty() || self.fits_version(version) {
+                self.version = Some(version);
+                return Ok(self.version);
+            }
+            return Err(QRError::InvalidLength(format!(
+                "Content does not fit into version {} at error correction level {:?}",
+                version, self.ec_level
+            )));
+        }
 
-            if fits {
-                self.version = Some((version_index + 1) as u8);
+        for version in 1..=VERSION_CAPACITIES.len() as u8 {
+            if self.fits_version(version) {
+                self.version = Some(version);
                 return Ok(self.version);
             }
         }
@@ -144,132 +183,26 @@ impl QRData {
         self.input.calculate_character_count_indicator(version)
     }
 
-    fn numeric_encoding(&self) -> Vec<String> {
-        let input = self.input.get_content();
-        let chars: Vec<char> = input.chars().collect();
-
-        let chunks: Vec<Vec<char>> = chars.chunks(3).map(|chunk| chunk.to_vec()).collect();
-        let mut converted_chunks: Vec<String> = vec![];
-
-        for chunk in chunks {
-            match chunk.len() {
-                3 => {
-                    let number = chunk.iter().collect::<String>().parse::<u16>().unwrap();
-                    let binary = format!("{:010b}", number);
-                    converted_chunks.push(binary);
-                }
-                2 => {
-                    let number = chunk.iter().collect::<String>().parse::<u16>().unwrap();
-                    let binary = format!("{:07b}", number);
-                    converted_chunks.push(binary);
-                }
-                1 => {
-                    let number = chunk.iter().collect::<String>().parse::<u16>().unwrap();
-                    let binary = format!("{:04b}", number);
-                    converted_chunks.push(binary);
-                }
-                _ => {}
-            }
-        }
-        converted_chunks
-    }
-
-    fn get_alphanumeric_value(c: char) -> u8 {
-        match c {
-            '0'..='9' => c as u8 - b'0',        // 0-9 map to 0-9
-            'A'..='Z' => (c as u8 - b'A') + 10, // A=10, B=11, etc...
-            ' ' => 36,
-            '$' => 37,
-            '%' => 38,
-            '*' => 39,
-            '+' => 40,
-            '-' => 41,
-            '.' => 42,
-            '/' => 43,
-            ':' => 44,
-            _ => panic!("Invalid alphanumeric character"),
-        }
-    }
-
-    fn alphanumeric_encoding(&self) -> Vec<String> {
-        let input = self.input.get_content();
-        let chars: Vec<char> = input.chars().collect();
-
-        let chunks: Vec<Vec<char>> = chars.chunks(2).map(|chunk| chunk.to_vec()).collect();
-
-        let mut converted_chunks: Vec<String> = vec![];
-        for chunk in chunks {
-            match chunk.len() {
-                2 => {
-                    let first = Self::get_alphanumeric_value(chunk[0]) as u16;
-                    let second = Self::get_alphanumeric_value(chunk[1]) as u16;
-                    let number = (first * 45) + second;
-                    let binary = format!("{:011b}", number);
-                    converted_chunks.push(binary);
-                }
-                1 => {
-                    let value = Self::get_alphanumeric_value(chunk[0]) as u16;
-                    let binary = format!("{:06b}", value);
-                    converted_chunks.push(binary);
-                }
-                _ => {}
-            }
-        }
-        converted_chunks
-    }
-
-    fn byte_encoding(&self) -> Vec<String> {
-        let input = self.input.get_content();
-        let mut converted_chunks: Vec<&u8> = vec![];
-        let mut result: Vec<String> = vec![];
-
-        for byte in input.as_bytes() {
-            converted_chunks.push(byte);
-        }
-
-        for n in converted_chunks {
-            let binary = format!("{:08b}", n);
-            result.push(binary);
+    /// The content as the single segment it is encoded in.
+    fn segment(&self) -> Segment<'_> {
+        let content = self.input.get_content();
+        match self.input.get_mode() {
+            InputMode::Numeric => Segment::Numeric(content),
+            InputMode::Alphanumeric => Segment::Alphanumeric(content),
+            InputMode::Byte => Segment::Bytes(content.as_bytes()),
         }
-        result
     }
 
+    /// The mode indicator, the character count indicator and the data bits
+    /// as a string of `0` and `1`.
     pub fn get_data(&mut self) -> (u8, u16, String) {
         let indicators = self.input.get_indicator(self.version);
-        let encoded_data = self.encode().join("");
-
-        (indicators.0, indicators.1, encoded_data)
-    }
+        let mut bits = BitBuffer::new();
+        self.segment().write_data(&mut bits);
 
-    pub fn encode(&self) -> Vec<String> {
-        match self.input.get_mode() {
-            InputMode::Numeric => self.numeric_encoding(),
-            InputMode::Alphanumeric => self.alphanumeric_encoding(),
-            InputMode::Byte => self.byte_encoding(),
-        }
+        (indicators.0, indicators.1, bits.to_bit_string())
     }
 
-    pub fn add_terminator(&mut self) -> String {
-        let (mode_indicator, char_count_indicator, data_string) = self.get_data();
-        let required_bits = self.get_required_bits();
-
-        let mut final_bits = String::new();
-        final_bits.push_str(&format!("{:04b}", mode_indicator));
-        final_bits.push_str(&format!("{:b}", char_count_indicator));
-        final_bits.push_str(&data_string);
-
-        let curr_len = final_bits.len();
-        if curr_len >= required_bits as usize {
-            return final_bits;
-        }
-
-        let remaining_bits = required_bits as usize - curr_len;
-        let terminator_length = remaining_bits.min(4);
-
-        final_bits.push_str(&"0".repeat(terminator_length));
-
-        final_bits
-    }
     pub fn get_required_bits(&mut self) -> u16 {
         let version_index = (self.version.unwrap() as usize) - 1;
 
@@ -279,18 +212,69 @@ impl QRData {
             ErrorCorrectionLevel::Q => 2,
             ErrorCorrectionLevel::H => 3,
         };
-        let required_bits = DATA_CODEWORDS[version_index][ec_index] * 8;
 
-        required_bits
+        DATA_CODEWORDS[version_index][ec_index] * 8
     }
 
-    pub fn get_final_data(&mut self) -> Result<String, QRError> {
-        if self.version.is_none() {
+    /// The segment followed by the terminator, before padding.
+    fn final_bits(&mut self) -> Result<BitBuffer, QRError> {
+        let Some(version) = self.version else {
             return Err(QRError::InvalidVersion(
                 "Version not determined".to_string(),
             ));
+        };
+
+        let mut bits = BitBuffer::new();
+        self.segment().write(version, &mut bits);
+        write_terminator(&mut bits, self.get_required_bits() as usize);
+        Ok(bits)
+    }
+
+    /// The final data bits as a string of `0` and `1`.
+    pub fn get_final_data(&mut self) -> Result<String, QRError> {
+        Ok(self.final_bits()?.to_bit_string())
+    }
+
+    /// Final data bits packed into codewords, padded with the alternating
+    /// 0xEC/0x11 pad bytes up to the data capacity of the version.
+    pub fn get_data_codewords(&mut self) -> Result<Vec<u8>, QRError> {
+        let mut bits = self.final_bits()?;
+        let required_bits = self.get_required_bits() as usize;
+
+        if bits.bit_len() > required_bits {
+            return Err(QRError::InvalidLength(format!(
+                "Encoded data needs {} bits but version {} holds {}",
+                bits.bit_len(),
+                self.version.unwrap_or(0),
+                required_bits
+            )));
         }
 
-        Ok(self.add_terminator())
+        write_pad_codewords(&mut bits, required_bits);
+        Ok(bits.into_bytes())
+    }
+
+    /// Data codewords followed by their error correction, interleaved across blocks.
+    pub fn get_codewords(&mut self) -> Result<Vec<u8>, QRError> {
+        let data = self.get_data_codewords()?;
+        let version = self.version.unwrap_or(0);
+
+        add_error_correction(&data, version, self.ec_level)
+    }
+
+    /// Encodes the content into a finished symbol. `mask` forces a mask
+    /// pattern; `None` picks the one with the lowest penalty.
+    pub fn generate(&mut self, mask: Option<u8>) -> Result<QRMatrix, QRError> {
+        let codewords = self.get_codewords()?;
+        let version = self.version.unwrap_or(0);
+
+        QRMatrix::build(version, self.ec_level, &codewords, mask)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Default for QRData {
+    fn default() -> Self {
+        Self::new()
     }
 }
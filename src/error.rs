@@ -1,12 +1,20 @@
-use std::fmt;
+use crate::capacity::SegmentMode;
+use crate::encoder::ErrorCorrectionLevel;
+#[cfg(feature = "alloc")]
+use alloc::string::{String, ToString};
+use core::fmt;
+
+#[cfg(feature = "alloc")]
 #[derive(Debug)]
 pub enum QRError {
     InvalidInput(String),
     InvalidLength(String),
     EncodingError(String),
     InvalidVersion(String),
+    DecodingError(String),
 }
 
+#[cfg(feature = "alloc")]
 impl fmt::Display for QRError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -14,8 +22,107 @@ impl fmt::Display for QRError {
             QRError::InvalidLength(msg) => write!(f, "Invalid length: {}", msg),
             QRError::EncodingError(msg) => write!(f, "Encoding error: {}", msg),
             QRError::InvalidVersion(msg) => write!(f, "Invalid version: {}", msg),
+            QRError::DecodingError(msg) => write!(f, "Decoding error: {}", msg),
         }
     }
 }
 
-impl std::error::Error for QRError {}
+#[cfg(feature = "alloc")]
+impl core::error::Error for QRError {}
+
+/// Errors of the segment checks and the fixed-buffer encoder, which carry
+/// their details as values so that reporting them never allocates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodeError {
+    /// No segments at all.
+    Empty,
+    /// A segment without characters.
+    EmptySegment,
+    InvalidCharacter(char, SegmentMode),
+    /// A Kanji segment with an odd number of bytes.
+    PartialKanji,
+    /// A double-byte code outside the Kanji mode ranges.
+    InvalidKanji(u16),
+    InvalidEci(u32),
+    InvalidMask(u8),
+    InvalidVersion(u8),
+    /// Buffers of `needed` bytes are required for `version`.
+    BufferTooShort {
+        version: u8,
+        needed: usize,
+        got: usize,
+    },
+    /// A codeword buffer of the wrong length for the block layout.
+    Codewords {
+        expected: usize,
+        got: usize,
+    },
+    /// The content does not fit into the pinned version.
+    TooLong(u8, ErrorCorrectionLevel),
+    /// The content does not fit into any version up to this one.
+    TooLongUpTo(u8, ErrorCorrectionLevel),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EncodeError::Empty => write!(f, "Input text cannot be empty"),
+            EncodeError::EmptySegment => write!(f, "Segments cannot be empty"),
+            EncodeError::InvalidCharacter(c, mode) => {
+                write!(f, "'{}' cannot be encoded in mode {:?}", c, mode)
+            }
+            EncodeError::PartialKanji => {
+                write!(f, "Kanji segments need whole double-byte characters")
+            }
+            EncodeError::InvalidKanji(code) => {
+                write!(f, "0x{:04X} is not a Kanji mode character", code)
+            }
+            EncodeError::InvalidEci(assignment) => {
+                write!(f, "ECI assignment {} is outside 0-999999", assignment)
+            }
+            EncodeError::InvalidMask(mask) => write!(f, "Mask {} is outside 0-7", mask),
+            EncodeError::InvalidVersion(version) => {
+                write!(f, "Version {} is outside 1-40", version)
+            }
+            EncodeError::BufferTooShort {
+                version,
+                needed,
+                got,
+            } => write!(
+                f,
+                "Version {} needs buffers of {} bytes, got {}",
+                version, needed, got
+            ),
+            EncodeError::Codewords { expected, got } => {
+                write!(f, "Expected {} codewords, got {}", expected, got)
+            }
+            EncodeError::TooLong(version, ec_level) => write!(
+                f,
+                "Content does not fit into version {} at error correction level {:?}",
+                version, ec_level
+            ),
+            EncodeError::TooLongUpTo(version, ec_level) => write!(
+                f,
+                "Content does not fit into version {} or below at error correction level {:?}",
+                version, ec_level
+            ),
+        }
+    }
+}
+
+impl core::error::Error for EncodeError {}
+
+#[cfg(feature = "alloc")]
+impl From<EncodeError> for QRError {
+    fn from(error: EncodeError) -> Self {
+        let message = error.to_string();
+        match error {
+            EncodeError::InvalidVersion(_) => QRError::InvalidVersion(message),
+            EncodeError::Codewords { .. } => QRError::EncodingError(message),
+            EncodeError::BufferTooShort { .. }
+            | EncodeError::TooLong(..)
+            | EncodeError::TooLongUpTo(..) => QRError::InvalidLength(message),
+            _ => QRError::InvalidInput(message),
+        }
+    }
+}
@@ -0,0 +1,476 @@
+use crate::encoder::ErrorCorrectionLevel;
+use crate::error::EncodeError;
+#[cfg(feature = "alloc")]
+use crate::error::QRError;
+use crate::versions::{ECC_CODEWORDS_PER_BLOCK, NUM_ERROR_CORRECTION_BLOCKS};
+#[cfg(feature = "alloc")]
+use alloc::{format, vec, vec::Vec};
+
+/// The most EC codewords any block has.
+pub(crate) const MAX_ECC_PER_BLOCK: usize = 30;
+
+/// Layout of the Reed-Solomon blocks for one version and error correction level.
+///
+/// Blocks come in two lengths: `num_short_blocks` blocks carry
+/// `short_block_data` data codewords and the remaining blocks carry one more.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockLayout {
+    pub num_blocks: usize,
+    pub num_short_blocks: usize,
+    pub short_block_data: usize,
+    pub ecc_per_block: usize,
+}
+
+impl BlockLayout {
+    pub fn new(version: u8, ec_level: ErrorCorrectionLevel) -> Result<Self, EncodeError> {
+        if !(1..=40).contains(&version) {
+            return Err(EncodeError::InvalidVersion(version));
+        }
+
+        let version_index = (version - 1) as usize;
+        let ec_index = ec_level as usize;
+        let num_blocks = NUM_ERROR_CORRECTION_BLOCKS[version_index][ec_index] as usize;
+        let ecc_per_block = ECC_CODEWORDS_PER_BLOCK[version_index][ec_index] as usize;
+        let total_codewords = total_codewords(version);
+
+        let num_short_blocks = num_blocks - total_codewords % num_blocks;
+        let short_block_len = total_codewords / num_blocks;
+
+        Ok(BlockLayout {
+            num_blocks,
+            num_short_blocks,
+            short_block_data: short_block_len - ecc_per_block,
+            ecc_per_block,
+        })
+    }
+
+    /// Number of data codewords carried by block `index`.
+    pub fn data_len(&self, index: usize) -> usize {
+        if index < self.num_short_blocks {
+            self.short_block_data
+        } else {
+            self.short_block_data + 1
+        }
+    }
+
+    pub fn data_codewords(&self) -> usize {
+        (0..self.num_blocks).map(|i| self.data_len(i)).sum()
+    }
+
+    pub fn total_codewords(&self) -> usize {
+        self.data_codewords() + self.num_blocks * self.ecc_per_block
+    }
+}
+
+/// Data codewords of one block together with their EC codewords.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub data: Vec<u8>,
+    pub ecc: Vec<u8>,
+}
+
+/// Number of modules available for codewords once all function patterns are placed.
+pub const fn raw_data_modules(version: u8) -> usize {
+    let v = version as usize;
+    let mut result = (16 * v + 128) * v + 64;
+
+    if v >= 2 {
+        let num_align = v / 7 + 2;
+        result -= (25 * num_align - 10) * num_align - 55;
+        if v >= 7 {
+            result -= 36;
+        }
+    }
+    result
+}
+
+pub const fn total_codewords(version: u8) -> usize {
+    raw_data_modules(version) / 8
+}
+
+/// Coefficients of the generator polynomial of the given degree, highest
+/// power first with the implicit leading 1 dropped.
+#[cfg(feature = "alloc")]
+pub fn generator_polynomial(degree: usize) -> Vec<u8> {
+    let mut result = vec![0u8; degree];
+    generator_into(&mut result);
+    result
+}
+
+/// Writes the generator polynomial of degree `result.len()` into `result`.
+pub(crate) fn generator_into(result: &mut [u8]) {
+    let degree = result.len();
+    result.fill(0);
+    result[degree - 1] = 1;
+
+    let mut root: u8 = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+}
+
+/// Remainder of `data` divided by `generator`, i.e. the EC codewords of one block.
+#[cfg(feature = "alloc")]
+pub fn compute_remainder(data: &[u8], generator: &[u8]) -> Vec<u8> {
+    let mut result = vec![0u8; generator.len()];
+    remainder_into(data, generator, &mut result);
+    result
+}
+
+/// Writes the remainder of `data` divided by `generator` into `result`,
+/// which is as long as the generator.
+pub(crate) fn remainder_into(data: &[u8], generator: &[u8], result: &mut [u8]) {
+    result.fill(0);
+    for &byte in data {
+        let factor = byte ^ result[0];
+        result.copy_within(1.., 0);
+        result[result.len() - 1] = 0;
+        for (r, &g) in result.iter_mut().zip(generator) {
+            *r ^= gf_multiply(g, factor);
+        }
+    }
+}
+
+/// Splits the data codewords into blocks and computes the EC codewords of each one.
+#[cfg(feature = "alloc")]
+pub fn split_blocks(data: &[u8], layout: &BlockLayout) -> Result<Vec<Block>, QRError> {
+    if data.len() != layout.data_codewords() {
+        return Err(QRError::EncodingError(format!(
+            "Expected {} data codewords, got {}",
+            layout.data_codewords(),
+            data.len()
+        )));
+    }
+
+    let generator = generator_polynomial(layout.ecc_per_block);
+    let mut blocks = Vec::with_capacity(layout.num_blocks);
+    let mut offset = 0;
+
+    for i in 0..layout.num_blocks {
+        let block = data[offset..offset + layout.data_len(i)].to_vec();
+        offset += block.len();
+        let ecc = compute_remainder(&block, &generator);
+        blocks.push(Block { data: block, ecc });
+    }
+    Ok(blocks)
+}
+
+/// Interleaves data and EC codewords into the final codeword sequence.
+#[cfg(feature = "alloc")]
+pub fn interleave(blocks: &[Block]) -> Vec<u8> {
+    let mut result = Vec::new();
+    let max_data = blocks.iter().map(|b| b.data.len()).max().unwrap_or(0);
+    let max_ecc = blocks.iter().map(|b| b.ecc.len()).max().unwrap_or(0);
+
+    for i in 0..max_data {
+        for block in blocks {
+            if let Some(&byte) = block.data.get(i) {
+                result.push(byte);
+            }
+        }
+    }
+    for i in 0..max_ecc {
+        for block in blocks {
+            if let Some(&byte) = block.ecc.get(i) {
+                result.push(byte);
+            }
+        }
+    }
+    result
+}
+
+/// For each codeword of the interleaved stream, the block it belongs to and
+/// its index within that block (data first, then EC codewords).
+#[cfg(feature = "alloc")]
+pub fn interleave_order(layout: &BlockLayout) -> Vec<(usize, usize)> {
+    let max_data = layout.short_block_data + 1;
+    let mut order = Vec::with_capacity(layout.total_codewords());
+
+    for i in 0..max_data {
+        for block in 0..layout.num_blocks {
+            if i < layout.data_len(block) {
+                order.push((block, i));
+            }
+        }
+    }
+    for i in 0..layout.ecc_per_block {
+        for block in 0..layout.num_blocks {
+            order.push((block, layout.data_len(block) + i));
+        }
+    }
+    order
+}
+
+/// Adds error correction to the data codewords and returns the interleaved stream.
+#[cfg(feature = "alloc")]
+pub fn add_error_correction(
+    data: &[u8],
+    version: u8,
+    ec_level: ErrorCorrectionLevel,
+) -> Result<Vec<u8>, QRError> {
+    let layout = BlockLayout::new(version, ec_level)?;
+    let mut result = vec![0; layout.total_codewords()];
+    interleave_into(data, &layout, &mut result)?;
+    Ok(result)
+}
+
+/// Computes the EC codewords of every block and writes data and EC
+/// codewords, interleaved, into `output`. Works without allocating.
+pub(crate) fn interleave_into(
+    data: &[u8],
+    layout: &BlockLayout,
+    output: &mut [u8],
+) -> Result<(), EncodeError> {
+    if data.len() != layout.data_codewords() {
+        return Err(EncodeError::Codewords {
+            expected: layout.data_codewords(),
+            got: data.len(),
+        });
+    }
+    if output.len() < layout.total_codewords() {
+        return Err(EncodeError::Codewords {
+            expected: layout.total_codewords(),
+            got: output.len(),
+        });
+    }
+
+    let ecc_len = layout.ecc_per_block;
+    let mut generator = [0u8; MAX_ECC_PER_BLOCK];
+    generator_into(&mut generator[..ecc_len]);
+    let mut ecc = [0u8; MAX_ECC_PER_BLOCK];
+
+    let blocks = layout.num_blocks;
+    let short_data = layout.short_block_data;
+    let data_total = layout.data_codewords();
+    let mut offset = 0;
+    for block in 0..blocks {
+        let block_data = &data[offset..offset + layout.data_len(block)];
+        offset += block_data.len();
+
+        // Long blocks' extra last codeword comes after every other block's.
+        for (i, &byte) in block_data.iter().enumerate() {
+            let position = if i < short_data {
+                i * blocks + block
+            } else {
+                short_data * blocks + block - layout.num_short_blocks
+            };
+            output[position] = byte;
+        }
+
+        remainder_into(block_data, &generator[..ecc_len], &mut ecc[..ecc_len]);
+        for (i, &byte) in ecc[..ecc_len].iter().enumerate() {
+            output[data_total + i * blocks + block] = byte;
+        }
+    }
+    Ok(())
+}
+
+const fn gf_tables() -> ([u8; 512], [u8; 256]) {
+    let mut exp = [0u8; 512];
+    let mut log = [0u8; 256];
+    let mut value: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = value as u8;
+        log[value as usize] = i as u8;
+        value <<= 1;
+        if value & 0x100 != 0 {
+            value ^= 0x11D;
+        }
+        i += 1;
+    }
+    // A second copy saves the modulo when adding logarithms.
+    while i < 512 {
+        exp[i] = exp[i - 255];
+        i += 1;
+    }
+    (exp, log)
+}
+
+const GF_TABLES: ([u8; 512], [u8; 256]) = gf_tables();
+const GF_EXP: [u8; 512] = GF_TABLES.0;
+const GF_LOG: [u8; 256] = GF_TABLES.1;
+
+/// Multiplication in GF(2^8) modulo the QR polynomial x^8 + x^4 + x^3 + x^2 + 1.
+pub fn gf_multiply(x: u8, y: u8) -> u8 {
+    if x == 0 || y == 0 {
+        return 0;
+    }
+    GF_EXP[GF_LOG[x as usize] as usize + GF_LOG[y as usize] as usize]
+}
+
+#[cfg(feature = "alloc")]
+fn gf_inverse(x: u8) -> u8 {
+    GF_EXP[255 - GF_LOG[x as usize] as usize]
+}
+
+/// `alpha^power`, for any non-negative power.
+#[cfg(feature = "alloc")]
+fn gf_pow_alpha(power: usize) -> u8 {
+    GF_EXP[power % 255]
+}
+
+/// Evaluates a polynomial stored lowest power first.
+#[cfg(feature = "alloc")]
+fn poly_eval(poly: &[u8], x: u8) -> u8 {
+    poly.iter().rev().fold(0, |acc, &c| gf_multiply(acc, x) ^ c)
+}
+
+/// How many codewords of one block had to be repaired.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Correction {
+    /// Codewords found and fixed without knowing their position.
+    pub errors: usize,
+    /// Codewords whose position was known to be unreliable.
+    pub erasures: usize,
+}
+
+impl Correction {
+    /// EC codewords still unused: each error costs two, each erasure one.
+    pub fn remaining_budget(&self, ecc_len: usize) -> usize {
+        ecc_len.saturating_sub(self.errors * 2 + self.erasures)
+    }
+}
+
+/// Corrects one block (data followed by EC codewords) in place.
+///
+/// `erasures` lists indices into `block` known to be unreliable. Up to
+/// `2 * errors + erasures <= ecc_len` damaged codewords can be repaired.
+#[cfg(feature = "alloc")]
+pub fn correct_block(
+    block: &mut [u8],
+    ecc_len: usize,
+    erasures: &[usize],
+) -> Result<Correction, QRError> {
+    let n = block.len();
+    if ecc_len == 0 || ecc_len >= n || n > 255 {
+        return Err(QRError::InvalidLength(format!(
+            "Cannot correct a block of {} codewords with {} EC codewords",
+            n, ecc_len
+        )));
+    }
+    if erasures.len() > ecc_len || erasures.iter().any(|&i| i >= n) {
+        return Err(QRError::DecodingError(format!(
+            "{} erasures exceed the {} EC codewords of the block",
+            erasures.len(),
+            ecc_len
+        )));
+    }
+
+    // Codeword i is the coefficient of x^(n-1-i); the generator's roots are
+    // alpha^0 .. alpha^(ecc_len-1).
+    let syndromes: Vec<u8> = (0..ecc_len)
+        .map(|k| {
+            block
+                .iter()
+                .fold(0, |acc, &c| gf_multiply(acc, gf_pow_alpha(k)) ^ c)
+        })
+        .collect();
+    if syndromes.iter().all(|&s| s == 0) {
+        return Ok(Correction::default());
+    }
+
+    // Erasure locator: product of (1 + X_e x) with X_e = alpha^(n-1-i).
+    let mut locator = vec![1u8];
+    for &i in erasures {
+        let x = gf_pow_alpha(n - 1 - i);
+        let mut next = vec![0u8; locator.len() + 1];
+        for (j, &c) in locator.iter().enumerate() {
+            next[j] ^= c;
+            next[j + 1] ^= gf_multiply(c, x);
+        }
+        locator = next;
+    }
+
+    // Berlekamp-Massey, started from the erasure locator.
+    let rho = erasures.len();
+    let mut previous = locator.clone();
+    let mut length = rho;
+    for r in rho..ecc_len {
+        let delta = locator
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i <= r)
+            .fold(0, |acc, (i, &c)| acc ^ gf_multiply(c, syndromes[r - i]));
+
+        previous.insert(0, 0);
+        if delta != 0 {
+            let mut next = locator.clone();
+            next.resize(next.len().max(previous.len()), 0);
+            for (j, &b) in previous.iter().enumerate() {
+                next[j] ^= gf_multiply(delta, b);
+            }
+            if 2 * length <= r + rho {
+                let inverse = gf_inverse(delta);
+                previous = locator.iter().map(|&c| gf_multiply(c, inverse)).collect();
+                length = r + 1 + rho - length;
+            }
+            locator = next;
+        }
+    }
+    while locator.len() > 1 && locator[locator.len() - 1] == 0 {
+        locator.pop();
+    }
+
+    let uncorrectable = || {
+        QRError::DecodingError(format!(
+            "Block has more damage than its {} EC codewords can repair",
+            ecc_len
+        ))
+    };
+    let degree = locator.len() - 1;
+    if degree != length || 2 * (length - rho) + rho > ecc_len {
+        return Err(uncorrectable());
+    }
+
+    // Chien search over the positions inside the block.
+    let positions: Vec<usize> = (0..n)
+        .filter(|&i| poly_eval(&locator, gf_inverse(gf_pow_alpha(n - 1 - i))) == 0)
+        .collect();
+    if positions.len() != degree {
+        return Err(uncorrectable());
+    }
+
+    // Forney: evaluator = syndromes * locator mod x^ecc_len.
+    let mut evaluator = vec![0u8; ecc_len];
+    for (i, &s) in syndromes.iter().enumerate() {
+        for (j, &l) in locator.iter().enumerate() {
+            if i + j < ecc_len {
+                evaluator[i + j] ^= gf_multiply(s, l);
+            }
+        }
+    }
+    let derivative: Vec<u8> = locator
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, &c)| if i % 2 == 1 { c } else { 0 })
+        .collect();
+
+    for &i in &positions {
+        let x = gf_pow_alpha(n - 1 - i);
+        let x_inverse = gf_inverse(x);
+        let denominator = poly_eval(&derivative, x_inverse);
+        if denominator == 0 {
+            return Err(uncorrectable());
+        }
+        let magnitude = gf_multiply(
+            x,
+            gf_multiply(poly_eval(&evaluator, x_inverse), gf_inverse(denominator)),
+        );
+        block[i] ^= magnitude;
+    }
+
+    let errors = positions.iter().filter(|i| !erasures.contains(i)).count();
+    Ok(Correction {
+        errors,
+        erasures: rho,
+    })
+}
@@ -0,0 +1,371 @@
+//! Encoding into caller-provided buffers, for targets without an allocator.
+//! Symbols are drawn one bit per module and errors are [`EncodeError`]
+//! values, so nothing here allocates. It is the one encoder left when the
+//! `alloc` feature is off.
+
+use crate::capacity::data_capacity_bits;
+use crate::encoder::ErrorCorrectionLevel;
+use crate::error::EncodeError;
+use crate::error_correction::{interleave_into, total_codewords, BlockLayout};
+use crate::input::ALPHANUMERIC_CHARS;
+use crate::matrix::{
+    apply_mask, draw_format_bits, draw_function_patterns, is_function_module, mask_penalties,
+    place_codewords, ModuleGrid, ModuleKind,
+};
+use crate::segment::{write_pad_codewords, write_terminator, BitWriter, Segment};
+
+/// Bytes a bit-packed symbol of this version takes.
+pub const fn modules_len(version: u8) -> usize {
+    let size = version as usize * 4 + 17;
+    (size * size).div_ceil(8)
+}
+
+/// Bytes each buffer passed to [`encode_segments`] needs for symbols up to
+/// `max_version`. A symbol always takes more bytes than its codewords.
+pub const fn buffer_len(max_version: u8) -> usize {
+    modules_len(max_version)
+}
+
+/// A finished symbol drawn into a caller's buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedSymbol<'a> {
+    version: u8,
+    ec_level: ErrorCorrectionLevel,
+    mask: u8,
+    modules: &'a [u8],
+}
+
+impl FixedSymbol<'_> {
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn size(&self) -> usize {
+        self.version as usize * 4 + 17
+    }
+
+    pub fn ec_level(&self) -> ErrorCorrectionLevel {
+        self.ec_level
+    }
+
+    pub fn mask(&self) -> u8 {
+        self.mask
+    }
+
+    /// Whether the module at column `x`, row `y` is dark.
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        packed_bit(self.modules, y * self.size() + x)
+    }
+
+    /// The modules row by row, one bit each, most significant bit first.
+    pub fn modules(&self) -> &[u8] {
+        self.modules
+    }
+}
+
+fn packed_bit(bytes: &[u8], index: usize) -> bool {
+    (bytes[index / 8] >> (7 - index % 8)) & 1 == 1
+}
+
+/// One bit per module in a borrowed slice. Function modules are worked out
+/// from the version rather than stored.
+pub(crate) struct PackedGrid<'a> {
+    version: u8,
+    modules: &'a mut [u8],
+}
+
+impl<'a> PackedGrid<'a> {
+    /// A grid of light modules over the start of `modules`.
+    pub(crate) fn new(version: u8, modules: &'a mut [u8]) -> Self {
+        let modules = &mut modules[..modules_len(version)];
+        modules.fill(0);
+        PackedGrid { version, modules }
+    }
+}
+
+impl ModuleGrid for PackedGrid<'_> {
+    fn size(&self) -> usize {
+        self.version as usize * 4 + 17
+    }
+
+    fn is_dark(&self, x: usize, y: usize) -> bool {
+        packed_bit(self.modules, y * self.size() + x)
+    }
+
+    fn set(&mut self, x: usize, y: usize, dark: bool) {
+        let index = y * self.size() + x;
+        let bit = 0x80 >> (index % 8);
+        if dark {
+            self.modules[index / 8] |= bit;
+        } else {
+            self.modules[index / 8] &= !bit;
+        }
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool, _kind: ModuleKind) {
+        self.set(x, y, dark);
+    }
+
+    fn is_function(&self, x: usize, y: usize) -> bool {
+        is_function_module(self.version, x, y)
+    }
+}
+
+/// Bits written into a borrowed slice, which must be long enough.
+struct SliceWriter<'a> {
+    bytes: &'a mut [u8],
+    len: usize,
+}
+
+impl BitWriter for SliceWriter<'_> {
+    fn push(&mut self, value: u32, count: usize) {
+        for i in (0..count).rev() {
+            if self.len.is_multiple_of(8) {
+                self.bytes[self.len / 8] = 0;
+            }
+            if (value >> i) & 1 == 1 {
+                self.bytes[self.len / 8] |= 0x80 >> (self.len % 8);
+            }
+            self.len += 1;
+        }
+    }
+
+    fn bit_len(&self) -> usize {
+        self.len
+    }
+}
+
+/// Encodes `text` as one segment in the most compact mode, like
+/// [`QRData`](crate::QRData) does.
+pub fn encode_text<'a>(
+    text: &str,
+    ec_level: ErrorCorrectionLevel,
+    version: Option<u8>,
+    mask: Option<u8>,
+    modules: &'a mut [u8],
+    scratch: &mut [u8],
+) -> Result<FixedSymbol<'a>, EncodeError> {
+    let segment = if text.chars().all(|c| c.is_ascii_digit()) {
+        Segment::Numeric(text)
+    } else if text.chars().all(|c| ALPHANUMERIC_CHARS.contains(c)) {
+        Segment::Alphanumeric(text)
+    } else {
+        Segment::Bytes(text.as_bytes())
+    };
+    encode_segments(&[segment], ec_level, version, mask, modules, scratch)
+}
+
+/// Encodes the segments into `modules`, using `scratch` for the codewords.
+/// Both buffers need [`buffer_len`] bytes for the largest version wanted;
+/// `version` defaults to the smallest that fits and `mask` to the lowest
+/// penalty.
+pub fn encode_segments<'a>(
+    segments: &[Segment],
+    ec_level: ErrorCorrectionLevel,
+    version: Option<u8>,
+    mask: Option<u8>,
+    modules: &'a mut [u8],
+    scratch: &mut [u8],
+) -> Result<FixedSymbol<'a>, EncodeError> {
+    if segments.is_empty() {
+        return Err(EncodeError::Empty);
+    }
+    for segment in segments {
+        segment.check()?;
+    }
+    if let Some(m) = mask.filter(|&m| m > 7) {
+        return Err(EncodeError::InvalidMask(m));
+    }
+
+    let room = modules.len().min(scratch.len());
+    let max_version = (1..=40u8)
+        .rev()
+        .find(|&v| buffer_len(v) <= room)
+        .unwrap_or(0);
+    let fits = |v: u8| {
+        segments
+            .iter()
+            .map(|s| s.bit_len(v))
+            .sum::<Option<usize>>()
+            .is_some_and(|bits| bits <= data_capacity_bits(v, ec_level))
+    };
+    let version = match version {
+        Some(v) if !(1..=40).contains(&v) => return Err(EncodeError::InvalidVersion(v)),
+        Some(v) if v > max_version => {
+            return Err(EncodeError::BufferTooShort {
+                version: v,
+                needed: buffer_len(v),
+                got: room,
+            });
+        }
+        Some(v) if fits(v) => v,
+        Some(v) => return Err(EncodeError::TooLong(v, ec_level)),
+        None => match (1..=max_version).find(|&v| fits(v)) {
+            Some(v) => v,
+            None => return Err(EncodeError::TooLongUpTo(max_version, ec_level)),
+        },
+    };
+
+    // Data codewords go into `modules` first, then are spread over the
+    // blocks with their EC codewords into `scratch`.
+    let capacity = data_capacity_bits(version, ec_level);
+    let mut data = SliceWriter {
+        bytes: &mut modules[..],
+        len: 0,
+    };
+    for segment in segments {
+        segment.write(version, &mut data);
+    }
+    write_terminator(&mut data, capacity);
+    write_pad_codewords(&mut data, capacity);
+
+    let layout = BlockLayout::new(version, ec_level)?;
+    let total = total_codewords(version);
+    interleave_into(&modules[..capacity / 8], &layout, &mut scratch[..total])?;
+
+    let mut grid = PackedGrid::new(version, modules);
+    draw_function_patterns(&mut grid, version);
+    place_codewords(&mut grid, &scratch[..total]);
+    let mask = match mask {
+        Some(m) => m,
+        None => {
+            let penalties = mask_penalties(&mut grid, ec_level);
+            (0..8u8).min_by_key(|&m| penalties[m as usize]).unwrap_or(0)
+        }
+    };
+    apply_mask(&mut grid, mask);
+    draw_format_bits(&mut grid, ec_level, mask);
+
+    Ok(FixedSymbol {
+        version,
+        ec_level,
+        mask,
+        modules: &modules[..modules_len(version)],
+    })
+}
+
+/// An encoder for symbols up to `MAX_VERSION` that draws into arrays the
+/// caller owns, for systems without an allocator. Size both arrays with
+/// [`QrBuffer::LEN`]:
+///
+/// ```
+/// use qrcodegenerator::fixed::QrBuffer;
+/// use qrcodegenerator::ErrorCorrectionLevel;
+///
+/// let mut modules = [0u8; QrBuffer::<5>::LEN];
+/// let mut scratch = [0u8; QrBuffer::<5>::LEN];
+/// let mut qr = QrBuffer::<5>::new(&mut modules, &mut scratch).ec_level(ErrorCorrectionLevel::Q);
+/// qr.encode_text("HELLO WORLD").unwrap();
+/// assert_eq!(qr.get_version(), 1);
+/// ```
+#[derive(Debug)]
+pub struct QrBuffer<'a, const MAX_VERSION: u8> {
+    modules: &'a mut [u8],
+    scratch: &'a mut [u8],
+    ec_level: ErrorCorrectionLevel,
+    version: Option<u8>,
+    mask: Option<u8>,
+    /// Version, level and mask of the symbol in `modules`, which the builder
+    /// methods do not change.
+    encoded: Option<(u8, ErrorCorrectionLevel, u8)>,
+}
+
+impl<'a, const MAX_VERSION: u8> QrBuffer<'a, MAX_VERSION> {
+    /// Bytes each array needs.
+    pub const LEN: usize = buffer_len(MAX_VERSION);
+
+    /// Wraps the module array and the codeword scratch array. Arrays shorter
+    /// than [`QrBuffer::LEN`] fail to compile.
+    pub fn new<const N: usize>(modules: &'a mut [u8; N], scratch: &'a mut [u8; N]) -> Self {
+        const {
+            assert!(
+                MAX_VERSION >= 1 && MAX_VERSION <= 40,
+                "MAX_VERSION must be 1-40"
+            );
+            assert!(
+                N >= buffer_len(MAX_VERSION),
+                "arrays are too short for MAX_VERSION"
+            );
+        }
+        QrBuffer {
+            modules: &mut modules[..Self::LEN],
+            scratch: &mut scratch[..Self::LEN],
+            ec_level: ErrorCorrectionLevel::M,
+            version: None,
+            mask: None,
+            encoded: None,
+        }
+    }
+
+    pub fn ec_level(mut self, ec_level: ErrorCorrectionLevel) -> Self {
+        self.ec_level = ec_level;
+        self
+    }
+
+    /// Pins the version instead of the smallest that fits.
+    pub fn version(mut self, version: u8) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Forces a mask pattern instead of the lowest penalty.
+    pub fn mask(mut self, mask: u8) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    /// Encodes `text` as one segment in the most compact mode.
+    pub fn encode_text(&mut self, text: &str) -> Result<(), EncodeError> {
+        self.encoded = None;
+        let symbol = encode_text(
+            text,
+            self.ec_level,
+            self.version,
+            self.mask,
+            self.modules,
+            self.scratch,
+        )?;
+        self.encoded = Some((symbol.version(), symbol.ec_level(), symbol.mask()));
+        Ok(())
+    }
+
+    pub fn encode_segments(&mut self, segments: &[Segment]) -> Result<(), EncodeError> {
+        self.encoded = None;
+        let symbol = encode_segments(
+            segments,
+            self.ec_level,
+            self.version,
+            self.mask,
+            self.modules,
+            self.scratch,
+        )?;
+        self.encoded = Some((symbol.version(), symbol.ec_level(), symbol.mask()));
+        Ok(())
+    }
+
+    /// The version of the last symbol encoded, 0 before the first.
+    pub fn get_version(&self) -> u8 {
+        self.encoded.map_or(0, |(version, _, _)| version)
+    }
+
+    pub fn get_mask(&self) -> Option<u8> {
+        self.encoded.map(|(_, _, mask)| mask)
+    }
+
+    /// The level of the last symbol encoded, or the one the next will use
+    /// before the first.
+    pub fn get_ec_level(&self) -> ErrorCorrectionLevel {
+        self.encoded
+            .map_or(self.ec_level, |(_, ec_level, _)| ec_level)
+    }
+
+    /// The last symbol encoded, if any.
+    pub fn symbol(&self) -> Option<FixedSymbol<'_>> {
+        self.encoded.map(|(version, ec_level, mask)| FixedSymbol {
+            version,
+            ec_level,
+            mask,
+            modules: &self.modules[..modules_len(version)],
+        })
+    }
+}
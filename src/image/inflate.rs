@@ -0,0 +1,275 @@
+use crate::error::QRError;
+use crate::render::png::adler32;
+
+/// Length base values and extra bits for codes 257-285.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// Distance base values and extra bits for codes 0-29.
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Order in which code length code lengths are stored in a dynamic block.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+const MAX_BITS: usize = 15;
+
+fn error(message: &str) -> QRError {
+    QRError::DecodingError(format!("Invalid deflate data: {}", message))
+}
+
+/// Decompresses a zlib stream and checks its Adler-32 checksum.
+pub fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>, QRError> {
+    if data.len() < 6 {
+        return Err(error("zlib stream is truncated"));
+    }
+    let (cmf, flags) = (data[0], data[1]);
+    if cmf & 0x0F != 8 || !(((cmf as u16) << 8) | flags as u16).is_multiple_of(31) {
+        return Err(error("bad zlib header"));
+    }
+    if flags & 0x20 != 0 {
+        return Err(error("preset dictionaries are not supported"));
+    }
+
+    let mut reader = BitReader {
+        data: &data[2..],
+        position: 0,
+    };
+    let output = inflate_from(&mut reader)?;
+
+    let end = 2 + reader.position.div_ceil(8);
+    let checksum = data
+        .get(end..end + 4)
+        .ok_or_else(|| error("missing Adler-32 checksum"))?;
+    if u32::from_be_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]) != adler32(&output)
+    {
+        return Err(error("Adler-32 checksum mismatch"));
+    }
+    Ok(output)
+}
+
+/// Decompresses a raw deflate stream.
+pub fn inflate(data: &[u8]) -> Result<Vec<u8>, QRError> {
+    inflate_from(&mut BitReader { data, position: 0 })
+}
+
+fn inflate_from(reader: &mut BitReader) -> Result<Vec<u8>, QRError> {
+    let mut output = Vec::new();
+    loop {
+        let last = reader.read(1)? == 1;
+        match reader.read(2)? {
+            0 => stored_block(reader, &mut output)?,
+            1 => {
+                let (literals, distances) = fixed_codes()?;
+                compressed_block(reader, &mut output, &literals, &distances)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_codes(reader)?;
+                compressed_block(reader, &mut output, &literals, &distances)?;
+            }
+            _ => return Err(error("reserved block type")),
+        }
+        if last {
+            return Ok(output);
+        }
+    }
+}
+
+/// Reads deflate's least-significant-bit-first bit fields.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn read(&mut self, bits: usize) -> Result<u32, QRError> {
+        let mut value = 0;
+        for i in 0..bits {
+            let byte = self
+                .data
+                .get(self.position / 8)
+                .ok_or_else(|| error("unexpected end of data"))?;
+            value |= ((byte >> (self.position % 8)) as u32 & 1) << i;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    fn align_to_byte(&mut self) {
+        self.position = self.position.div_ceil(8) * 8;
+    }
+}
+
+/// A canonical Huffman code, stored as the number of codes of each length
+/// and the symbols in code order.
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self, QRError> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        // Reject over-subscribed codes; incomplete ones are allowed.
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = left * 2 - count as i32;
+            if left < 0 {
+                return Err(error("over-subscribed Huffman code"));
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for length in 1..=MAX_BITS {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0u16; offsets[MAX_BITS + 1] as usize];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, QRError> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for length in 1..=MAX_BITS {
+            code |= reader.read(1)? as i32;
+            let count = self.counts[length] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(error("invalid Huffman code"))
+    }
+}
+
+fn stored_block(reader: &mut BitReader, output: &mut Vec<u8>) -> Result<(), QRError> {
+    reader.align_to_byte();
+    let length = reader.read(16)? as u16;
+    let complement = reader.read(16)? as u16;
+    if length != !complement {
+        return Err(error("stored block length mismatch"));
+    }
+
+    let start = reader.position / 8;
+    let bytes = reader
+        .data
+        .get(start..start + length as usize)
+        .ok_or_else(|| error("stored block runs past the end"))?;
+    output.extend_from_slice(bytes);
+    reader.position += length as usize * 8;
+    Ok(())
+}
+
+fn fixed_codes() -> Result<(Huffman, Huffman), QRError> {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5; 30])?))
+}
+
+fn dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), QRError> {
+    let literal_count = reader.read(5)? as usize + 257;
+    let distance_count = reader.read(5)? as usize + 1;
+    let code_length_count = reader.read(4)? as usize + 4;
+    if literal_count > 286 || distance_count > 30 {
+        return Err(error("too many codes in dynamic block"));
+    }
+
+    let mut code_lengths = [0u8; 19];
+    for &index in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[index] = reader.read(3)? as u8;
+    }
+    let code_length_code = Huffman::new(&code_lengths)?;
+
+    let mut lengths = vec![0u8; literal_count + distance_count];
+    let mut i = 0;
+    while i < lengths.len() {
+        let symbol = code_length_code.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths[..i]
+                    .last()
+                    .ok_or_else(|| error("repeat with no previous length"))?;
+                (previous, 3 + reader.read(2)? as usize)
+            }
+            17 => (0, 3 + reader.read(3)? as usize),
+            _ => (0, 11 + reader.read(7)? as usize),
+        };
+        if i + repeat > lengths.len() {
+            return Err(error("code lengths overflow"));
+        }
+        lengths[i..i + repeat].fill(value);
+        i += repeat;
+    }
+    if lengths[256] == 0 {
+        return Err(error("missing end-of-block code"));
+    }
+
+    Ok((
+        Huffman::new(&lengths[..literal_count])?,
+        Huffman::new(&lengths[literal_count..])?,
+    ))
+}
+
+fn compressed_block(
+    reader: &mut BitReader,
+    output: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> Result<(), QRError> {
+    loop {
+        let symbol = literals.decode(reader)? as usize;
+        match symbol {
+            0..=255 => output.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let index = symbol - 257;
+                let length = LENGTH_BASE[index] as usize
+                    + reader.read(LENGTH_EXTRA[index] as usize)? as usize;
+                let code = distances.decode(reader)? as usize;
+                if code >= 30 {
+                    return Err(error("invalid distance code"));
+                }
+                let distance = DISTANCE_BASE[code] as usize
+                    + reader.read(DISTANCE_EXTRA[code] as usize)? as usize;
+                if distance > output.len() {
+                    return Err(error("distance reaches before the start"));
+                }
+                // Copies may overlap their own output, so go byte by byte.
+                let start = output.len() - distance;
+                for k in 0..length {
+                    output.push(output[start + k]);
+                }
+            }
+            _ => return Err(error("invalid literal/length code")),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+//! Readers that turn image files into luminance for the image decoder.
+
+pub mod inflate;
+pub mod netpbm;
+pub mod png;
+
+use crate::detector::GrayImage;
+use crate::error::QRError;
+
+pub use netpbm::read_netpbm;
+pub use png::read_png;
+
+/// Reads a PNG, PGM or PBM file, telling them apart by their signature.
+pub fn read_image(bytes: &[u8]) -> Result<GrayImage, QRError> {
+    if bytes.starts_with(&png::SIGNATURE) {
+        read_png(bytes)
+    } else if bytes.len() >= 2 && bytes[0] == b'P' && (b'1'..=b'5').contains(&bytes[1]) {
+        read_netpbm(bytes)
+    } else {
+        Err(QRError::InvalidInput(
+            "Unrecognised image format, expected PNG, PGM or PBM".to_string(),
+        ))
+    }
+}
+
+/// Reads an image file from disk.
+pub fn load_image(path: &str) -> Result<GrayImage, QRError> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| QRError::InvalidInput(format!("Cannot read '{}': {}", path, e)))?;
+    read_image(&bytes)
+}
+
+/// ITU-R BT.601 luma of an RGB colour.
+pub(crate) fn luminance(r: u8, g: u8, b: u8) -> u8 {
+    ((299 * r as u32 + 587 * g as u32 + 114 * b as u32 + 500) / 1000) as u8
+}
+
+/// Composites a grey level with the given coverage over a white background,
+/// where a missing quiet zone is most likely meant to be.
+pub(crate) fn over_white(value: u8, alpha: u8) -> u8 {
+    let alpha = alpha as u32;
+    ((value as u32 * alpha + 255 * (255 - alpha) + 127) / 255) as u8
+}
@@ -0,0 +1,155 @@
+use crate::detector::GrayImage;
+use crate::error::QRError;
+
+fn error(message: &str) -> QRError {
+    QRError::DecodingError(format!("Invalid Netpbm image: {}", message))
+}
+
+/// Decodes a PBM (`P1`, `P4`) or PGM (`P2`, `P5`) image to luminance.
+pub fn read_netpbm(bytes: &[u8]) -> Result<GrayImage, QRError> {
+    let mut reader = HeaderReader { bytes, position: 0 };
+    let magic = reader.token()?;
+    let bitmap = matches!(magic, b"P1" | b"P4");
+    if !bitmap && !matches!(magic, b"P2" | b"P5") {
+        return Err(error("expected a P1, P2, P4 or P5 signature"));
+    }
+
+    let width = reader.number()?;
+    let height = reader.number()?;
+    let max = if bitmap { 1 } else { reader.number()? };
+    if width == 0 || height == 0 || max == 0 || max > 65535 {
+        return Err(error("bad dimensions or maximum value"));
+    }
+    let count = width
+        .checked_mul(height)
+        .ok_or_else(|| error("image is too large"))?;
+    // Every pixel takes at least one byte in the plain formats, and a known
+    // number of bits in the binary ones.
+    let needed = match magic {
+        b"P1" | b"P2" => Some(count),
+        b"P4" => width.div_ceil(8).checked_mul(height),
+        _ if max > 255 => count.checked_mul(2),
+        _ => Some(count),
+    };
+    let available = match magic {
+        b"P1" | b"P2" => bytes.len().saturating_sub(reader.position),
+        _ => reader.raster()?.len(),
+    };
+    if needed.is_none_or(|needed| needed > available) {
+        return Err(error("raster is too short"));
+    }
+
+    let values: Vec<usize> = match magic {
+        b"P1" => {
+            // Plain PBM digits need not be separated by whitespace.
+            let digits: Vec<usize> = bytes[reader.position..]
+                .iter()
+                .filter(|b| matches!(b, b'0' | b'1'))
+                .take(count)
+                .map(|b| (b - b'0') as usize)
+                .collect();
+            digits
+        }
+        b"P2" => (0..count)
+            .map(|_| reader.number())
+            .collect::<Result<Vec<usize>, QRError>>()?,
+        b"P4" => {
+            let data = reader.raster()?;
+            let row_bytes = width.div_ceil(8);
+            (0..count)
+                .map(|i| {
+                    let (x, y) = (i % width, i / width);
+                    data.get(y * row_bytes + x / 8)
+                        .map(|byte| (byte >> (7 - x % 8)) as usize & 1)
+                        .ok_or_else(|| error("raster is too short"))
+                })
+                .collect::<Result<Vec<usize>, QRError>>()?
+        }
+        _ => {
+            let data = reader.raster()?;
+            let sample_bytes = if max > 255 { 2 } else { 1 };
+            (0..count)
+                .map(|i| match sample_bytes {
+                    2 => data
+                        .get(i * 2..i * 2 + 2)
+                        .map(|v| u16::from_be_bytes([v[0], v[1]]) as usize),
+                    _ => data.get(i).map(|&v| v as usize),
+                })
+                .collect::<Option<Vec<usize>>>()
+                .ok_or_else(|| error("raster is too short"))?
+        }
+    };
+    if values.len() != count {
+        return Err(error("not enough pixels"));
+    }
+
+    let pixels = values
+        .into_iter()
+        .map(|value| {
+            if bitmap {
+                // In PBM, 1 is black.
+                if value == 1 {
+                    0
+                } else {
+                    255
+                }
+            } else {
+                (value.min(max) * 255 / max) as u8
+            }
+        })
+        .collect();
+    GrayImage::new(width, height, pixels)
+}
+
+/// Reads whitespace-separated header tokens, skipping `#` comments.
+struct HeaderReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> HeaderReader<'a> {
+    fn token(&mut self) -> Result<&'a [u8], QRError> {
+        loop {
+            match self.bytes.get(self.position) {
+                Some(b) if b.is_ascii_whitespace() => self.position += 1,
+                Some(b'#') => {
+                    while self.bytes.get(self.position).is_some_and(|&b| b != b'\n') {
+                        self.position += 1;
+                    }
+                }
+                Some(_) => break,
+                None => return Err(error("unexpected end of header")),
+            }
+        }
+
+        let start = self.position;
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|b| !b.is_ascii_whitespace() && *b != b'#')
+        {
+            self.position += 1;
+        }
+        Ok(&self.bytes[start..self.position])
+    }
+
+    fn number(&mut self) -> Result<usize, QRError> {
+        let token = self.token()?;
+        std::str::from_utf8(token)
+            .ok()
+            .and_then(|t| t.parse().ok())
+            .ok_or_else(|| {
+                error(&format!(
+                    "expected a number, got '{}'",
+                    String::from_utf8_lossy(token)
+                ))
+            })
+    }
+
+    /// The binary raster, which starts after exactly one whitespace byte.
+    fn raster(&self) -> Result<&'a [u8], QRError> {
+        self.bytes
+            .get(self.position + 1..)
+            .ok_or_else(|| error("missing raster"))
+    }
+}
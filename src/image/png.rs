@@ -0,0 +1,349 @@
+use super::inflate::zlib_decompress;
+use super::{luminance, over_white};
+use crate::detector::GrayImage;
+use crate::error::QRError;
+use crate::render::png::crc32;
+
+pub const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+/// Column and row start and step of the seven Adam7 passes.
+const ADAM7: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+fn error(message: &str) -> QRError {
+    QRError::DecodingError(format!("Invalid PNG: {}", message))
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Header {
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    fn channels(&self) -> usize {
+        match self.color_type {
+            0 | 3 => 1,
+            4 => 2,
+            2 => 3,
+            _ => 4,
+        }
+    }
+
+    fn bits_per_pixel(&self) -> usize {
+        self.channels() * self.bit_depth as usize
+    }
+
+    /// Bytes per row of a (sub)image `width` pixels wide, without the filter
+    /// byte.
+    fn row_bytes(&self, width: usize) -> usize {
+        (width * self.bits_per_pixel()).div_ceil(8)
+    }
+
+    /// The passes as column and row start and step: the Adam7 ones, or one
+    /// covering the whole image.
+    fn passes(&self) -> Vec<(usize, usize, usize, usize)> {
+        if self.interlaced {
+            ADAM7.to_vec()
+        } else {
+            vec![(0, 0, 1, 1)]
+        }
+    }
+
+    /// Bytes of filtered image data the header implies, or `None` if that
+    /// does not fit in a `usize`.
+    fn filtered_len(&self) -> Option<usize> {
+        let mut total = 0usize;
+        for (x0, y0, dx, dy) in self.passes() {
+            if x0 >= self.width || y0 >= self.height {
+                continue;
+            }
+            let pass_width = (self.width - x0).div_ceil(dx);
+            let pass_height = (self.height - y0).div_ceil(dy);
+            let row_bytes = pass_width
+                .checked_mul(self.bits_per_pixel())?
+                .div_ceil(8)
+                .checked_add(1)?;
+            total = total.checked_add(row_bytes.checked_mul(pass_height)?)?;
+        }
+        Some(total)
+    }
+}
+
+/// Decodes a PNG file to luminance. Every colour type and bit depth is
+/// supported, as is Adam7 interlacing; transparency is composited over
+/// white.
+pub fn read_png(bytes: &[u8]) -> Result<GrayImage, QRError> {
+    if !bytes.starts_with(&SIGNATURE) {
+        return Err(error("missing signature"));
+    }
+
+    let mut header = None;
+    let mut palette: Vec<[u8; 3]> = Vec::new();
+    let mut palette_alpha: Vec<u8> = Vec::new();
+    let mut transparent: Option<Vec<u16>> = None;
+    let mut compressed = Vec::new();
+
+    let mut position = SIGNATURE.len();
+    loop {
+        let chunk_header = bytes
+            .get(position..position + 8)
+            .ok_or_else(|| error("truncated chunk"))?;
+        let length = u32::from_be_bytes([
+            chunk_header[0],
+            chunk_header[1],
+            chunk_header[2],
+            chunk_header[3],
+        ]) as usize;
+        let kind = &bytes[position + 4..position + 8];
+        let data = bytes
+            .get(position + 8..position + 8 + length)
+            .ok_or_else(|| error("truncated chunk"))?;
+        let crc = bytes
+            .get(position + 8 + length..position + 12 + length)
+            .ok_or_else(|| error("truncated chunk"))?;
+        if crc32(&bytes[position + 4..position + 8 + length])
+            != u32::from_be_bytes([crc[0], crc[1], crc[2], crc[3]])
+        {
+            return Err(error(&format!(
+                "CRC mismatch in {} chunk",
+                String::from_utf8_lossy(kind)
+            )));
+        }
+        position += 12 + length;
+
+        match kind {
+            b"IHDR" => header = Some(parse_header(data)?),
+            b"PLTE" => {
+                palette = data
+                    .chunks_exact(3)
+                    .map(|rgb| [rgb[0], rgb[1], rgb[2]])
+                    .collect();
+            }
+            b"tRNS" => match header.map(|h| h.color_type) {
+                Some(3) => palette_alpha = data.to_vec(),
+                Some(0) | Some(2) => {
+                    transparent = Some(
+                        data.chunks_exact(2)
+                            .map(|v| u16::from_be_bytes([v[0], v[1]]))
+                            .collect(),
+                    );
+                }
+                _ => {}
+            },
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            _ if kind[0].is_ascii_uppercase() => {
+                return Err(error(&format!(
+                    "unsupported critical chunk {}",
+                    String::from_utf8_lossy(kind)
+                )));
+            }
+            _ => {}
+        }
+    }
+
+    let header = header.ok_or_else(|| error("missing IHDR chunk"))?;
+    if header.color_type == 3 && palette.is_empty() {
+        return Err(error("missing PLTE chunk"));
+    }
+    let raw = zlib_decompress(&compressed)?;
+
+    // The header is untrusted: check it against the data before sizing
+    // anything from it.
+    let sample_count = header
+        .width
+        .checked_mul(header.height)
+        .and_then(|pixels| pixels.checked_mul(header.channels()))
+        .ok_or_else(|| error("image is too large"))?;
+    match header.filtered_len() {
+        Some(length) if length <= raw.len() => {}
+        Some(_) => return Err(error("image data is too short")),
+        None => return Err(error("image is too large")),
+    }
+
+    // Each pass, or the whole image, is a run of filtered rows.
+    let mut samples = vec![0u16; sample_count];
+    let mut offset = 0;
+    for (x0, y0, dx, dy) in header.passes() {
+        if x0 >= header.width || y0 >= header.height {
+            continue;
+        }
+        let pass_width = (header.width - x0).div_ceil(dx);
+        let pass_height = (header.height - y0).div_ceil(dy);
+        let row_bytes = header.row_bytes(pass_width);
+        let length = (row_bytes + 1) * pass_height;
+        let data = raw
+            .get(offset..offset + length)
+            .ok_or_else(|| error("image data is too short"))?;
+        offset += length;
+
+        let rows = unfilter(data, row_bytes, header.bits_per_pixel().div_ceil(8))?;
+        for (row_index, row) in rows.chunks_exact(row_bytes).enumerate() {
+            let y = y0 + row_index * dy;
+            for column in 0..pass_width {
+                let x = x0 + column * dx;
+                for channel in 0..header.channels() {
+                    let index = column * header.channels() + channel;
+                    samples[(y * header.width + x) * header.channels() + channel] =
+                        read_sample(row, index, header.bit_depth);
+                }
+            }
+        }
+    }
+
+    let channels = header.channels();
+    let max = ((1u32 << header.bit_depth) - 1) as u16;
+    // Scales a sample to 8 bits.
+    let to_byte = |value: u16| (value as u32 * 255 / max as u32) as u8;
+    let pixels = samples
+        .chunks_exact(channels)
+        .map(|pixel| -> Result<u8, QRError> {
+            Ok(match header.color_type {
+                0 => {
+                    let alpha = match &transparent {
+                        Some(key) if key.first() == Some(&pixel[0]) => 0,
+                        _ => 255,
+                    };
+                    over_white(to_byte(pixel[0]), alpha)
+                }
+                2 => {
+                    let grey = luminance(to_byte(pixel[0]), to_byte(pixel[1]), to_byte(pixel[2]));
+                    let alpha = match &transparent {
+                        Some(key) if key[..] == pixel[..3] => 0,
+                        _ => 255,
+                    };
+                    over_white(grey, alpha)
+                }
+                3 => {
+                    let index = pixel[0] as usize;
+                    let [r, g, b] = *palette
+                        .get(index)
+                        .ok_or_else(|| error("palette index out of range"))?;
+                    let alpha = palette_alpha.get(index).copied().unwrap_or(255);
+                    over_white(luminance(r, g, b), alpha)
+                }
+                4 => over_white(to_byte(pixel[0]), to_byte(pixel[1])),
+                _ => over_white(
+                    luminance(to_byte(pixel[0]), to_byte(pixel[1]), to_byte(pixel[2])),
+                    to_byte(pixel[3]),
+                ),
+            })
+        })
+        .collect::<Result<Vec<u8>, QRError>>()?;
+
+    GrayImage::new(header.width, header.height, pixels)
+}
+
+fn parse_header(data: &[u8]) -> Result<Header, QRError> {
+    if data.len() != 13 {
+        return Err(error("IHDR chunk has the wrong length"));
+    }
+    let width = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    let height = u32::from_be_bytes([data[4], data[5], data[6], data[7]]) as usize;
+    let (bit_depth, color_type) = (data[8], data[9]);
+
+    let valid_depth = match color_type {
+        0 => [1, 2, 4, 8, 16].contains(&bit_depth),
+        3 => [1, 2, 4, 8].contains(&bit_depth),
+        2 | 4 | 6 => [8, 16].contains(&bit_depth),
+        _ => false,
+    };
+    if !valid_depth {
+        return Err(error(&format!(
+            "colour type {} with bit depth {} is not valid",
+            color_type, bit_depth
+        )));
+    }
+    if data[10] != 0 || data[11] != 0 || data[12] > 1 {
+        return Err(error("unknown compression, filter or interlace method"));
+    }
+    if width == 0 || height == 0 {
+        return Err(error("image has no pixels"));
+    }
+
+    Ok(Header {
+        width,
+        height,
+        bit_depth,
+        color_type,
+        interlaced: data[12] == 1,
+    })
+}
+
+/// Reverses the per-row filters. `pixel_bytes` is the distance to the
+/// corresponding byte of the previous pixel, at least one.
+fn unfilter(data: &[u8], row_bytes: usize, pixel_bytes: usize) -> Result<Vec<u8>, QRError> {
+    let mut rows = Vec::with_capacity(data.len());
+    let mut previous = vec![0u8; row_bytes];
+
+    for line in data.chunks_exact(row_bytes + 1) {
+        let (filter, line) = (line[0], &line[1..]);
+        let mut row = line.to_vec();
+        for i in 0..row_bytes {
+            let left = if i >= pixel_bytes {
+                row[i - pixel_bytes]
+            } else {
+                0
+            };
+            let up = previous[i];
+            let up_left = if i >= pixel_bytes {
+                previous[i - pixel_bytes]
+            } else {
+                0
+            };
+            let predictor = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(error(&format!("unknown filter type {}", filter))),
+            };
+            row[i] = row[i].wrapping_add(predictor);
+        }
+        rows.extend_from_slice(&row);
+        previous = row;
+    }
+    Ok(rows)
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// The `index`th sample of an unfiltered row.
+fn read_sample(row: &[u8], index: usize, bit_depth: u8) -> u16 {
+    match bit_depth {
+        16 => u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+        8 => row[index] as u16,
+        _ => {
+            let bits = bit_depth as usize;
+            let bit = index * bits;
+            let shift = 8 - bits - bit % 8;
+            ((row[bit / 8] >> shift) & ((1 << bits) - 1)) as u16
+        }
+    }
+}
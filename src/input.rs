@@ -1,5 +1,13 @@
+#[cfg(feature = "alloc")]
 use crate::error::QRError;
-use std::collections::HashMap;
+#[cfg(feature = "alloc")]
+use alloc::{
+    format,
+    string::{String, ToString},
+};
+
+/// The alphanumeric mode character set, in code value order.
+pub const ALPHANUMERIC_CHARS: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum InputMode {
@@ -9,6 +17,7 @@ pub enum InputMode {
 }
 
 impl InputMode {
+    #[cfg(feature = "alloc")]
     fn get_indicator(self) -> u8 {
         match self {
             InputMode::Numeric => 0b0001,
@@ -16,35 +25,43 @@ impl InputMode {
             InputMode::Byte => 0b0100,
         }
     }
+
+    /// Width of the character count indicator for this mode at the given version.
+    pub fn character_count_bits(self, version: u8) -> usize {
+        match version {
+            1..=9 => match self {
+                InputMode::Numeric => 10,
+                InputMode::Alphanumeric => 9,
+                InputMode::Byte => 8,
+            },
+            10..=26 => match self {
+                InputMode::Numeric => 12,
+                InputMode::Alphanumeric => 11,
+                InputMode::Byte => 16,
+            },
+            27..=40 => match self {
+                InputMode::Numeric => 14,
+                InputMode::Alphanumeric => 13,
+                InputMode::Byte => 16,
+            },
+            0 | 41..=u8::MAX => 0,
+        }
+    }
 }
 
+#[cfg(feature = "alloc")]
 #[derive(Debug, Clone)]
 pub struct QRInput {
     content: String,
     mode: InputMode,
-    alphanumeric_chars: HashMap<char, bool>,
 }
 
+#[cfg(feature = "alloc")]
 impl QRInput {
     pub fn new() -> Self {
-        let mut alpha_chars = HashMap::new();
-
-        for c in '0'..='9' {
-            alpha_chars.insert(c, true);
-        }
-
-        for c in 'A'..='Z' {
-            alpha_chars.insert(c, true);
-        }
-
-        for c in [' ', '$', '%', '*', '+', '-', '.', '/', ':'] {
-            alpha_chars.insert(c, true);
-        }
-
         QRInput {
             content: String::new(),
             mode: InputMode::Numeric,
-            alphanumeric_chars: alpha_chars,
         }
     }
 
@@ -63,6 +80,28 @@ impl QRInput {
         Ok(self.mode)
     }
 
+    /// Forces a mode instead of the automatically detected one. Fails when the
+    /// content contains characters the mode cannot represent.
+    pub fn set_mode(&mut self, mode: InputMode) -> Result<(), QRError> {
+        let content = self.content.as_str();
+
+        let valid = match mode {
+            InputMode::Numeric => content.chars().all(|c| c.is_ascii_digit()),
+            InputMode::Alphanumeric => content.chars().all(|c| ALPHANUMERIC_CHARS.contains(c)),
+            InputMode::Byte => true,
+        };
+
+        if !valid {
+            return Err(QRError::InvalidInput(format!(
+                "Content cannot be encoded in mode {:?}",
+                mode
+            )));
+        }
+
+        self.mode = mode;
+        Ok(())
+    }
+
     pub fn get_mode(&self) -> InputMode {
         self.mode
     }
@@ -72,10 +111,7 @@ impl QRInput {
 
         self.mode = if content.chars().all(|c| c.is_ascii_digit()) {
             InputMode::Numeric
-        } else if content
-            .chars()
-            .all(|c| self.alphanumeric_chars.contains_key(&c))
-        {
+        } else if content.chars().all(|c| ALPHANUMERIC_CHARS.contains(c)) {
             InputMode::Alphanumeric
         } else {
             InputMode::Byte
@@ -88,31 +124,20 @@ impl QRInput {
         self.mode.get_indicator()
     }
 
-    pub fn calculate_character_count_indicator(&self, version: Option<u8>) -> u16 {
-        let version = version.unwrap();
-        let bit_length = match version {
-            1..=9 => match self.mode {
-                InputMode::Numeric => 10,
-                InputMode::Alphanumeric => 9,
-                InputMode::Byte => 8,
-            },
-            10..=26 => match self.mode {
-                InputMode::Numeric => 12,
-                InputMode::Alphanumeric => 11,
-                InputMode::Byte => 16,
-            },
-            27..=40 => match self.mode {
-                InputMode::Numeric => 14,
-                InputMode::Alphanumeric => 13,
-                InputMode::Byte => 16,
-            },
-            0 | 41..=u8::MAX => 0,
-        };
+    /// Number of characters as counted by the character count indicator;
+    /// byte mode counts bytes rather than characters.
+    pub fn get_character_count(&self) -> usize {
+        match self.mode {
+            InputMode::Byte => self.content.len(),
+            _ => self.content.chars().count(),
+        }
+    }
 
-        let count = self.content.chars().count();
-        let binary_padded = format!("{:0width$b}", count, width = bit_length);
+    pub fn calculate_character_count_indicator(&self, version: Option<u8>) -> u16 {
+        let bit_length = self.mode.character_count_bits(version.unwrap_or(0));
+        let count = self.get_character_count() as u32;
 
-        return u16::from_str_radix(&binary_padded, 2).unwrap();
+        (count & ((1u32 << bit_length) - 1)) as u16
     }
 
     pub fn get_indicator(&self, version: Option<u8>) -> (u8, u16) {
@@ -140,3 +165,10 @@ impl QRInput {
         Ok(())
     }
 }
+
+#[cfg(feature = "alloc")]
+impl Default for QRInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,9 +1,40 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod batch;
+pub mod capacity;
+#[cfg(feature = "std")]
+pub mod damage;
+#[cfg(feature = "alloc")]
+pub mod decoder;
+#[cfg(feature = "std")]
+pub mod detector;
 pub mod encoder;
 pub mod error;
+pub mod error_correction;
+pub mod fixed;
+#[cfg(feature = "std")]
+pub mod image;
 pub mod input;
+pub mod matrix;
+#[cfg(feature = "std")]
+pub mod payload;
+#[cfg(feature = "alloc")]
+pub mod random;
+#[cfg(feature = "std")]
+pub mod render;
+pub mod segment;
 pub mod versions;
 
+pub use encoder::ErrorCorrectionLevel;
+#[cfg(feature = "alloc")]
 pub use encoder::QRData;
 pub use input::InputMode;
+#[cfg(feature = "alloc")]
 pub use input::QRInput;
+#[cfg(feature = "alloc")]
+pub use matrix::QRMatrix;
 pub use versions::VERSION_CAPACITIES;
@@ -1,6 +1,363 @@
-use std::fmt::format;
+use qrcodegenerator::batch::{self, BatchFailure, BatchFormat, BatchOptions};
+use qrcodegenerator::capacity::{self, ContentPlan, SegmentMode};
+use qrcodegenerator::detector::decode_image;
 use qrcodegenerator::encoder::ErrorCorrectionLevel;
+use qrcodegenerator::error_correction::{interleave, split_blocks, BlockLayout};
+use qrcodegenerator::image;
+use qrcodegenerator::matrix::{format_information, version_information, QRMatrix};
+use qrcodegenerator::render::{
+    self, Color, ImageFormat, Logo, LogoImage, LogoPolicy, RenderOptions, POINTS_PER_MM,
+};
 use qrcodegenerator::{InputMode, QRData};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process;
+
+const USAGE: &str = "\
+Usage: qrgen [OPTIONS] [TEXT]
+       qrgen debug [OPTIONS] [TEXT]
+       qrgen batch [OPTIONS] --output-dir <DIR> <FILE>
+       qrgen decode <IMAGE>
+       qrgen capacity [OPTIONS] [TEXT]
+
+Encodes TEXT (or the --input file, or stdin) into a QR code.
+
+Commands:
+  debug                  Print every encoding stage instead of an image
+  batch                  Generate one symbol per record of a CSV or JSON-lines
+                         file with the fields content, filename and optional
+                         ec_level and version
+  decode                 Read a QR code from a PNG, PGM or PBM image ('-' for
+                         stdin) and print its content
+  capacity               List every version and level that holds TEXT or
+                         the --segment plan, or with --version alone the
+                         most characters per mode at that version
+
+Options:
+  -i, --input <FILE>     Read the content from FILE ('-' for stdin)
+  -e, --ec <L|M|Q|H>     Error correction level [default: M]
+  -v, --version <1-40>   Symbol version [default: smallest that fits]
+  -m, --mask <0-7>       Mask pattern [default: lowest penalty]
+      --mode <MODE>      auto, numeric, alphanumeric or byte [default: auto]
+  -o, --output <FILE>    Write an .svg, .png, .eps or .pdf file, or '-' for
+                         SVG on stdout
+  -s, --scale <N>        Pixels per module [default: 8]
+  -b, --border <N>       Quiet zone width in modules [default: 4]
+      --dark <COLOUR>    Dark module colour, e.g. #000000 [default: black]
+      --light <COLOUR>   Light module colour, e.g. #ffffff [default: white]
+      --module-size <LEN>
+                         Physical module size such as 0.5mm or 1.5pt; sets the
+                         print size of SVG, PNG, EPS and PDF output
+      --dark-cmyk <C,M,Y,K>
+                         Dark colour in EPS and PDF as CMYK percentages
+      --light-cmyk <C,M,Y,K>
+                         Light colour in EPS and PDF as CMYK percentages
+      --module-shape <SHAPE>
+                         SVG data modules: square, rounded[:R], circle or
+                         connected[:R] [default: square]
+      --finder-ring <SHAPE>
+                         SVG finder rings: square, rounded[:R] or circle
+      --finder-eye <SHAPE>
+                         SVG finder centres: square, rounded[:R] or circle
+      --fill <FILL>      Dark module paint: solid, linear:<COLOUR>:<COLOUR>[:DEG]
+                         or radial:<COLOUR>:<COLOUR> [default: solid]
+      --finder-colour <COLOUR>
+                         Separate colour for the finder patterns
+      --allow-inverted   Accept a light colour darker than the dark colour
+      --logo <FILE>      Centre an .svg or .png logo over a cleared area
+      --logo-size <F>    Logo width as a fraction of the symbol [default: 0.2]
+      --raise-ec         Raise the error correction level until the logo fits
+                         instead of failing
+  -t, --terminal         Print a preview to the terminal
+  -h, --help             Print this help
+
+Batch options:
+  -d, --output-dir <DIR> Directory the symbols are written to
+      --format <FORMAT>  csv or jsonl [default: from the file extension]
+  -j, --threads <N>      Worker threads [default: available cores]
+      --report <FILE>    Write failures as CSV to FILE instead of stderr
+
+Capacity options:
+      --segment <MODE:LEN>
+                         Add a segment of LEN characters in numeric,
+                         alphanumeric, byte or kanji mode (repeatable)
+      --eci <N>          Include an ECI header for assignment N";
+
+type Command = fn(&Options) -> Result<(), CliError>;
+
+/// Why a command stopped early. A closed stdout, as with `qrgen ... | head`,
+/// is not a failure.
+#[derive(Debug)]
+enum CliError {
+    Message(String),
+    BrokenPipe,
+}
+
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        CliError::Message(message)
+    }
+}
+
+impl From<&str> for CliError {
+    fn from(message: &str) -> Self {
+        CliError::Message(message.to_string())
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::BrokenPipe => CliError::BrokenPipe,
+            _ => CliError::Message(format!("cannot write to stdout: {}", e)),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Options {
+    text: Option<String>,
+    input: Option<String>,
+    ec_level: ErrorCorrectionLevel,
+    version: Option<u8>,
+    mask: Option<u8>,
+    mode: Option<InputMode>,
+    output: Option<String>,
+    render: RenderOptions,
+    logo: Option<String>,
+    logo_size: f64,
+    raise_ec: bool,
+    terminal: bool,
+    output_dir: Option<String>,
+    batch_format: Option<BatchFormat>,
+    threads: usize,
+    report: Option<String>,
+    segments: Vec<(SegmentMode, usize)>,
+    eci: Option<u32>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            text: None,
+            input: None,
+            ec_level: ErrorCorrectionLevel::M,
+            version: None,
+            mask: None,
+            mode: None,
+            output: None,
+            render: RenderOptions::default(),
+            logo: None,
+            logo_size: 0.2,
+            raise_ec: false,
+            terminal: false,
+            output_dir: None,
+            batch_format: None,
+            threads: 0,
+            report: None,
+            segments: Vec::new(),
+            eci: None,
+        }
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{}' for {}", value, option))
+}
+
+/// Parses a length in millimetres, points or inches (`0.5mm`, `1.5pt`,
+/// `0.02in`) into millimetres. A bare number is taken as millimetres.
+fn parse_length(value: &str) -> Result<f64, String> {
+    let lower = value.to_ascii_lowercase();
+    let (number, per_unit) = if let Some(n) = lower.strip_suffix("mm") {
+        (n, 1.0)
+    } else if let Some(n) = lower.strip_suffix("pt") {
+        (n, 1.0 / POINTS_PER_MM)
+    } else if let Some(n) = lower.strip_suffix("in") {
+        (n, 25.4)
+    } else {
+        (lower.as_str(), 1.0)
+    };
+
+    number
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite() && *n > 0.0)
+        .map(|n| n * per_unit)
+        .ok_or_else(|| format!("invalid length '{}'", value))
+}
+
+fn parse_batch_format(value: &str) -> Result<BatchFormat, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "csv" => Ok(BatchFormat::Csv),
+        "jsonl" | "ndjson" => Ok(BatchFormat::JsonLines),
+        _ => Err(format!("unknown batch format '{}'", value)),
+    }
+}
+
+fn parse_mode(value: &str) -> Result<Option<InputMode>, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "auto" => Ok(None),
+        "numeric" => Ok(Some(InputMode::Numeric)),
+        "alphanumeric" => Ok(Some(InputMode::Alphanumeric)),
+        "byte" => Ok(Some(InputMode::Byte)),
+        _ => Err(format!("unknown mode '{}'", value)),
+    }
+}
+
+/// Parses a capacity plan segment such as `numeric:20`.
+fn parse_segment(value: &str) -> Result<(SegmentMode, usize), String> {
+    let (mode, length) = value
+        .split_once(':')
+        .ok_or_else(|| format!("invalid segment '{}' (expected MODE:LEN)", value))?;
+    let mode = match mode.to_ascii_lowercase().as_str() {
+        "numeric" => SegmentMode::Numeric,
+        "alphanumeric" => SegmentMode::Alphanumeric,
+        "byte" => SegmentMode::Byte,
+        "kanji" => SegmentMode::Kanji,
+        _ => return Err(format!("unknown mode '{}'", mode)),
+    };
+    Ok((mode, parse_number("--segment", length)?))
+}
+
+/// Parses the command line. Returns `Ok(None)` when help was requested.
+fn parse_args(args: &[String]) -> Result<Option<Options>, String> {
+    let mut options = Options::default();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        // Accept both `--option value` and `--option=value`.
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| iter.next().cloned())
+                .ok_or_else(|| format!("missing value for {}", name))
+        };
+
+        match name {
+            "-h" | "--help" => return Ok(None),
+            "-i" | "--input" => options.input = Some(value()?),
+            "-e" | "--ec" => {
+                options.ec_level = value()?.parse().map_err(|e| format!("{}", e))?;
+            }
+            "-v" | "--version" => options.version = Some(parse_number(name, &value()?)?),
+            "-m" | "--mask" => options.mask = Some(parse_number(name, &value()?)?),
+            "--mode" => options.mode = parse_mode(&value()?)?,
+            "-o" | "--output" => options.output = Some(value()?),
+            "-s" | "--scale" => options.render.scale = parse_number(name, &value()?)?,
+            "-b" | "--border" => options.render.border = parse_number(name, &value()?)?,
+            "--dark" => {
+                options.render.dark = value()?.parse::<Color>().map_err(|e| format!("{}", e))?;
+            }
+            "--light" => {
+                options.render.light = value()?.parse::<Color>().map_err(|e| format!("{}", e))?;
+            }
+            "--module-size" => options.render.module_size_mm = Some(parse_length(&value()?)?),
+            "--dark-cmyk" => {
+                options.render.dark_cmyk = Some(value()?.parse().map_err(|e| format!("{}", e))?);
+            }
+            "--light-cmyk" => {
+                options.render.light_cmyk = Some(value()?.parse().map_err(|e| format!("{}", e))?);
+            }
+            "--module-shape" => {
+                options.render.style.module = value()?.parse().map_err(|e| format!("{}", e))?;
+            }
+            "--finder-ring" => {
+                options.render.style.finder_ring =
+                    value()?.parse().map_err(|e| format!("{}", e))?;
+            }
+            "--finder-eye" => {
+                options.render.style.finder_eye = value()?.parse().map_err(|e| format!("{}", e))?;
+            }
+            "--fill" => {
+                options.render.fill = value()?.parse().map_err(|e| format!("{}", e))?;
+            }
+            "--finder-colour" | "--finder-color" => {
+                options.render.finder_color =
+                    Some(value()?.parse::<Color>().map_err(|e| format!("{}", e))?);
+            }
+            "--allow-inverted" => options.render.allow_inverted = true,
+            "--logo" => options.logo = Some(value()?),
+            "--logo-size" => options.logo_size = parse_number(name, &value()?)?,
+            "--raise-ec" => options.raise_ec = true,
+            "-t" | "--terminal" => options.terminal = true,
+            "-d" | "--output-dir" => options.output_dir = Some(value()?),
+            "--format" => options.batch_format = Some(parse_batch_format(&value()?)?),
+            "-j" | "--threads" => options.threads = parse_number(name, &value()?)?,
+            "--report" => options.report = Some(value()?),
+            "--segment" => options.segments.push(parse_segment(&value()?)?),
+            "--eci" => options.eci = Some(parse_number(name, &value()?)?),
+            "-" => options.input = Some("-".to_string()),
+            _ if name.starts_with('-') && name.len() > 1 => {
+                return Err(format!("unknown option '{}'", name));
+            }
+            _ => {
+                if options.text.is_some() {
+                    return Err(format!("unexpected argument '{}'", arg));
+                }
+                options.text = Some(arg.clone());
+            }
+        }
+    }
+
+    if options.text.is_some() && options.input.is_some() {
+        return Err("give either TEXT or --input, not both".to_string());
+    }
+    if options.render.scale == 0 {
+        return Err("--scale must be at least 1".to_string());
+    }
+    options.render.check_contrast().map_err(|e| e.to_string())?;
+    Ok(Some(options))
+}
+
+/// Reads the content to encode. A single trailing newline from a file or
+/// stdin is dropped, since it is almost never meant to be part of the code.
+fn read_content(options: &Options) -> Result<String, String> {
+    if let Some(text) = &options.text {
+        return Ok(text.clone());
+    }
+
+    let mut content = match options.input.as_deref() {
+        None | Some("-") => {
+            let mut buffer = String::new();
+            io::stdin()
+                .read_to_string(&mut buffer)
+                .map_err(|e| format!("cannot read stdin: {}", e))?;
+            buffer
+        }
+        Some(path) => {
+            std::fs::read_to_string(path).map_err(|e| format!("cannot read '{}': {}", path, e))?
+        }
+    };
+
+    if content.ends_with('\n') {
+        content.pop();
+        if content.ends_with('\r') {
+            content.pop();
+        }
+    }
+    Ok(content)
+}
+
+fn output_format(path: &str) -> Result<ImageFormat, String> {
+    if path == "-" {
+        return Ok(ImageFormat::Svg);
+    }
+
+    ImageFormat::from_path(path).ok_or_else(|| {
+        format!(
+            "cannot infer the image format of '{}' (use .svg, .png, .eps or .pdf)",
+            path
+        )
+    })
+}
 
 fn format_bit_string(bit_string: &str, chunk_size: usize) -> String {
     bit_string
@@ -12,134 +369,437 @@ fn format_bit_string(bit_string: &str, chunk_size: usize) -> String {
         .join(" ")
 }
 
-fn main() {
+fn format_codewords(codewords: &[u8], indent: &str) -> String {
+    codewords
+        .chunks(16)
+        .map(|line| {
+            let values: Vec<String> = line.iter().map(|c| format!("{:3}", c)).collect();
+            format!("{}{}", indent, values.join(" "))
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn build_data(options: &Options, content: &str) -> Result<QRData, String> {
     let mut qr_data = QRData::new();
+    qr_data.set_content(content).map_err(|e| e.to_string())?;
+    if let Some(mode) = options.mode {
+        qr_data.set_mode(mode).map_err(|e| e.to_string())?;
+    }
+    qr_data
+        .set_ec_level(options.ec_level)
+        .map_err(|e| e.to_string())?;
+    qr_data
+        .set_version(options.version)
+        .map_err(|e| e.to_string())?;
+    if qr_data.get_version().is_none() {
+        return Err(format!(
+            "content is too long for error correction level {:?}",
+            options.ec_level
+        ));
+    }
+    Ok(qr_data)
+}
 
-    let test_cases = vec![
-        "12345",
-        "ABC123",
-        "Hello, World!",
-        "Hello, 世界!",
-        "12345",
-        "123456789012345",
-        "9999999999999999999",
-        "HELLO WORLD",
-        "ABC123",
-        "HELLO WORLD:123",
-        "TEST-TEST/123:ABC",
-        "Hello, World!",
-        "Hello... World!!!",
-        "Test@email.com",
-        "https://test.com",
-        "Hello, 世界!",
-        "こんにちは",
-        "Café München",
-        "Hello 👋 World",
-        "",
-        "A",
-        " ",
-        "12A",
-        "123456",
-        "1234567",
-        "HELLO WORLD|L",
-        "HELLO WORLD|Q",
-        "HELLO WORLD|H",
-        "ABC123|Q",
-        "TEST DATA|L",
-        "SECURE DATA|H",
-        "99999|Q",
-    ];
-
-    for test in test_cases {
-        let (content, ec_level) = if test.contains('|') {
-            let parts: Vec<&str> = test.split('|').collect();
-            (
-                parts[0],
-                match parts[1] {
-                    "L" => ErrorCorrectionLevel::L,
-                    "Q" => ErrorCorrectionLevel::Q,
-                    "H" => ErrorCorrectionLevel::H,
-                    _ => ErrorCorrectionLevel::M,
-                },
-            )
+fn run(options: &Options) -> Result<(), CliError> {
+    let mut out = io::stdout().lock();
+    let content = read_content(options)?;
+    let format = options.output.as_deref().map(output_format).transpose()?;
+    options.render.check_contrast().map_err(|e| e.to_string())?;
+
+    let mut qr_data = build_data(options, &content)?;
+    let mut render_options = options.render.clone();
+    let matrix = match &options.logo {
+        Some(path) => {
+            let image = LogoImage::from_file(path).map_err(|e| e.to_string())?;
+            let policy = if options.raise_ec {
+                LogoPolicy::RaiseLevel
+            } else {
+                LogoPolicy::Refuse
+            };
+            let placement = Logo::new(image)
+                .size(options.logo_size)
+                .place(&mut qr_data, options.mask, policy)
+                .map_err(|e| e.to_string())?;
+            render_options.overlay = Some(placement.overlay);
+            placement.matrix
+        }
+        None => qr_data.generate(options.mask).map_err(|e| e.to_string())?,
+    };
+
+    if let (Some(path), Some(format)) = (options.output.as_deref(), format) {
+        let bytes = render::render(&matrix, format, &render_options);
+
+        if path == "-" {
+            out.write_all(&bytes)?;
+        } else {
+            std::fs::write(path, bytes).map_err(|e| format!("cannot write '{}': {}", path, e))?;
+        }
+    }
+
+    if options.terminal || options.output.is_none() {
+        write!(
+            out,
+            "{}",
+            render::to_terminal(&matrix, options.render.border)
+        )?;
+    }
+    Ok(())
+}
+
+/// Prints each encoding stage, from the segment bits to the final matrix.
+fn run_debug(options: &Options) -> Result<(), CliError> {
+    let mut out = io::stdout().lock();
+    let content = read_content(options)?;
+    let mut qr_data = build_data(options, &content)?;
+    let version = qr_data.get_version().unwrap_or(0);
+    let ec_level = qr_data.get_ec_level();
+    let mode = qr_data.get_input().get_mode();
+
+    writeln!(out, "Content: {:?}", content)?;
+    writeln!(
+        out,
+        "Mode: {:?}  EC level: {:?}  Version: {} ({}x{})",
+        mode,
+        ec_level,
+        version,
+        version as usize * 4 + 17,
+        version as usize * 4 + 17
+    )?;
+
+    let (mode_indicator, char_count, data_bits) = qr_data.get_data();
+    let mode_bits = format!("{:04b}", mode_indicator);
+    let count_bits = format!(
+        "{:0width$b}",
+        char_count,
+        width = mode.character_count_bits(version)
+    );
+
+    writeln!(out, "\nSegments:")?;
+    writeln!(
+        out,
+        "  #1 {:?}, {} characters",
+        mode,
+        qr_data.get_input().get_character_count()
+    )?;
+    writeln!(
+        out,
+        "    Mode indicator:  {} ({} bits)",
+        format_bit_string(&mode_bits, 4),
+        mode_bits.len()
+    )?;
+    writeln!(
+        out,
+        "    Character count: {} ({} bits)",
+        format_bit_string(&count_bits, 4),
+        count_bits.len()
+    )?;
+    writeln!(
+        out,
+        "    Data:            {} ({} bits)",
+        format_bit_string(&data_bits, 4),
+        data_bits.len()
+    )?;
+
+    let final_bits = qr_data.get_final_data().map_err(|e| e.to_string())?;
+    let segment_len = mode_bits.len() + count_bits.len() + data_bits.len();
+    let terminator = final_bits.get(segment_len..).unwrap_or("");
+    let bit_padding = (8 - final_bits.len() % 8) % 8;
+    writeln!(
+        out,
+        "  Terminator:        {} ({} bits)",
+        format_bit_string(terminator, 4),
+        terminator.len()
+    )?;
+    writeln!(
+        out,
+        "  Bit padding:       {} ({} bits)",
+        "0".repeat(bit_padding),
+        bit_padding
+    )?;
+
+    let data_codewords = qr_data.get_data_codewords().map_err(|e| e.to_string())?;
+    let pad_codewords = data_codewords.len() - (final_bits.len() + bit_padding) / 8;
+    writeln!(
+        out,
+        "\nData codewords ({}, {} pad):",
+        data_codewords.len(),
+        pad_codewords
+    )?;
+    writeln!(out, "{}", format_codewords(&data_codewords, "  "))?;
+
+    let layout = BlockLayout::new(version, ec_level).map_err(|e| e.to_string())?;
+    let blocks = split_blocks(&data_codewords, &layout).map_err(|e| e.to_string())?;
+    writeln!(
+        out,
+        "\nBlocks ({} blocks, {} EC codewords each):",
+        layout.num_blocks, layout.ecc_per_block
+    )?;
+    for (i, block) in blocks.iter().enumerate() {
+        writeln!(out, "  Block {} data ({}):", i + 1, block.data.len())?;
+        writeln!(out, "{}", format_codewords(&block.data, "    "))?;
+        writeln!(out, "  Block {} EC ({}):", i + 1, block.ecc.len())?;
+        writeln!(out, "{}", format_codewords(&block.ecc, "    "))?;
+    }
+
+    let codewords = interleave(&blocks);
+    writeln!(out, "\nInterleaved codewords ({}):", codewords.len())?;
+    writeln!(out, "{}", format_codewords(&codewords, "  "))?;
+
+    let unmasked = QRMatrix::with_data(version, ec_level, &codewords).map_err(|e| e.to_string())?;
+    let matrix = qr_data.generate(options.mask).map_err(|e| e.to_string())?;
+    let selected = matrix.get_mask().unwrap_or(0);
+    writeln!(out, "\nMask penalties:")?;
+    for (mask, penalty) in unmasked.mask_penalties().iter().enumerate() {
+        let marker = if mask as u8 == selected {
+            "  <- selected"
         } else {
-            (test, ErrorCorrectionLevel::M)
+            ""
         };
+        writeln!(out, "  {}: {}{}", mask, penalty, marker)?;
+    }
 
-        println!("\nTesting: {}", content);
-        println!("{}", "-".repeat(40));
-
-        match qr_data.set_content(content) {
-            Ok(mode) => {
-                qr_data.set_ec_level(ec_level).unwrap();
-                println!("Mode: {:?}", qr_data.get_input().get_mode());
-                println!("EC Level: {:?}", qr_data.get_ec_level());
-                println!("Version: {:?}", qr_data.get_version().unwrap());
-                let required_bits = qr_data.get_required_bits();
-                println!("Required Bits: {}", required_bits);
-
-                match qr_data.validate_length() {
-                    Ok(_) => println!("Length validated"),
-                    Err(e) => println!("Length validation failed: ({})", e),
-                }
+    let format_bits = format!("{:015b}", format_information(ec_level, selected));
+    writeln!(
+        out,
+        "\nFormat information: {} (EC {:?}, mask {})",
+        format_bit_string(&format_bits, 5),
+        ec_level,
+        selected
+    )?;
+    match version_information(version) {
+        Some(bits) => {
+            let version_bits = format!("{:018b}", bits);
+            writeln!(
+                out,
+                "Version information: {}",
+                format_bit_string(&version_bits, 6)
+            )?;
+        }
+        None => writeln!(out, "Version information: none (version < 7)")?,
+    }
 
-                let (mode_ind, char_count, encoded_data) = qr_data.get_data();
-
-                let count_str = match qr_data.get_input().get_mode() {
-                    InputMode::Numeric => match qr_data.get_version() {
-                        Some(v) if v <= 9 => format!("{:010b}", char_count),
-                        Some(v) if v <= 26 => format!("{:012b}", char_count),
-                        Some(_) => format!("{:014b}", char_count),
-                        None => format!("{:b}", char_count),
-                    },
-                    InputMode::Alphanumeric => match qr_data.get_version() {
-                        Some(v) if v <= 9 => format!("{:09b}", char_count),
-                        Some(v) if v <= 26 => format!("{:011b}", char_count),
-                        Some(_) => format!("{:013b}", char_count),
-                        None => format!("{:b}", char_count),
-                    },
-                    InputMode::Byte => match qr_data.get_version() {
-                        Some(v) if v <= 9 => format!("{:08b}", char_count),
-                        Some(_) => format!("{:016b}", char_count),
-                        None => format!("{:b}", char_count),
-                    },
-                };
-
-                let mode = format!("{:04b}", mode_ind);
-                let initial_data = format!("{}{}{}", mode, count_str, encoded_data);
-
-                let final_data = {
-                    let mut d = initial_data.clone();
-                    let remaining_bits = required_bits as usize - d.len();
-                    if remaining_bits > 0 {
-                        let terminator_length = remaining_bits.min(4);
-                        d.push_str(&"0".repeat(terminator_length));
-                    }
-                    d
-                };
-
-                println!("\nDetailed Bit String:");
-                println!("Mode Indicator: {} ({} bits)", format_bit_string(&mode, 4), mode.len());
-                println!("Character Count: {} ({} bits)", format_bit_string(&count_str, 4), count_str.len());
-
-                println!(
-                    "Encoded Data: {} ({} bits)",
-                    format_bit_string(&encoded_data, 4),
-                    encoded_data.len()
-                );
-                let terminator = final_data.get(initial_data.len()..).unwrap_or("");
-                println!(
-                    "Terminator: {} ({} bits)",
-                    format_bit_string(&terminator, 4),
-                    terminator.len()
-                );
-                println!(
-                    "\nFinal String ({} bits):",
-                    final_data.len()
-                );
-                println!("{}", format_bit_string(&final_data, 8));
+    writeln!(out, "\nMatrix (upper case dark, lower case light):")?;
+    writeln!(
+        out,
+        "  F finder  S separator  T timing  A alignment  K dark module"
+    )?;
+    writeln!(
+        out,
+        "  I format  V version    # data (dark)  . data (light)\n"
+    )?;
+    write!(out, "{}", render::to_annotated(&matrix))?;
+    Ok(())
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn format_failures(failures: &[BatchFailure]) -> String {
+    let mut report = String::from("line,filename,error\n");
+    for failure in failures {
+        report.push_str(&format!(
+            "{},{},{}\n",
+            failure.line,
+            csv_field(failure.filename.as_deref().unwrap_or("")),
+            csv_field(&failure.error)
+        ));
+    }
+    report
+}
+
+/// Generates every record of a CSV or JSON-lines file into a directory.
+fn run_batch(options: &Options) -> Result<(), CliError> {
+    let path = options
+        .input
+        .as_deref()
+        .or(options.text.as_deref())
+        .ok_or("batch needs an input file")?;
+    let output_dir = options
+        .output_dir
+        .as_deref()
+        .ok_or("batch needs --output-dir")?;
+    let format = match options.batch_format {
+        Some(format) => format,
+        None => BatchFormat::from_path(path).ok_or_else(|| {
+            format!(
+                "cannot infer the record format of '{}' (use --format)",
+                path
+            )
+        })?,
+    };
+
+    let input = if path == "-" {
+        let mut buffer = String::new();
+        io::stdin()
+            .read_to_string(&mut buffer)
+            .map_err(|e| format!("cannot read stdin: {}", e))?;
+        buffer
+    } else {
+        std::fs::read_to_string(path).map_err(|e| format!("cannot read '{}': {}", path, e))?
+    };
+    std::fs::create_dir_all(output_dir)
+        .map_err(|e| format!("cannot create '{}': {}", output_dir, e))?;
+
+    let (records, mut failures) = batch::parse_records(&input, format);
+    let batch_options = BatchOptions {
+        ec_level: options.ec_level,
+        render: options.render.clone(),
+        threads: options.threads,
+        ..BatchOptions::default()
+    };
+    let report = batch::generate_batch(&records, Path::new(output_dir), &batch_options);
+    failures.extend(report.failures);
+    failures.sort_by_key(|f| f.line);
+
+    eprintln!(
+        "qrgen: {} written, {} failed",
+        report.written.len(),
+        failures.len()
+    );
+    if failures.is_empty() {
+        return Ok(());
+    }
+
+    let failure_report = format_failures(&failures);
+    match options.report.as_deref() {
+        Some(report_path) => std::fs::write(report_path, failure_report)
+            .map_err(|e| format!("cannot write '{}': {}", report_path, e))?,
+        None => eprint!("{}", failure_report),
+    }
+    Err(format!("{} records failed", failures.len()).into())
+}
+
+/// Reads a symbol from an image file and prints its content.
+fn run_decode(options: &Options) -> Result<(), CliError> {
+    let path = options
+        .input
+        .as_deref()
+        .or(options.text.as_deref())
+        .ok_or("decode needs an image file")?;
+    let image = if path == "-" {
+        let mut bytes = Vec::new();
+        io::stdin()
+            .read_to_end(&mut bytes)
+            .map_err(|e| format!("cannot read stdin: {}", e))?;
+        image::read_image(&bytes)
+    } else {
+        image::load_image(path)
+    }
+    .map_err(|e| e.to_string())?;
+
+    let decoded = decode_image(&image).map_err(|e| e.to_string())?;
+    let mut out = io::stdout().lock();
+    out.write_all(&decoded.content)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Prints which versions and levels hold the content, or with only a
+/// version, how much each mode fits into it.
+fn run_capacity(options: &Options) -> Result<(), CliError> {
+    let mut out = io::stdout().lock();
+    let mut plan = if options.text.is_some() || options.input.is_some() {
+        ContentPlan::for_content(&read_content(options)?).map_err(|e| e.to_string())?
+    } else {
+        ContentPlan::new()
+    };
+    for &(mode, length) in &options.segments {
+        plan = plan.segment(mode, length);
+    }
+    if let Some(assignment) = options.eci {
+        plan = plan.eci(assignment);
+    }
+
+    if plan.segments.is_empty() {
+        let version = options
+            .version
+            .ok_or("capacity needs TEXT, --input, --segment or --version")?;
+        writeln!(
+            out,
+            "Version {} ({}x{}), maximum characters per mode:",
+            version,
+            version as usize * 4 + 17,
+            version as usize * 4 + 17
+        )?;
+        writeln!(
+            out,
+            "  {:<14}{:>7}{:>7}{:>7}{:>7}",
+            "Mode", "L", "M", "Q", "H"
+        )?;
+        for mode in SegmentMode::ALL {
+            let mut row = format!("  {:<14}", format!("{:?}", mode).to_lowercase());
+            for level in capacity::LEVELS {
+                let length =
+                    capacity::max_length(version, level, mode, &plan).map_err(|e| e.to_string())?;
+                row.push_str(&format!("{:>7}", length));
             }
-            Err(e) => println!("Error: {}", e),
+            writeln!(out, "{}", row)?;
+        }
+        return Ok(());
+    }
+
+    let fits = capacity::fits(&plan).map_err(|e| e.to_string())?;
+    let fits: Vec<_> = fits
+        .into_iter()
+        .filter(|fit| options.version.is_none_or(|v| fit.version == v))
+        .collect();
+    if fits.is_empty() {
+        return Err("the content does not fit any version".into());
+    }
+    writeln!(
+        out,
+        "  {:<9}{:<10}{:<7}{:>10}{:>10}{:>11}",
+        "Version", "Size", "Level", "Used", "Capacity", "Remaining"
+    )?;
+    for fit in fits {
+        writeln!(
+            out,
+            "  {:<9}{:<10}{:<7}{:>10}{:>10}{:>11}",
+            fit.version,
+            format!("{}x{}", fit.size, fit.size),
+            format!("{:?}", fit.ec_level),
+            fit.used_bits,
+            fit.capacity_bits,
+            fit.remaining_bits()
+        )?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (command, args): (Command, &[String]) = match args.first().map(String::as_str) {
+        Some("debug") => (run_debug, &args[1..]),
+        Some("batch") => (run_batch, &args[1..]),
+        Some("decode") => (run_decode, &args[1..]),
+        Some("capacity") => (run_capacity, &args[1..]),
+        _ => (run, &args[..]),
+    };
+
+    let options = match parse_args(args) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            // Nothing useful to do if stdout is gone.
+            let _ = writeln!(io::stdout().lock(), "{}", USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("qrgen: {}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    match command(&options) {
+        Ok(()) | Err(CliError::BrokenPipe) => {}
+        Err(CliError::Message(e)) => {
+            eprintln!("qrgen: {}", e);
+            process::exit(1);
         }
     }
 }
@@ -0,0 +1,615 @@
+use crate::encoder::ErrorCorrectionLevel;
+#[cfg(feature = "alloc")]
+use crate::error::QRError;
+#[cfg(feature = "alloc")]
+use alloc::{format, vec, vec::Vec};
+
+const PENALTY_N1: i32 = 3;
+const PENALTY_N2: i32 = 3;
+const PENALTY_N3: i32 = 40;
+const PENALTY_N4: i32 = 10;
+
+/// What a module in the symbol is used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleKind {
+    FinderPattern,
+    Separator,
+    TimingPattern,
+    AlignmentPattern,
+    DarkModule,
+    FormatInfo,
+    VersionInfo,
+    Data,
+}
+
+impl ModuleKind {
+    pub fn is_function(self) -> bool {
+        self != ModuleKind::Data
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QRMatrix {
+    version: u8,
+    size: usize,
+    ec_level: ErrorCorrectionLevel,
+    mask: Option<u8>,
+    modules: Vec<bool>,
+    kinds: Vec<ModuleKind>,
+}
+
+#[cfg(feature = "alloc")]
+impl QRMatrix {
+    /// Creates a symbol of the given version with every function pattern drawn
+    /// and the format information left light.
+    pub fn new(version: u8, ec_level: ErrorCorrectionLevel) -> Result<Self, QRError> {
+        if !(1..=40).contains(&version) {
+            return Err(QRError::InvalidVersion(format!(
+                "Version {} is outside 1-40",
+                version
+            )));
+        }
+
+        let size = version as usize * 4 + 17;
+        let mut matrix = QRMatrix {
+            version,
+            size,
+            ec_level,
+            mask: None,
+            modules: vec![false; size * size],
+            kinds: vec![ModuleKind::Data; size * size],
+        };
+        draw_function_patterns(&mut matrix, version);
+        Ok(matrix)
+    }
+
+    /// Creates a symbol with the codewords placed but no mask applied yet.
+    pub fn with_data(
+        version: u8,
+        ec_level: ErrorCorrectionLevel,
+        codewords: &[u8],
+    ) -> Result<Self, QRError> {
+        let mut matrix = QRMatrix::new(version, ec_level)?;
+        matrix.place_data(codewords)?;
+        Ok(matrix)
+    }
+
+    /// Builds the complete symbol from the interleaved codewords.
+    ///
+    /// When `mask` is `None` every mask is tried and the one with the lowest
+    /// penalty score is kept.
+    pub fn build(
+        version: u8,
+        ec_level: ErrorCorrectionLevel,
+        codewords: &[u8],
+        mask: Option<u8>,
+    ) -> Result<Self, QRError> {
+        let mut matrix = QRMatrix::with_data(version, ec_level, codewords)?;
+
+        let mask = match mask {
+            Some(m) if m > 7 => {
+                return Err(QRError::InvalidInput(format!("Mask {} is outside 0-7", m)));
+            }
+            Some(m) => m,
+            None => {
+                let penalties = matrix.mask_penalties();
+                (0..8u8).min_by_key(|&m| penalties[m as usize]).unwrap_or(0)
+            }
+        };
+
+        matrix.apply_mask(mask);
+        matrix.draw_format_bits(mask);
+        matrix.mask = Some(mask);
+        Ok(matrix)
+    }
+
+    /// Rebuilds a symbol from module colours read back, e.g. from an image.
+    /// `modules` holds one entry per module in row-major order.
+    pub fn from_modules(
+        version: u8,
+        ec_level: ErrorCorrectionLevel,
+        mask: Option<u8>,
+        modules: Vec<bool>,
+    ) -> Result<Self, QRError> {
+        let mut matrix = QRMatrix::new(version, ec_level)?;
+        if modules.len() != matrix.size * matrix.size {
+            return Err(QRError::InvalidLength(format!(
+                "Version {} needs {} modules, got {}",
+                version,
+                matrix.size * matrix.size,
+                modules.len()
+            )));
+        }
+        matrix.modules = modules;
+        matrix.mask = mask;
+        Ok(matrix)
+    }
+
+    pub fn get_version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get_ec_level(&self) -> ErrorCorrectionLevel {
+        self.ec_level
+    }
+
+    pub fn get_mask(&self) -> Option<u8> {
+        self.mask
+    }
+
+    /// Whether the module at column `x`, row `y` is dark.
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    pub fn get_kind(&self, x: usize, y: usize) -> ModuleKind {
+        self.kinds[y * self.size + x]
+    }
+
+    pub fn is_function(&self, x: usize, y: usize) -> bool {
+        self.get_kind(x, y).is_function()
+    }
+
+    pub fn set_module(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+    }
+
+    /// Draws both copies of the format information for the given mask.
+    pub fn draw_format_bits(&mut self, mask: u8) {
+        draw_format_bits(self, self.ec_level, mask);
+    }
+
+    /// Data module coordinates in placement order, following the two-column
+    /// zig-zag from the bottom-right corner.
+    pub fn data_positions(&self) -> Vec<(usize, usize)> {
+        zigzag(self.size)
+            .filter(|&(x, y)| !self.is_function(x, y))
+            .collect()
+    }
+
+    /// Writes the codewords into the data area. Remainder bits stay light.
+    pub fn place_data(&mut self, codewords: &[u8]) -> Result<(), QRError> {
+        let capacity = zigzag(self.size)
+            .filter(|&(x, y)| !self.is_function(x, y))
+            .count();
+        if codewords.len() * 8 > capacity {
+            return Err(QRError::EncodingError(format!(
+                "{} codewords do not fit into version {}",
+                codewords.len(),
+                self.version
+            )));
+        }
+
+        place_codewords(self, codewords);
+        Ok(())
+    }
+
+    /// XORs the mask pattern over every data module. Applying the same mask
+    /// twice restores the original modules.
+    pub fn apply_mask(&mut self, mask: u8) {
+        apply_mask(self, mask);
+    }
+
+    /// Penalty score of each of the eight masks applied to the current data.
+    pub fn mask_penalties(&self) -> [i32; 8] {
+        mask_penalties(&mut self.clone(), self.ec_level)
+    }
+
+    /// Total penalty score of the symbol as it currently stands.
+    pub fn penalty_score(&self) -> i32 {
+        penalty_score(self)
+    }
+}
+
+/// Module storage the symbol is drawn into, shared by [`QRMatrix`] and the
+/// fixed-buffer encoder in `fixed`.
+pub(crate) trait ModuleGrid {
+    fn size(&self) -> usize;
+
+    fn is_dark(&self, x: usize, y: usize) -> bool;
+
+    fn set(&mut self, x: usize, y: usize, dark: bool);
+
+    /// Sets a module that belongs to a function pattern.
+    fn set_function(&mut self, x: usize, y: usize, dark: bool, kind: ModuleKind);
+
+    fn is_function(&self, x: usize, y: usize) -> bool;
+}
+
+#[cfg(feature = "alloc")]
+impl ModuleGrid for QRMatrix {
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn is_dark(&self, x: usize, y: usize) -> bool {
+        QRMatrix::is_dark(self, x, y)
+    }
+
+    fn set(&mut self, x: usize, y: usize, dark: bool) {
+        self.set_module(x, y, dark);
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool, kind: ModuleKind) {
+        let index = y * self.size + x;
+        self.modules[index] = dark;
+        self.kinds[index] = kind;
+    }
+
+    fn is_function(&self, x: usize, y: usize) -> bool {
+        QRMatrix::is_function(self, x, y)
+    }
+}
+
+/// Draws every function pattern of `version`, with the format areas
+/// reserved but left light.
+pub(crate) fn draw_function_patterns(grid: &mut impl ModuleGrid, version: u8) {
+    let size = grid.size();
+
+    for i in 0..size {
+        grid.set_function(6, i, i % 2 == 0, ModuleKind::TimingPattern);
+        grid.set_function(i, 6, i % 2 == 0, ModuleKind::TimingPattern);
+    }
+
+    draw_finder_pattern(grid, 3, 3);
+    draw_finder_pattern(grid, size - 4, 3);
+    draw_finder_pattern(grid, 3, size - 4);
+
+    let (positions, count) = alignment_positions(version);
+    for (i, &x) in positions[..count].iter().enumerate() {
+        for (j, &y) in positions[..count].iter().enumerate() {
+            if !overlaps_finder(i, j, count) {
+                draw_alignment_pattern(grid, x, y);
+            }
+        }
+    }
+
+    // Reserve the format areas; the real bits are drawn once the mask is known.
+    for i in 0..15 {
+        let (a, b) = format_bit_positions(size, i);
+        grid.set_function(a.0, a.1, false, ModuleKind::FormatInfo);
+        grid.set_function(b.0, b.1, false, ModuleKind::FormatInfo);
+    }
+    grid.set_function(8, size - 8, true, ModuleKind::DarkModule);
+
+    draw_version_bits(grid, version);
+}
+
+fn draw_finder_pattern(grid: &mut impl ModuleGrid, x: usize, y: usize) {
+    let size = grid.size() as i32;
+    for dy in -4i32..=4 {
+        for dx in -4i32..=4 {
+            let xx = x as i32 + dx;
+            let yy = y as i32 + dy;
+            if xx < 0 || yy < 0 || xx >= size || yy >= size {
+                continue;
+            }
+
+            let distance = dx.abs().max(dy.abs());
+            let kind = if distance == 4 {
+                ModuleKind::Separator
+            } else {
+                ModuleKind::FinderPattern
+            };
+            grid.set_function(
+                xx as usize,
+                yy as usize,
+                distance != 2 && distance != 4,
+                kind,
+            );
+        }
+    }
+}
+
+fn draw_alignment_pattern(grid: &mut impl ModuleGrid, x: usize, y: usize) {
+    for dy in -2i32..=2 {
+        for dx in -2i32..=2 {
+            let distance = dx.abs().max(dy.abs());
+            grid.set_function(
+                (x as i32 + dx) as usize,
+                (y as i32 + dy) as usize,
+                distance != 1,
+                ModuleKind::AlignmentPattern,
+            );
+        }
+    }
+}
+
+/// Draws both copies of the format information for the given mask.
+pub(crate) fn draw_format_bits(
+    grid: &mut impl ModuleGrid,
+    ec_level: ErrorCorrectionLevel,
+    mask: u8,
+) {
+    let bits = format_information(ec_level, mask);
+
+    for i in 0..15 {
+        let dark = (bits >> i) & 1 != 0;
+        let (a, b) = format_bit_positions(grid.size(), i);
+        grid.set_function(a.0, a.1, dark, ModuleKind::FormatInfo);
+        grid.set_function(b.0, b.1, dark, ModuleKind::FormatInfo);
+    }
+}
+
+fn draw_version_bits(grid: &mut impl ModuleGrid, version: u8) {
+    let Some(bits) = version_information(version) else {
+        return;
+    };
+
+    let size = grid.size();
+    for i in 0..18 {
+        let dark = (bits >> i) & 1 != 0;
+        let a = size - 11 + i % 3;
+        let b = i / 3;
+        grid.set_function(a, b, dark, ModuleKind::VersionInfo);
+        grid.set_function(b, a, dark, ModuleKind::VersionInfo);
+    }
+}
+
+/// Every module coordinate in placement order, function modules included:
+/// two-column strips from the right, alternating upwards and downwards and
+/// skipping the vertical timing pattern column.
+fn zigzag(size: usize) -> impl Iterator<Item = (usize, usize)> {
+    (0..(size - 1) / 2).flat_map(move |pair| {
+        let base = size - 1 - 2 * pair;
+        let right = if base <= 6 { base - 1 } else { base };
+        let upward = (right + 1) & 2 == 0;
+        (0..size).flat_map(move |vert| {
+            let y = if upward { size - 1 - vert } else { vert };
+            [(right, y), (right - 1, y)]
+        })
+    })
+}
+
+/// Writes the codewords into the data modules in placement order. Data
+/// modules beyond the last codeword are left light.
+pub(crate) fn place_codewords(grid: &mut impl ModuleGrid, codewords: &[u8]) {
+    let mut i = 0;
+    for (x, y) in zigzag(grid.size()) {
+        if grid.is_function(x, y) {
+            continue;
+        }
+        let dark = codewords
+            .get(i / 8)
+            .is_some_and(|byte| (byte >> (7 - i % 8)) & 1 != 0);
+        grid.set(x, y, dark);
+        i += 1;
+    }
+}
+
+/// XORs the mask pattern over every data module.
+pub(crate) fn apply_mask(grid: &mut impl ModuleGrid, mask: u8) {
+    for y in 0..grid.size() {
+        for x in 0..grid.size() {
+            if !grid.is_function(x, y) && mask_applies(mask, x, y) {
+                let dark = grid.is_dark(x, y);
+                grid.set(x, y, !dark);
+            }
+        }
+    }
+}
+
+/// Penalty score of each mask with its format information, trying each in
+/// place and undoing it again. The format bits of mask 7 are left drawn.
+pub(crate) fn mask_penalties(
+    grid: &mut impl ModuleGrid,
+    ec_level: ErrorCorrectionLevel,
+) -> [i32; 8] {
+    let mut penalties = [0; 8];
+
+    for (mask, penalty) in penalties.iter_mut().enumerate() {
+        apply_mask(grid, mask as u8);
+        draw_format_bits(grid, ec_level, mask as u8);
+        *penalty = penalty_score(grid);
+        apply_mask(grid, mask as u8);
+    }
+    penalties
+}
+
+/// Total penalty score of the symbol as it currently stands.
+pub(crate) fn penalty_score(grid: &impl ModuleGrid) -> i32 {
+    let size = grid.size();
+    let mut result = 0;
+
+    for y in 0..size {
+        result += line_penalty(size, |i| grid.is_dark(i, y));
+    }
+    for x in 0..size {
+        result += line_penalty(size, |i| grid.is_dark(x, i));
+    }
+
+    let mut dark = 0;
+    for y in 0..size {
+        for x in 0..size {
+            let color = grid.is_dark(x, y);
+            dark += color as i32;
+            if x + 1 < size
+                && y + 1 < size
+                && color == grid.is_dark(x + 1, y)
+                && color == grid.is_dark(x, y + 1)
+                && color == grid.is_dark(x + 1, y + 1)
+            {
+                result += PENALTY_N2;
+            }
+        }
+    }
+
+    let total = (size * size) as i32;
+    let k = ((dark * 20 - total * 10).abs() + total - 1) / total - 1;
+    result + k * PENALTY_N4
+}
+
+/// Adjacent-run and finder-like penalties for one row or column.
+fn line_penalty(size: usize, module: impl Fn(usize) -> bool) -> i32 {
+    let mut result = 0;
+    let mut run_color = false;
+    let mut run_length = 0;
+    let mut history = [0i32; 7];
+
+    for i in 0..size {
+        if module(i) == run_color {
+            run_length += 1;
+            if run_length == 5 {
+                result += PENALTY_N1;
+            } else if run_length > 5 {
+                result += 1;
+            }
+        } else {
+            add_run_history(size, run_length, &mut history);
+            if !run_color {
+                result += finder_like_count(&history) * PENALTY_N3;
+            }
+            run_color = module(i);
+            run_length = 1;
+        }
+    }
+
+    if run_color {
+        add_run_history(size, run_length, &mut history);
+        run_length = 0;
+    }
+    run_length += size as i32;
+    add_run_history(size, run_length, &mut history);
+    result + finder_like_count(&history) * PENALTY_N3
+}
+
+fn add_run_history(size: usize, mut run_length: i32, history: &mut [i32; 7]) {
+    if history[0] == 0 {
+        // Treat the area outside the symbol as light quiet zone.
+        run_length += size as i32;
+    }
+    history.copy_within(0..6, 1);
+    history[0] = run_length;
+}
+
+/// Counts 1:1:3:1:1 patterns with four light modules on either side.
+fn finder_like_count(history: &[i32; 7]) -> i32 {
+    let n = history[1];
+    let core =
+        n > 0 && history[2] == n && history[3] == n * 3 && history[4] == n && history[5] == n;
+
+    (core && history[0] >= n * 4 && history[6] >= n) as i32
+        + (core && history[6] >= n * 4 && history[0] >= n) as i32
+}
+
+pub fn mask_applies(mask: u8, x: usize, y: usize) -> bool {
+    match mask {
+        0 => (x + y).is_multiple_of(2),
+        1 => y.is_multiple_of(2),
+        2 => x.is_multiple_of(3),
+        3 => (x + y).is_multiple_of(3),
+        4 => (x / 3 + y / 2).is_multiple_of(2),
+        5 => x * y % 2 + x * y % 3 == 0,
+        6 => (x * y % 2 + x * y % 3).is_multiple_of(2),
+        7 => ((x + y) % 2 + x * y % 3).is_multiple_of(2),
+        _ => false,
+    }
+}
+
+/// Centre coordinates of the alignment patterns, in ascending order.
+#[cfg(feature = "alloc")]
+pub fn alignment_pattern_positions(version: u8) -> Vec<usize> {
+    let (positions, count) = alignment_positions(version);
+    positions[..count].to_vec()
+}
+
+/// The alignment pattern centres in the first `count` entries, without
+/// allocating. Version 1 has none.
+pub(crate) fn alignment_positions(version: u8) -> ([usize; 7], usize) {
+    let mut positions = [0; 7];
+    if version <= 1 {
+        return (positions, 0);
+    }
+
+    let version = version as usize;
+    let size = version * 4 + 17;
+    let count = version / 7 + 2;
+    let step = if version == 32 {
+        26
+    } else {
+        (version * 4 + count * 2 + 1) / (count * 2 - 2) * 2
+    };
+
+    positions[0] = 6;
+    for i in 1..count {
+        positions[count - i] = size - 7 - (i - 1) * step;
+    }
+    (positions, count)
+}
+
+/// Whether the alignment pattern in row `i` and column `j` of the grid of
+/// centres would sit on a finder pattern and is left out.
+fn overlaps_finder(i: usize, j: usize, count: usize) -> bool {
+    (i == 0 && (j == 0 || j == count - 1)) || (i == count - 1 && j == 0)
+}
+
+/// Whether the module belongs to a function pattern, worked out from the
+/// version alone.
+pub(crate) fn is_function_module(version: u8, x: usize, y: usize) -> bool {
+    let size = version as usize * 4 + 17;
+    // Finders with their separators and the format areas, which include
+    // the dark module.
+    if (x < 9 && (y < 9 || y >= size - 8)) || (x >= size - 8 && y < 9) {
+        return true;
+    }
+    if x == 6 || y == 6 {
+        return true;
+    }
+    if version >= 7
+        && ((x >= size - 11 && x < size - 8 && y < 6) || (y >= size - 11 && y < size - 8 && x < 6))
+    {
+        return true;
+    }
+
+    let (positions, count) = alignment_positions(version);
+    let near = |c: usize| positions[..count].iter().position(|&p| c.abs_diff(p) <= 2);
+    match (near(x), near(y)) {
+        (Some(i), Some(j)) => !overlaps_finder(i, j, count),
+        _ => false,
+    }
+}
+
+/// The 15-bit BCH-protected format information word, already XOR-masked.
+pub fn format_information(ec_level: ErrorCorrectionLevel, mask: u8) -> u32 {
+    let data = ec_level.format_bits() << 3 | mask as u32;
+    let mut rem = data;
+    for _ in 0..10 {
+        rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+    }
+    (data << 10 | rem) ^ 0x5412
+}
+
+/// The 18-bit version information word, present from version 7 upwards.
+pub fn version_information(version: u8) -> Option<u32> {
+    if version < 7 {
+        return None;
+    }
+
+    let mut rem = version as u32;
+    for _ in 0..12 {
+        rem = (rem << 1) ^ ((rem >> 11) * 0x1F25);
+    }
+    Some((version as u32) << 12 | rem)
+}
+
+/// Positions `(x, y)` of format bit `i` in the top-left copy and in the split copy.
+pub(crate) fn format_bit_positions(size: usize, i: usize) -> ((usize, usize), (usize, usize)) {
+    let first = match i {
+        0..=5 => (8, i),
+        6 => (8, 7),
+        7 => (8, 8),
+        8 => (7, 8),
+        _ => (14 - i, 8),
+    };
+    let second = if i < 8 {
+        (size - 1 - i, 8)
+    } else {
+        (8, size - 15 + i)
+    };
+    (first, second)
+}
@@ -0,0 +1,204 @@
+use super::{escape_text_value, fold_line, smallest_version, Payload, CONTENT_LINE_LIMIT};
+use crate::encoder::ErrorCorrectionLevel;
+use crate::error::QRError;
+
+/// A date and time as written in DTSTART/DTEND.
+///
+/// UTC times carry the `Z` suffix; floating times are interpreted in
+/// whatever time zone the scanning device is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub utc: bool,
+}
+
+impl EventTime {
+    pub fn utc(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Self {
+        EventTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            utc: true,
+        }
+    }
+
+    pub fn floating(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Self {
+        EventTime {
+            utc: false,
+            ..EventTime::utc(year, month, day, hour, minute, second)
+        }
+    }
+
+    fn validate(&self) -> Result<(), QRError> {
+        let leap = self.year.is_multiple_of(4)
+            && (!self.year.is_multiple_of(100) || self.year.is_multiple_of(400));
+        let days_in_month = match self.month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap => 29,
+            2 => 28,
+            _ => 0,
+        };
+
+        // RFC 5545 allows a leap second of 60.
+        if self.year > 9999
+            || self.day == 0
+            || self.day > days_in_month
+            || self.hour > 23
+            || self.minute > 59
+            || self.second > 60
+        {
+            return Err(QRError::InvalidInput(format!(
+                "Invalid date-time {}",
+                self.to_ical()
+            )));
+        }
+        Ok(())
+    }
+
+    fn sort_key(&self) -> (u16, u8, u8, u8, u8, u8) {
+        (
+            self.year,
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.second,
+        )
+    }
+
+    /// The `YYYYMMDDTHHMMSS[Z]` form.
+    pub fn to_ical(&self) -> String {
+        format!(
+            "{:04}{:02}{:02}T{:02}{:02}{:02}{}",
+            self.year,
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.second,
+            if self.utc { "Z" } else { "" }
+        )
+    }
+}
+
+/// An iCalendar event serialised as a bare `BEGIN:VEVENT` block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalendarEvent {
+    pub summary: String,
+    pub start: EventTime,
+    pub end: Option<EventTime>,
+    pub location: Option<String>,
+    pub description: Option<String>,
+    pub url: Option<String>,
+}
+
+impl CalendarEvent {
+    pub fn new(summary: &str, start: EventTime) -> Self {
+        CalendarEvent {
+            summary: summary.to_string(),
+            start,
+            end: None,
+            location: None,
+            description: None,
+            url: None,
+        }
+    }
+
+    pub fn end(mut self, end: EventTime) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    pub fn location(mut self, location: &str) -> Self {
+        self.location = Some(location.to_string());
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    pub fn url(mut self, url: &str) -> Self {
+        self.url = Some(url.to_string());
+        self
+    }
+
+    fn validate(&self) -> Result<(), QRError> {
+        if self.summary.is_empty() {
+            return Err(QRError::InvalidInput(
+                "Event summary cannot be empty".to_string(),
+            ));
+        }
+
+        self.start.validate()?;
+        if let Some(end) = &self.end {
+            end.validate()?;
+            if end.utc != self.start.utc {
+                return Err(QRError::InvalidInput(
+                    "Start and end must both be UTC or both floating".to_string(),
+                ));
+            }
+            if end.sort_key() < self.start.sort_key() {
+                return Err(QRError::InvalidInput(
+                    "Event ends before it starts".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Smallest version that holds the event at the given level, or an
+    /// error when the event is too large for any version.
+    pub fn required_version(&self, ec_level: ErrorCorrectionLevel) -> Result<u8, QRError> {
+        let payload = self.to_payload()?;
+
+        smallest_version(&payload, ec_level)?.ok_or_else(|| {
+            QRError::InvalidLength(format!(
+                "Event of {} bytes does not fit at error correction level {:?}",
+                payload.len(),
+                ec_level
+            ))
+        })
+    }
+}
+
+impl Payload for CalendarEvent {
+    fn to_payload(&self) -> Result<String, QRError> {
+        self.validate()?;
+
+        let mut lines = vec![
+            "BEGIN:VEVENT".to_string(),
+            format!("SUMMARY:{}", escape_text_value(&self.summary)),
+            format!("DTSTART:{}", self.start.to_ical()),
+        ];
+        if let Some(end) = &self.end {
+            lines.push(format!("DTEND:{}", end.to_ical()));
+        }
+        if let Some(location) = &self.location {
+            lines.push(format!("LOCATION:{}", escape_text_value(location)));
+        }
+        if let Some(description) = &self.description {
+            lines.push(format!("DESCRIPTION:{}", escape_text_value(description)));
+        }
+        if let Some(url) = &self.url {
+            lines.push(format!("URL:{}", url));
+        }
+        lines.push("END:VEVENT".to_string());
+
+        // Every content line ends in CRLF, the last one included.
+        Ok(lines
+            .iter()
+            .map(|line| fold_line(line, CONTENT_LINE_LIMIT) + "\r\n")
+            .collect())
+    }
+}
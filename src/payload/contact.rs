@@ -0,0 +1,359 @@
+use super::{
+    escape_text_value, escape_with_backslash, fold_line, smallest_version, Payload,
+    CONTENT_LINE_LIMIT,
+};
+use crate::encoder::ErrorCorrectionLevel;
+use crate::error::QRError;
+
+const MECARD_SPECIAL_CHARS: [char; 5] = ['\\', ';', ',', ':', '"'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContactFormat {
+    VCard3,
+    VCard4,
+    MeCard,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhoneType {
+    Cell,
+    Home,
+    Work,
+    Voice,
+    Fax,
+}
+
+impl PhoneType {
+    fn as_str(self) -> &'static str {
+        match self {
+            PhoneType::Cell => "cell",
+            PhoneType::Home => "home",
+            PhoneType::Work => "work",
+            PhoneType::Voice => "voice",
+            PhoneType::Fax => "fax",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Phone {
+    pub number: String,
+    pub types: Vec<PhoneType>,
+}
+
+/// Postal address, in the component order shared by vCard and MeCard.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Address {
+    pub po_box: String,
+    pub extended: String,
+    pub street: String,
+    pub locality: String,
+    pub region: String,
+    pub postal_code: String,
+    pub country: String,
+}
+
+impl Address {
+    fn components(&self) -> [&str; 7] {
+        [
+            &self.po_box,
+            &self.extended,
+            &self.street,
+            &self.locality,
+            &self.region,
+            &self.postal_code,
+            &self.country,
+        ]
+    }
+}
+
+/// Contact details serialised as a vCard 3.0/4.0 or MeCard payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contact {
+    pub format: ContactFormat,
+    pub given_name: String,
+    pub family_name: String,
+    /// Display name; defaults to "given family".
+    pub formatted_name: Option<String>,
+    pub organization: Option<String>,
+    /// Job title. MeCard has no title field, so it is left out there.
+    pub title: Option<String>,
+    pub phones: Vec<Phone>,
+    pub emails: Vec<String>,
+    pub url: Option<String>,
+    pub address: Option<Address>,
+    pub note: Option<String>,
+    /// URI of a photo. Not part of MeCard.
+    pub photo_uri: Option<String>,
+}
+
+/// Encoded size of a contact in both formats at one error correction level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContactSize {
+    pub vcard_bytes: usize,
+    /// Smallest version holding the vCard, `None` when it does not fit at all.
+    pub vcard_version: Option<u8>,
+    pub mecard_bytes: usize,
+    pub mecard_version: Option<u8>,
+    /// Whether the vCard exceeds the target version while the MeCard does not.
+    pub suggest_mecard: bool,
+}
+
+impl Contact {
+    /// A vCard 3.0 contact with the given name.
+    pub fn new(given_name: &str, family_name: &str) -> Self {
+        Contact {
+            format: ContactFormat::VCard3,
+            given_name: given_name.to_string(),
+            family_name: family_name.to_string(),
+            formatted_name: None,
+            organization: None,
+            title: None,
+            phones: Vec::new(),
+            emails: Vec::new(),
+            url: None,
+            address: None,
+            note: None,
+            photo_uri: None,
+        }
+    }
+
+    pub fn format(mut self, format: ContactFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn formatted_name(mut self, name: &str) -> Self {
+        self.formatted_name = Some(name.to_string());
+        self
+    }
+
+    pub fn organization(mut self, organization: &str) -> Self {
+        self.organization = Some(organization.to_string());
+        self
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    pub fn phone(mut self, number: &str, types: &[PhoneType]) -> Self {
+        self.phones.push(Phone {
+            number: number.to_string(),
+            types: types.to_vec(),
+        });
+        self
+    }
+
+    pub fn email(mut self, email: &str) -> Self {
+        self.emails.push(email.to_string());
+        self
+    }
+
+    pub fn url(mut self, url: &str) -> Self {
+        self.url = Some(url.to_string());
+        self
+    }
+
+    pub fn address(mut self, address: Address) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    pub fn note(mut self, note: &str) -> Self {
+        self.note = Some(note.to_string());
+        self
+    }
+
+    pub fn photo_uri(mut self, uri: &str) -> Self {
+        self.photo_uri = Some(uri.to_string());
+        self
+    }
+
+    fn display_name(&self) -> String {
+        match &self.formatted_name {
+            Some(name) => name.clone(),
+            None => [self.given_name.as_str(), self.family_name.as_str()]
+                .iter()
+                .filter(|part| !part.is_empty())
+                .cloned()
+                .collect::<Vec<&str>>()
+                .join(" "),
+        }
+    }
+
+    fn validate(&self) -> Result<(), QRError> {
+        if self.display_name().is_empty() {
+            return Err(QRError::InvalidInput("Contact needs a name".to_string()));
+        }
+        if self.phones.iter().any(|p| p.number.is_empty()) {
+            return Err(QRError::InvalidInput(
+                "Phone numbers cannot be empty".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Serialises the contact as a vCard, folding long lines with CRLF.
+    pub fn to_vcard(&self, version: ContactFormat) -> Result<String, QRError> {
+        self.validate()?;
+        let v4 = match version {
+            ContactFormat::VCard3 => false,
+            ContactFormat::VCard4 => true,
+            ContactFormat::MeCard => {
+                return Err(QRError::InvalidInput(
+                    "MeCard is not a vCard version".to_string(),
+                ))
+            }
+        };
+
+        let mut lines = vec![
+            "BEGIN:VCARD".to_string(),
+            format!("VERSION:{}", if v4 { "4.0" } else { "3.0" }),
+            format!(
+                "N:{};{};;;",
+                escape_text_value(&self.family_name),
+                escape_text_value(&self.given_name)
+            ),
+            format!("FN:{}", escape_text_value(&self.display_name())),
+        ];
+
+        if let Some(organization) = &self.organization {
+            lines.push(format!("ORG:{}", escape_text_value(organization)));
+        }
+        if let Some(title) = &self.title {
+            lines.push(format!("TITLE:{}", escape_text_value(title)));
+        }
+        for phone in &self.phones {
+            let types: Vec<&str> = phone.types.iter().map(|t| t.as_str()).collect();
+            let type_param = if types.is_empty() {
+                String::new()
+            } else if v4 {
+                format!(";TYPE=\"{}\"", types.join(","))
+            } else {
+                format!(";TYPE={}", types.join(",").to_uppercase())
+            };
+
+            if v4 {
+                // Spaces are not allowed in a tel: URI; hyphens are the
+                // visual separator it accepts instead.
+                let number = phone.number.trim().replace(' ', "-");
+                lines.push(format!("TEL;VALUE=uri{}:tel:{}", type_param, number));
+            } else {
+                lines.push(format!(
+                    "TEL{}:{}",
+                    type_param,
+                    escape_text_value(&phone.number)
+                ));
+            }
+        }
+        for email in &self.emails {
+            if v4 {
+                lines.push(format!("EMAIL:{}", escape_text_value(email)));
+            } else {
+                lines.push(format!("EMAIL;TYPE=INTERNET:{}", escape_text_value(email)));
+            }
+        }
+        if let Some(url) = &self.url {
+            lines.push(format!("URL:{}", url));
+        }
+        if let Some(address) = &self.address {
+            let components: Vec<String> = address
+                .components()
+                .iter()
+                .map(|c| escape_text_value(c))
+                .collect();
+            lines.push(format!("ADR:{}", components.join(";")));
+        }
+        if let Some(note) = &self.note {
+            lines.push(format!("NOTE:{}", escape_text_value(note)));
+        }
+        if let Some(uri) = &self.photo_uri {
+            if v4 {
+                lines.push(format!("PHOTO:{}", uri));
+            } else {
+                lines.push(format!("PHOTO;VALUE=URI:{}", uri));
+            }
+        }
+        lines.push("END:VCARD".to_string());
+
+        // Every content line ends in CRLF, the last one included.
+        Ok(lines
+            .iter()
+            .map(|line| fold_line(line, CONTENT_LINE_LIMIT) + "\r\n")
+            .collect())
+    }
+
+    /// Serialises the contact in the compact MeCard format.
+    pub fn to_mecard(&self) -> Result<String, QRError> {
+        self.validate()?;
+        let escape = |value: &str| escape_with_backslash(value, &MECARD_SPECIAL_CHARS);
+
+        let name = if self.family_name.is_empty() && self.given_name.is_empty() {
+            escape(&self.display_name())
+        } else {
+            format!("{},{}", escape(&self.family_name), escape(&self.given_name))
+        };
+
+        let mut payload = format!("MECARD:N:{};", name);
+        if let Some(organization) = &self.organization {
+            payload.push_str(&format!("ORG:{};", escape(organization)));
+        }
+        for phone in &self.phones {
+            payload.push_str(&format!("TEL:{};", escape(&phone.number)));
+        }
+        for email in &self.emails {
+            payload.push_str(&format!("EMAIL:{};", escape(email)));
+        }
+        if let Some(url) = &self.url {
+            payload.push_str(&format!("URL:{};", escape(url)));
+        }
+        if let Some(address) = &self.address {
+            let components: Vec<String> = address.components().iter().map(|c| escape(c)).collect();
+            payload.push_str(&format!("ADR:{};", components.join(",")));
+        }
+        if let Some(note) = &self.note {
+            payload.push_str(&format!("NOTE:{};", escape(note)));
+        }
+        payload.push(';');
+        Ok(payload)
+    }
+
+    /// Sizes the contact as vCard (in its configured version, 3.0 when set
+    /// to MeCard) and as MeCard, and reports whether MeCard should be used to
+    /// stay within `target_version`.
+    pub fn size_report(
+        &self,
+        ec_level: ErrorCorrectionLevel,
+        target_version: u8,
+    ) -> Result<ContactSize, QRError> {
+        let vcard_format = match self.format {
+            ContactFormat::MeCard => ContactFormat::VCard3,
+            format => format,
+        };
+        let vcard = self.to_vcard(vcard_format)?;
+        let mecard = self.to_mecard()?;
+
+        let vcard_version = smallest_version(&vcard, ec_level)?;
+        let mecard_version = smallest_version(&mecard, ec_level)?;
+        let fits_target = |version: Option<u8>| version.is_some_and(|v| v <= target_version);
+
+        Ok(ContactSize {
+            vcard_bytes: vcard.len(),
+            vcard_version,
+            mecard_bytes: mecard.len(),
+            mecard_version,
+            suggest_mecard: !fits_target(vcard_version) && fits_target(mecard_version),
+        })
+    }
+}
+
+impl Payload for Contact {
+    fn to_payload(&self) -> Result<String, QRError> {
+        match self.format {
+            ContactFormat::MeCard => self.to_mecard(),
+            format => self.to_vcard(format),
+        }
+    }
+}
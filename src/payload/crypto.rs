@@ -0,0 +1,416 @@
+use super::hash::{keccak256, sha256};
+use super::{percent_encode, Payload, QUERY_SAFE};
+use crate::error::QRError;
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32_GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+/// Checksum constants of Bech32 (witness version 0) and Bech32m (1-16).
+const BECH32_CONST: u32 = 1;
+const BECH32M_CONST: u32 = 0x2bc830a3;
+
+/// Mainnet, testnet and regtest human-readable parts.
+const BECH32_HRPS: [&str; 3] = ["bc", "tb", "bcrt"];
+/// P2PKH and P2SH version bytes on mainnet and testnet.
+const BASE58_VERSIONS: [u8; 4] = [0x00, 0x05, 0x6f, 0xc4];
+
+const SATOSHIS_PER_BTC: u64 = 100_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitcoinAddressKind {
+    /// Legacy P2PKH or P2SH address.
+    Base58,
+    /// SegWit version 0, Bech32 checksum.
+    Bech32,
+    /// SegWit version 1 or later (Taproot), Bech32m checksum.
+    Bech32m,
+}
+
+/// A BIP21 `bitcoin:` payment request.
+///
+/// SegWit addresses are written upper-case together with the scheme, so
+/// [`Payload::to_segments`] encodes the part before the query in
+/// alphanumeric mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitcoinPayment {
+    pub address: String,
+    pub amount_sats: Option<u64>,
+    pub label: Option<String>,
+    pub message: Option<String>,
+    /// BOLT 11 invoice offered as a Lightning alternative.
+    pub lightning: Option<String>,
+}
+
+impl BitcoinPayment {
+    pub fn new(address: &str) -> Self {
+        BitcoinPayment {
+            address: address.trim().to_string(),
+            amount_sats: None,
+            label: None,
+            message: None,
+            lightning: None,
+        }
+    }
+
+    pub fn amount_sats(mut self, sats: u64) -> Self {
+        self.amount_sats = Some(sats);
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    pub fn message(mut self, message: &str) -> Self {
+        self.message = Some(message.to_string());
+        self
+    }
+
+    pub fn lightning(mut self, invoice: &str) -> Self {
+        self.lightning = Some(invoice.trim().to_string());
+        self
+    }
+}
+
+impl Payload for BitcoinPayment {
+    fn to_payload(&self) -> Result<String, QRError> {
+        let kind = validate_bitcoin_address(&self.address)?;
+
+        let mut payload = match kind {
+            BitcoinAddressKind::Base58 => format!("bitcoin:{}", self.address),
+            _ => format!("BITCOIN:{}", self.address.to_ascii_uppercase()),
+        };
+
+        let mut params = Vec::new();
+        if let Some(sats) = self.amount_sats {
+            if sats == 0 || sats > 21_000_000 * SATOSHIS_PER_BTC {
+                return Err(QRError::InvalidInput(format!(
+                    "Amount of {} satoshis is out of range",
+                    sats
+                )));
+            }
+            params.push(format!("amount={}", format_btc(sats)));
+        }
+        if let Some(label) = &self.label {
+            params.push(format!("label={}", percent_encode(label, QUERY_SAFE)));
+        }
+        if let Some(message) = &self.message {
+            params.push(format!("message={}", percent_encode(message, QUERY_SAFE)));
+        }
+        if let Some(invoice) = &self.lightning {
+            let lower = invoice.to_ascii_lowercase();
+            if !lower.starts_with("ln") || decode_bech32(&lower).is_none() {
+                return Err(QRError::InvalidInput(format!(
+                    "'{}' is not a Lightning invoice",
+                    invoice
+                )));
+            }
+            params.push(format!("lightning={}", invoice));
+        }
+
+        if !params.is_empty() {
+            payload.push('?');
+            payload.push_str(&params.join("&"));
+        }
+        Ok(payload)
+    }
+}
+
+/// Amount in BTC with trailing zeros removed, e.g. `0.0015`.
+fn format_btc(sats: u64) -> String {
+    let fraction = format!("{:08}", sats % SATOSHIS_PER_BTC);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        format!("{}", sats / SATOSHIS_PER_BTC)
+    } else {
+        format!("{}.{}", sats / SATOSHIS_PER_BTC, fraction)
+    }
+}
+
+/// Checks the checksum and structure of a Bitcoin address.
+pub fn validate_bitcoin_address(address: &str) -> Result<BitcoinAddressKind, QRError> {
+    let invalid = || QRError::InvalidInput(format!("'{}' is not a valid Bitcoin address", address));
+
+    let lower = address.to_ascii_lowercase();
+    let is_segwit = BECH32_HRPS.iter().any(|hrp| {
+        lower
+            .strip_prefix(hrp)
+            .is_some_and(|rest| rest.starts_with('1'))
+    });
+
+    if is_segwit {
+        let (_, data, constant) = decode_bech32(address).ok_or_else(invalid)?;
+        let (&version, program) = data.split_first().ok_or_else(invalid)?;
+        let program = convert_bits(program, 5, 8).ok_or_else(invalid)?;
+
+        let expected_constant = if version == 0 {
+            BECH32_CONST
+        } else {
+            BECH32M_CONST
+        };
+        let valid = version <= 16
+            && constant == expected_constant
+            && (2..=40).contains(&program.len())
+            && (version != 0 || program.len() == 20 || program.len() == 32);
+        if !valid {
+            return Err(invalid());
+        }
+        Ok(if version == 0 {
+            BitcoinAddressKind::Bech32
+        } else {
+            BitcoinAddressKind::Bech32m
+        })
+    } else {
+        let bytes = decode_base58(address).ok_or_else(invalid)?;
+        if bytes.len() != 25 || !BASE58_VERSIONS.contains(&bytes[0]) {
+            return Err(invalid());
+        }
+        let checksum = sha256(&sha256(&bytes[..21]));
+        if checksum[..4] != bytes[21..] {
+            return Err(invalid());
+        }
+        Ok(BitcoinAddressKind::Base58)
+    }
+}
+
+fn decode_base58(value: &str) -> Option<Vec<u8>> {
+    // Big-endian base-256 digits, grown as the number gets larger.
+    let mut bytes: Vec<u8> = Vec::new();
+    for c in value.bytes() {
+        let mut carry = BASE58_ALPHABET.iter().position(|&a| a == c)? as u32;
+        for byte in bytes.iter_mut().rev() {
+            carry += *byte as u32 * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    // Each leading '1' stands for a leading zero byte.
+    let zeros = value.bytes().take_while(|&c| c == b'1').count();
+    let mut decoded = vec![0u8; zeros];
+    decoded.extend(bytes);
+    Some(decoded)
+}
+
+fn bech32_polymod(values: impl Iterator<Item = u8>) -> u32 {
+    let mut checksum = 1u32;
+    for value in values {
+        let top = checksum >> 25;
+        checksum = ((checksum & 0x1ffffff) << 5) ^ value as u32;
+        for (i, generator) in BECH32_GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+/// Splits a Bech32 or Bech32m string into its human-readable part and data
+/// values (checksum removed), returning the checksum constant it matched.
+fn decode_bech32(value: &str) -> Option<(String, Vec<u8>, u32)> {
+    let has_lower = value.chars().any(|c| c.is_ascii_lowercase());
+    let has_upper = value.chars().any(|c| c.is_ascii_uppercase());
+    if has_lower && has_upper || !value.is_ascii() {
+        return None;
+    }
+
+    let value = value.to_ascii_lowercase();
+    let (hrp, data) = value.rsplit_once('1')?;
+    if hrp.is_empty() || data.len() < 6 || hrp.bytes().any(|b| !(33..=126).contains(&b)) {
+        return None;
+    }
+
+    let values = data
+        .bytes()
+        .map(|c| BECH32_CHARSET.iter().position(|&a| a == c).map(|v| v as u8))
+        .collect::<Option<Vec<u8>>>()?;
+
+    let expanded = hrp
+        .bytes()
+        .map(|b| b >> 5)
+        .chain(std::iter::once(0))
+        .chain(hrp.bytes().map(|b| b & 31))
+        .chain(values.iter().copied());
+    let constant = bech32_polymod(expanded);
+    if constant != BECH32_CONST && constant != BECH32M_CONST {
+        return None;
+    }
+
+    let payload = values[..values.len() - 6].to_vec();
+    Some((hrp.to_string(), payload, constant))
+}
+
+/// Regroups `from`-bit values into `to`-bit values without padding, as
+/// needed to turn Bech32 data back into the witness program.
+fn convert_bits(values: &[u8], from: u32, to: u32) -> Option<Vec<u8>> {
+    let mut accumulator = 0u32;
+    let mut bits = 0;
+    let mut converted = Vec::new();
+
+    for &value in values {
+        accumulator = (accumulator << from) | value as u32;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            converted.push(((accumulator >> bits) & ((1 << to) - 1)) as u8);
+        }
+    }
+    // Leftover bits must be zero padding shorter than one input group.
+    if bits >= from || (accumulator & ((1 << bits) - 1)) != 0 {
+        return None;
+    }
+    Some(converted)
+}
+
+/// An EIP-681 `ethereum:` transaction request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthereumPayment {
+    /// Target address: the recipient, or the contract for function calls.
+    pub address: String,
+    pub chain_id: Option<u64>,
+    /// Ether to send, in wei.
+    pub value_wei: Option<u128>,
+    pub gas_limit: Option<u64>,
+    /// Contract function name, e.g. `transfer`.
+    pub function: Option<String>,
+    /// Function arguments as `(type, value)` pairs.
+    pub arguments: Vec<(String, String)>,
+}
+
+impl EthereumPayment {
+    pub fn new(address: &str) -> Self {
+        EthereumPayment {
+            address: address.trim().to_string(),
+            chain_id: None,
+            value_wei: None,
+            gas_limit: None,
+            function: None,
+            arguments: Vec::new(),
+        }
+    }
+
+    pub fn chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    pub fn value_wei(mut self, wei: u128) -> Self {
+        self.value_wei = Some(wei);
+        self
+    }
+
+    pub fn gas_limit(mut self, gas: u64) -> Self {
+        self.gas_limit = Some(gas);
+        self
+    }
+
+    /// Calls `function` on the contract at `address` instead of sending
+    /// ether to it.
+    pub fn function(mut self, function: &str) -> Self {
+        self.function = Some(function.to_string());
+        self
+    }
+
+    pub fn argument(mut self, kind: &str, value: &str) -> Self {
+        self.arguments.push((kind.to_string(), value.to_string()));
+        self
+    }
+}
+
+impl Payload for EthereumPayment {
+    fn to_payload(&self) -> Result<String, QRError> {
+        let mut payload = format!("ethereum:{}", eip55_checksum(&self.address)?);
+        if let Some(chain_id) = self.chain_id {
+            payload.push_str(&format!("@{}", chain_id));
+        }
+
+        let mut params = Vec::new();
+        if let Some(function) = &self.function {
+            let valid = function
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && function
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid {
+                return Err(QRError::InvalidInput(format!(
+                    "'{}' is not a function name",
+                    function
+                )));
+            }
+            payload.push('/');
+            payload.push_str(function);
+
+            for (kind, value) in &self.arguments {
+                let value = if kind == "address" {
+                    eip55_checksum(value)?
+                } else {
+                    percent_encode(value, QUERY_SAFE)
+                };
+                params.push(format!("{}={}", percent_encode(kind, "[]"), value));
+            }
+        } else if !self.arguments.is_empty() {
+            return Err(QRError::InvalidInput(
+                "Arguments need a function to call".to_string(),
+            ));
+        }
+
+        if let Some(wei) = self.value_wei {
+            params.push(format!("value={}", wei));
+        }
+        if let Some(gas) = self.gas_limit {
+            params.push(format!("gasLimit={}", gas));
+        }
+
+        if !params.is_empty() {
+            payload.push('?');
+            payload.push_str(&params.join("&"));
+        }
+        Ok(payload)
+    }
+}
+
+/// Validates an Ethereum address and returns it in EIP-55 mixed-case form.
+/// Mixed-case input must already carry a correct checksum; all-lower and
+/// all-upper input has none and is accepted as is.
+pub fn eip55_checksum(address: &str) -> Result<String, QRError> {
+    let invalid = |reason: &str| {
+        QRError::InvalidInput(format!(
+            "'{}' is not a valid Ethereum address: {}",
+            address, reason
+        ))
+    };
+
+    let hex = address
+        .strip_prefix("0x")
+        .filter(|hex| hex.len() == 40 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+        .ok_or_else(|| invalid("expected 0x and 40 hex digits"))?;
+
+    let lower = hex.to_ascii_lowercase();
+    let hash = keccak256(lower.as_bytes());
+    let checksummed: String = lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    let mixed_case = hex != lower && hex != hex.to_ascii_uppercase();
+    if mixed_case && hex != checksummed {
+        return Err(invalid("checksum mismatch"));
+    }
+    Ok(format!("0x{}", checksummed))
+}
@@ -0,0 +1,434 @@
+use super::{mod97_remainder, Payload};
+use crate::encoder::ErrorCorrectionLevel;
+use crate::error::QRError;
+use crate::segment::SegmentBuilder;
+use crate::{InputMode, QRData};
+
+/// Payloads longer than this are rejected by EPC069-12.
+pub const EPC_MAX_BYTES: usize = 331;
+
+const MAX_AMOUNT_CENTS: u64 = 99_999_999_999;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EpcVersion {
+    /// Version 001, BIC mandatory.
+    V001,
+    /// Version 002, BIC optional inside the EEA.
+    V002,
+}
+
+/// How the payment is referenced; the standard allows one or the other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Remittance {
+    /// Structured creditor reference, e.g. an ISO 11649 `RF` reference.
+    Reference(String),
+    /// Free text for the beneficiary.
+    Text(String),
+}
+
+/// Character sets EPC069-12 allows, numbered 1-8 in the payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EpcCharset {
+    Utf8,
+    Iso8859_1,
+    Iso8859_2,
+    Iso8859_4,
+    Iso8859_5,
+    Iso8859_7,
+    Iso8859_10,
+    Iso8859_15,
+}
+
+impl EpcCharset {
+    pub fn from_code(code: u8) -> Result<Self, QRError> {
+        Ok(match code {
+            1 => EpcCharset::Utf8,
+            2 => EpcCharset::Iso8859_1,
+            3 => EpcCharset::Iso8859_2,
+            4 => EpcCharset::Iso8859_4,
+            5 => EpcCharset::Iso8859_5,
+            6 => EpcCharset::Iso8859_7,
+            7 => EpcCharset::Iso8859_10,
+            8 => EpcCharset::Iso8859_15,
+            _ => {
+                return Err(QRError::InvalidInput(format!(
+                    "EPC character set {} is outside 1-8",
+                    code
+                )))
+            }
+        })
+    }
+
+    /// The digit written on the third line of the payload.
+    pub fn code(self) -> u8 {
+        match self {
+            EpcCharset::Utf8 => 1,
+            EpcCharset::Iso8859_1 => 2,
+            EpcCharset::Iso8859_2 => 3,
+            EpcCharset::Iso8859_4 => 4,
+            EpcCharset::Iso8859_5 => 5,
+            EpcCharset::Iso8859_7 => 6,
+            EpcCharset::Iso8859_10 => 7,
+            EpcCharset::Iso8859_15 => 8,
+        }
+    }
+
+    /// Upper half (0xA0-0xFF) of the single-byte sets other than Latin-1,
+    /// with 0 marking unassigned bytes.
+    fn upper_half(self) -> Option<&'static [u16; 96]> {
+        match self {
+            EpcCharset::Utf8 | EpcCharset::Iso8859_1 => None,
+            EpcCharset::Iso8859_2 => Some(&ISO_8859_2),
+            EpcCharset::Iso8859_4 => Some(&ISO_8859_4),
+            EpcCharset::Iso8859_5 => Some(&ISO_8859_5),
+            EpcCharset::Iso8859_7 => Some(&ISO_8859_7),
+            EpcCharset::Iso8859_10 => Some(&ISO_8859_10),
+            EpcCharset::Iso8859_15 => Some(&ISO_8859_15),
+        }
+    }
+
+    /// Transcodes `text`, failing on the first character the set lacks.
+    pub fn encode(self, text: &str) -> Result<Vec<u8>, QRError> {
+        if self == EpcCharset::Utf8 {
+            return Ok(text.as_bytes().to_vec());
+        }
+
+        text.chars()
+            .map(|c| {
+                let code = c as u32;
+                let byte = match self.upper_half() {
+                    _ if code < 0xA0 => Some(code as u8),
+                    None => u8::try_from(code).ok(),
+                    Some(table) => table
+                        .iter()
+                        .position(|&mapped| u32::from(mapped) == code)
+                        .map(|i| 0xA0 + i as u8),
+                };
+                byte.ok_or_else(|| {
+                    QRError::InvalidInput(format!(
+                        "'{}' is not in EPC character set {}",
+                        c,
+                        self.code()
+                    ))
+                })
+            })
+            .collect()
+    }
+}
+
+/// SEPA credit transfer payload ("GiroCode") following EPC069-12.
+///
+/// [`to_payload`](Payload::to_payload) returns the text, which is
+/// transcoded into `charset` by [`to_bytes`](EpcPayment::to_bytes) and
+/// [`to_segments`](Payload::to_segments).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpcPayment {
+    pub version: EpcVersion,
+    pub charset: EpcCharset,
+    pub bic: Option<String>,
+    pub name: String,
+    pub iban: String,
+    /// Amount in euro cents.
+    pub amount_cents: Option<u64>,
+    /// Four-letter ISO 20022 purpose code.
+    pub purpose: Option<String>,
+    pub remittance: Option<Remittance>,
+    /// Beneficiary to originator information.
+    pub information: Option<String>,
+}
+
+impl EpcPayment {
+    pub fn new(name: &str, iban: &str) -> Self {
+        EpcPayment {
+            version: EpcVersion::V002,
+            charset: EpcCharset::Utf8,
+            bic: None,
+            name: name.to_string(),
+            iban: iban.to_string(),
+            amount_cents: None,
+            purpose: None,
+            remittance: None,
+            information: None,
+        }
+    }
+
+    pub fn version(mut self, version: EpcVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn charset(mut self, charset: EpcCharset) -> Self {
+        self.charset = charset;
+        self
+    }
+
+    pub fn bic(mut self, bic: &str) -> Self {
+        self.bic = Some(bic.to_string());
+        self
+    }
+
+    pub fn amount_cents(mut self, cents: u64) -> Self {
+        self.amount_cents = Some(cents);
+        self
+    }
+
+    pub fn purpose(mut self, purpose: &str) -> Self {
+        self.purpose = Some(purpose.to_string());
+        self
+    }
+
+    pub fn reference(mut self, reference: &str) -> Self {
+        self.remittance = Some(Remittance::Reference(reference.to_string()));
+        self
+    }
+
+    pub fn text(mut self, text: &str) -> Self {
+        self.remittance = Some(Remittance::Text(text.to_string()));
+        self
+    }
+
+    pub fn information(mut self, information: &str) -> Self {
+        self.information = Some(information.to_string());
+        self
+    }
+
+    fn validate(&self) -> Result<(), QRError> {
+        match (&self.bic, self.version) {
+            (Some(bic), _) => validate_bic(bic)?,
+            (None, EpcVersion::V001) => {
+                return Err(QRError::InvalidInput(
+                    "EPC version 001 requires a BIC".to_string(),
+                ))
+            }
+            (None, EpcVersion::V002) => {}
+        }
+
+        check_length("Beneficiary name", &self.name, 1, 70)?;
+        validate_iban(&compact(&self.iban))?;
+
+        if let Some(cents) = self.amount_cents {
+            if cents == 0 || cents > MAX_AMOUNT_CENTS {
+                return Err(QRError::InvalidInput(
+                    "Amount must be between EUR0.01 and EUR999999999.99".to_string(),
+                ));
+            }
+        }
+        if let Some(purpose) = &self.purpose {
+            if purpose.len() != 4 || !purpose.chars().all(|c| c.is_ascii_uppercase()) {
+                return Err(QRError::InvalidInput(format!(
+                    "Purpose '{}' must be four upper-case letters",
+                    purpose
+                )));
+            }
+        }
+        match &self.remittance {
+            Some(Remittance::Reference(reference)) => {
+                check_length("Remittance reference", reference, 1, 35)?
+            }
+            Some(Remittance::Text(text)) => check_length("Remittance text", text, 1, 140)?,
+            None => {}
+        }
+        if let Some(information) = &self.information {
+            check_length("Beneficiary information", information, 0, 70)?;
+        }
+        Ok(())
+    }
+
+    /// The payload in its character set, as it goes into the symbol.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, QRError> {
+        self.charset.encode(&self.to_payload()?)
+    }
+}
+
+impl Payload for EpcPayment {
+    fn to_payload(&self) -> Result<String, QRError> {
+        self.validate()?;
+
+        let (reference, text) = match &self.remittance {
+            Some(Remittance::Reference(reference)) => (reference.as_str(), ""),
+            Some(Remittance::Text(text)) => ("", text.as_str()),
+            None => ("", ""),
+        };
+        let amount = self
+            .amount_cents
+            .map(|cents| format!("EUR{}.{:02}", cents / 100, cents % 100))
+            .unwrap_or_default();
+
+        let lines = [
+            "BCD",
+            match self.version {
+                EpcVersion::V001 => "001",
+                EpcVersion::V002 => "002",
+            },
+            &self.charset.code().to_string(),
+            "SCT",
+            self.bic.as_deref().unwrap_or(""),
+            &self.name,
+            &compact(&self.iban),
+            &amount,
+            self.purpose.as_deref().unwrap_or(""),
+            reference,
+            text,
+            self.information.as_deref().unwrap_or(""),
+        ];
+
+        // Trailing empty elements are left out entirely.
+        let used = lines.iter().rposition(|line| !line.is_empty()).unwrap_or(0) + 1;
+        let payload = lines[..used].join("\n");
+
+        // The limit counts bytes in the chosen character set.
+        let len = self.charset.encode(&payload)?.len();
+        if len > EPC_MAX_BYTES {
+            return Err(QRError::InvalidLength(format!(
+                "EPC payload is {} bytes, the limit is {}",
+                len, EPC_MAX_BYTES
+            )));
+        }
+        Ok(payload)
+    }
+
+    /// Builds a `QRData` with the settings the standard mandates: byte mode
+    /// and error correction level M. `QRData` holds UTF-8 text, so other
+    /// character sets only work here while the text is plain ASCII; use
+    /// [`to_segments`](Payload::to_segments) for the rest.
+    fn to_qr_data(&self) -> Result<QRData, QRError> {
+        let payload = self.to_payload()?;
+        if self.charset != EpcCharset::Utf8 && !payload.is_ascii() {
+            return Err(QRError::InvalidInput(format!(
+                "EPC character set {} needs to_segments for non-ASCII text",
+                self.charset.code()
+            )));
+        }
+        let mut qr_data = QRData::new();
+        qr_data.set_content(&payload)?;
+        qr_data.set_mode(InputMode::Byte)?;
+        qr_data.set_ec_level(ErrorCorrectionLevel::M)?;
+        Ok(qr_data)
+    }
+
+    /// One byte segment, as the standard requires. Generating at level M is
+    /// left to the caller.
+    fn to_segments(&self) -> Result<SegmentBuilder, QRError> {
+        let mut segments = SegmentBuilder::new();
+        segments.push_bytes(&self.to_bytes()?)?;
+        Ok(segments)
+    }
+}
+
+/// Removes the spaces IBANs are usually printed with and upper-cases them.
+fn compact(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_ascii_uppercase()
+}
+
+fn check_length(field: &str, value: &str, min: usize, max: usize) -> Result<(), QRError> {
+    let len = value.chars().count();
+    if len < min || len > max {
+        return Err(QRError::InvalidLength(format!(
+            "{} must be {}-{} characters, got {}",
+            field, min, max, len
+        )));
+    }
+    Ok(())
+}
+
+/// Checks the structure and the ISO 7064 mod-97 check digits of an IBAN.
+pub fn validate_iban(iban: &str) -> Result<(), QRError> {
+    let chars: Vec<char> = iban.chars().collect();
+    let well_formed = (15..=34).contains(&chars.len())
+        && chars[..2].iter().all(|c| c.is_ascii_uppercase())
+        && chars[2..4].iter().all(|c| c.is_ascii_digit())
+        && chars
+            .iter()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+    if !well_formed {
+        return Err(QRError::InvalidInput(format!("Malformed IBAN '{}'", iban)));
+    }
+
+    if mod97_remainder(iban) != 1 {
+        return Err(QRError::InvalidInput(format!(
+            "IBAN '{}' has wrong check digits",
+            iban
+        )));
+    }
+    Ok(())
+}
+
+/// Checks the ISO 9362 structure of a BIC (8 or 11 characters).
+pub fn validate_bic(bic: &str) -> Result<(), QRError> {
+    let chars: Vec<char> = bic.chars().collect();
+    let valid = (chars.len() == 8 || chars.len() == 11)
+        && chars[..6].iter().all(|c| c.is_ascii_uppercase())
+        && chars[6..]
+            .iter()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+
+    if !valid {
+        return Err(QRError::InvalidInput(format!("Malformed BIC '{}'", bic)));
+    }
+    Ok(())
+}
+
+const ISO_8859_2: [u16; 96] = [
+    0x00A0, 0x0104, 0x02D8, 0x0141, 0x00A4, 0x013D, 0x015A, 0x00A7, 0x00A8, 0x0160, 0x015E, 0x0164,
+    0x0179, 0x00AD, 0x017D, 0x017B, 0x00B0, 0x0105, 0x02DB, 0x0142, 0x00B4, 0x013E, 0x015B, 0x02C7,
+    0x00B8, 0x0161, 0x015F, 0x0165, 0x017A, 0x02DD, 0x017E, 0x017C, 0x0154, 0x00C1, 0x00C2, 0x0102,
+    0x00C4, 0x0139, 0x0106, 0x00C7, 0x010C, 0x00C9, 0x0118, 0x00CB, 0x011A, 0x00CD, 0x00CE, 0x010E,
+    0x0110, 0x0143, 0x0147, 0x00D3, 0x00D4, 0x0150, 0x00D6, 0x00D7, 0x0158, 0x016E, 0x00DA, 0x0170,
+    0x00DC, 0x00DD, 0x0162, 0x00DF, 0x0155, 0x00E1, 0x00E2, 0x0103, 0x00E4, 0x013A, 0x0107, 0x00E7,
+    0x010D, 0x00E9, 0x0119, 0x00EB, 0x011B, 0x00ED, 0x00EE, 0x010F, 0x0111, 0x0144, 0x0148, 0x00F3,
+    0x00F4, 0x0151, 0x00F6, 0x00F7, 0x0159, 0x016F, 0x00FA, 0x0171, 0x00FC, 0x00FD, 0x0163, 0x02D9,
+];
+const ISO_8859_4: [u16; 96] = [
+    0x00A0, 0x0104, 0x0138, 0x0156, 0x00A4, 0x0128, 0x013B, 0x00A7, 0x00A8, 0x0160, 0x0112, 0x0122,
+    0x0166, 0x00AD, 0x017D, 0x00AF, 0x00B0, 0x0105, 0x02DB, 0x0157, 0x00B4, 0x0129, 0x013C, 0x02C7,
+    0x00B8, 0x0161, 0x0113, 0x0123, 0x0167, 0x014A, 0x017E, 0x014B, 0x0100, 0x00C1, 0x00C2, 0x00C3,
+    0x00C4, 0x00C5, 0x00C6, 0x012E, 0x010C, 0x00C9, 0x0118, 0x00CB, 0x0116, 0x00CD, 0x00CE, 0x012A,
+    0x0110, 0x0145, 0x014C, 0x0136, 0x00D4, 0x00D5, 0x00D6, 0x00D7, 0x00D8, 0x0172, 0x00DA, 0x00DB,
+    0x00DC, 0x0168, 0x016A, 0x00DF, 0x0101, 0x00E1, 0x00E2, 0x00E3, 0x00E4, 0x00E5, 0x00E6, 0x012F,
+    0x010D, 0x00E9, 0x0119, 0x00EB, 0x0117, 0x00ED, 0x00EE, 0x012B, 0x0111, 0x0146, 0x014D, 0x0137,
+    0x00F4, 0x00F5, 0x00F6, 0x00F7, 0x00F8, 0x0173, 0x00FA, 0x00FB, 0x00FC, 0x0169, 0x016B, 0x02D9,
+];
+const ISO_8859_5: [u16; 96] = [
+    0x00A0, 0x0401, 0x0402, 0x0403, 0x0404, 0x0405, 0x0406, 0x0407, 0x0408, 0x0409, 0x040A, 0x040B,
+    0x040C, 0x00AD, 0x040E, 0x040F, 0x0410, 0x0411, 0x0412, 0x0413, 0x0414, 0x0415, 0x0416, 0x0417,
+    0x0418, 0x0419, 0x041A, 0x041B, 0x041C, 0x041D, 0x041E, 0x041F, 0x0420, 0x0421, 0x0422, 0x0423,
+    0x0424, 0x0425, 0x0426, 0x0427, 0x0428, 0x0429, 0x042A, 0x042B, 0x042C, 0x042D, 0x042E, 0x042F,
+    0x0430, 0x0431, 0x0432, 0x0433, 0x0434, 0x0435, 0x0436, 0x0437, 0x0438, 0x0439, 0x043A, 0x043B,
+    0x043C, 0x043D, 0x043E, 0x043F, 0x0440, 0x0441, 0x0442, 0x0443, 0x0444, 0x0445, 0x0446, 0x0447,
+    0x0448, 0x0449, 0x044A, 0x044B, 0x044C, 0x044D, 0x044E, 0x044F, 0x2116, 0x0451, 0x0452, 0x0453,
+    0x0454, 0x0455, 0x0456, 0x0457, 0x0458, 0x0459, 0x045A, 0x045B, 0x045C, 0x00A7, 0x045E, 0x045F,
+];
+const ISO_8859_7: [u16; 96] = [
+    0x00A0, 0x2018, 0x2019, 0x00A3, 0x20AC, 0x20AF, 0x00A6, 0x00A7, 0x00A8, 0x00A9, 0x037A, 0x00AB,
+    0x00AC, 0x00AD, 0x0000, 0x2015, 0x00B0, 0x00B1, 0x00B2, 0x00B3, 0x0384, 0x0385, 0x0386, 0x00B7,
+    0x0388, 0x0389, 0x038A, 0x00BB, 0x038C, 0x00BD, 0x038E, 0x038F, 0x0390, 0x0391, 0x0392, 0x0393,
+    0x0394, 0x0395, 0x0396, 0x0397, 0x0398, 0x0399, 0x039A, 0x039B, 0x039C, 0x039D, 0x039E, 0x039F,
+    0x03A0, 0x03A1, 0x0000, 0x03A3, 0x03A4, 0x03A5, 0x03A6, 0x03A7, 0x03A8, 0x03A9, 0x03AA, 0x03AB,
+    0x03AC, 0x03AD, 0x03AE, 0x03AF, 0x03B0, 0x03B1, 0x03B2, 0x03B3, 0x03B4, 0x03B5, 0x03B6, 0x03B7,
+    0x03B8, 0x03B9, 0x03BA, 0x03BB, 0x03BC, 0x03BD, 0x03BE, 0x03BF, 0x03C0, 0x03C1, 0x03C2, 0x03C3,
+    0x03C4, 0x03C5, 0x03C6, 0x03C7, 0x03C8, 0x03C9, 0x03CA, 0x03CB, 0x03CC, 0x03CD, 0x03CE, 0x0000,
+];
+const ISO_8859_10: [u16; 96] = [
+    0x00A0, 0x0104, 0x0112, 0x0122, 0x012A, 0x0128, 0x0136, 0x00A7, 0x013B, 0x0110, 0x0160, 0x0166,
+    0x017D, 0x00AD, 0x016A, 0x014A, 0x00B0, 0x0105, 0x0113, 0x0123, 0x012B, 0x0129, 0x0137, 0x00B7,
+    0x013C, 0x0111, 0x0161, 0x0167, 0x017E, 0x2015, 0x016B, 0x014B, 0x0100, 0x00C1, 0x00C2, 0x00C3,
+    0x00C4, 0x00C5, 0x00C6, 0x012E, 0x010C, 0x00C9, 0x0118, 0x00CB, 0x0116, 0x00CD, 0x00CE, 0x00CF,
+    0x00D0, 0x0145, 0x014C, 0x00D3, 0x00D4, 0x00D5, 0x00D6, 0x0168, 0x00D8, 0x0172, 0x00DA, 0x00DB,
+    0x00DC, 0x00DD, 0x00DE, 0x00DF, 0x0101, 0x00E1, 0x00E2, 0x00E3, 0x00E4, 0x00E5, 0x00E6, 0x012F,
+    0x010D, 0x00E9, 0x0119, 0x00EB, 0x0117, 0x00ED, 0x00EE, 0x00EF, 0x00F0, 0x0146, 0x014D, 0x00F3,
+    0x00F4, 0x00F5, 0x00F6, 0x0169, 0x00F8, 0x0173, 0x00FA, 0x00FB, 0x00FC, 0x00FD, 0x00FE, 0x0138,
+];
+const ISO_8859_15: [u16; 96] = [
+    0x00A0, 0x00A1, 0x00A2, 0x00A3, 0x20AC, 0x00A5, 0x0160, 0x00A7, 0x0161, 0x00A9, 0x00AA, 0x00AB,
+    0x00AC, 0x00AD, 0x00AE, 0x00AF, 0x00B0, 0x00B1, 0x00B2, 0x00B3, 0x017D, 0x00B5, 0x00B6, 0x00B7,
+    0x017E, 0x00B9, 0x00BA, 0x00BB, 0x0152, 0x0153, 0x0178, 0x00BF, 0x00C0, 0x00C1, 0x00C2, 0x00C3,
+    0x00C4, 0x00C5, 0x00C6, 0x00C7, 0x00C8, 0x00C9, 0x00CA, 0x00CB, 0x00CC, 0x00CD, 0x00CE, 0x00CF,
+    0x00D0, 0x00D1, 0x00D2, 0x00D3, 0x00D4, 0x00D5, 0x00D6, 0x00D7, 0x00D8, 0x00D9, 0x00DA, 0x00DB,
+    0x00DC, 0x00DD, 0x00DE, 0x00DF, 0x00E0, 0x00E1, 0x00E2, 0x00E3, 0x00E4, 0x00E5, 0x00E6, 0x00E7,
+    0x00E8, 0x00E9, 0x00EA, 0x00EB, 0x00EC, 0x00ED, 0x00EE, 0x00EF, 0x00F0, 0x00F1, 0x00F2, 0x00F3,
+    0x00F4, 0x00F5, 0x00F6, 0x00F7, 0x00F8, 0x00F9, 0x00FA, 0x00FB, 0x00FC, 0x00FD, 0x00FE, 0x00FF,
+];
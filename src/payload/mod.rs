@@ -0,0 +1,213 @@
+pub mod calendar;
+pub mod contact;
+pub mod crypto;
+pub mod epc;
+mod hash;
+pub mod otp;
+pub mod swiss;
+pub mod uri;
+pub mod wifi;
+
+use crate::encoder::ErrorCorrectionLevel;
+use crate::error::QRError;
+use crate::segment::SegmentBuilder;
+use crate::QRData;
+
+/// vCard and iCalendar content lines are folded beyond this many octets.
+pub(crate) const CONTENT_LINE_LIMIT: usize = 75;
+
+pub use calendar::{CalendarEvent, EventTime};
+pub use contact::{Address, Contact, ContactFormat, ContactSize, Phone, PhoneType};
+pub use crypto::{BitcoinAddressKind, BitcoinPayment, EthereumPayment};
+pub use epc::{EpcCharset, EpcPayment, EpcVersion, Remittance};
+pub use otp::{OtpAlgorithm, OtpAuth, OtpKind};
+pub use swiss::{SwissAddress, SwissCurrency, SwissQrBill, SwissReference};
+pub use uri::{EmailMessage, GeoLocation, SmsFormat, SmsMessage, TelNumber, Url};
+pub use wifi::{WifiAuth, WifiConfig};
+
+/// Structured content that serialises into the text of a symbol.
+pub trait Payload {
+    /// The text to encode, after validating the fields.
+    fn to_payload(&self) -> Result<String, QRError>;
+
+    /// Builds a `QRData` holding the serialised payload.
+    fn to_qr_data(&self) -> Result<QRData, QRError> {
+        let mut qr_data = QRData::new();
+        qr_data.set_content(&self.to_payload()?)?;
+        Ok(qr_data)
+    }
+
+    /// The serialised payload split into segments, so upper-case parts such
+    /// as schemes and Base32 secrets are encoded in alphanumeric mode.
+    /// Payloads whose standard fixes the mode override this.
+    fn to_segments(&self) -> Result<SegmentBuilder, QRError> {
+        let mut segments = SegmentBuilder::new();
+        segments.push_text(&self.to_payload()?)?;
+        Ok(segments)
+    }
+}
+
+/// Smallest version holding `payload` at the given level, `None` when it
+/// does not fit into any version.
+pub fn smallest_version(
+    payload: &str,
+    ec_level: ErrorCorrectionLevel,
+) -> Result<Option<u8>, QRError> {
+    let mut qr_data = QRData::new();
+    qr_data.set_content(payload)?;
+    qr_data.set_ec_level(ec_level)?;
+    qr_data.determine_version()
+}
+
+/// Escapes a vCard or iCalendar text value: backslash, comma, semicolon and
+/// line breaks.
+pub(crate) fn escape_text_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ',' => escaped.push_str("\\,"),
+            ';' => escaped.push_str("\\;"),
+            '\r' => {
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                escaped.push_str("\\n");
+            }
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Folds a content line into chunks of at most `limit` octets, each
+/// continuation starting with CRLF and a space. Never splits a character.
+pub(crate) fn fold_line(line: &str, limit: usize) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / limit * 3);
+    let mut width = 0;
+
+    for c in line.chars() {
+        let len = c.len_utf8();
+        if width + len > limit {
+            folded.push_str("\r\n ");
+            // The leading space counts towards the continuation line.
+            width = 1;
+        }
+        folded.push(c);
+        width += len;
+    }
+    folded
+}
+
+/// ISO 7064 mod-97 remainder as used by IBANs and ISO 11649 creditor
+/// references: the first four characters move to the end and letters
+/// count as 10-35. A valid value leaves a remainder of 1.
+pub(crate) fn mod97_remainder(value: &str) -> u32 {
+    let chars: Vec<char> = value.chars().collect();
+    let split = chars.len().min(4);
+    let mut remainder = 0u32;
+
+    for c in chars[split..].iter().chain(&chars[..split]) {
+        let digit = c.to_digit(36).unwrap_or(0);
+        remainder = if digit >= 10 {
+            (remainder * 100 + digit) % 97
+        } else {
+            (remainder * 10 + digit) % 97
+        };
+    }
+    remainder
+}
+
+/// Backslash-escapes every character in `special`, as used by the
+/// `WIFI:` and `MECARD:` formats.
+pub(crate) fn escape_with_backslash(value: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Splits `KEY:value;` fields on unescaped `;`, removing the escapes.
+pub(crate) fn split_escaped_fields(body: &str) -> Result<Vec<(String, String)>, QRError> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut chars = body.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                // Keep the escape so the key/value split below can tell
+                // escaped colons apart; it is removed afterwards.
+                Some(next) => {
+                    current.push('\\');
+                    current.push(next);
+                }
+                None => {
+                    return Err(QRError::InvalidInput(
+                        "Payload ends with a dangling escape".to_string(),
+                    ))
+                }
+            },
+            ';' => {
+                if !current.is_empty() {
+                    fields.push(split_field(&current)?);
+                }
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+
+    if !current.is_empty() {
+        fields.push(split_field(&current)?);
+    }
+    Ok(fields)
+}
+
+fn split_field(field: &str) -> Result<(String, String), QRError> {
+    let (key, value) = field
+        .split_once(':')
+        .ok_or_else(|| QRError::InvalidInput(format!("Field '{}' has no key", field)))?;
+
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(next) = chars.next() {
+                unescaped.push(next);
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    Ok((key.to_string(), unescaped))
+}
+
+/// `pchar` characters allowed in a query value besides the unreserved set,
+/// minus `&` and `=` which separate the parameters.
+pub(crate) const QUERY_SAFE: &str = "!$'()*+,;:@/?";
+
+/// RFC 3986 percent-encoding of the UTF-8 bytes of `value`. Unreserved
+/// characters and those in `keep` stay literal; hex digits are upper-case,
+/// which also keeps escapes within the alphanumeric character set.
+pub(crate) fn percent_encode(value: &str, keep: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c.is_ascii_alphanumeric() || "-._~".contains(c) || keep.contains(c) {
+            encoded.push(c);
+        } else {
+            let mut buf = [0u8; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        }
+    }
+    encoded
+}
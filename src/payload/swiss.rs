@@ -0,0 +1,382 @@
+use super::{mod97_remainder, Payload};
+use crate::encoder::ErrorCorrectionLevel;
+use crate::error::QRError;
+use crate::matrix::QRMatrix;
+use crate::render::{Overlay, RenderOptions};
+use crate::segment::SegmentBuilder;
+use crate::QRData;
+
+/// Symbol width mandated by the QR-bill standard, quiet zone excluded.
+pub const SWISS_SYMBOL_MM: f64 = 46.0;
+
+/// Quiet zone around the symbol.
+pub const SWISS_QUIET_ZONE_MM: f64 = 5.0;
+
+const MAX_PAYLOAD_CHARS: usize = 997;
+const MAX_AMOUNT_CENTS: u64 = 99_999_999_999;
+
+// Carry table of the recursive mod-10 check digit used by QR references.
+const MOD10_TABLE: [u32; 10] = [0, 9, 4, 6, 8, 2, 7, 1, 3, 5];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SwissAddress {
+    /// Address type `S`: street, building number, postal code and town in
+    /// separate fields.
+    Structured {
+        name: String,
+        street: String,
+        building_number: String,
+        postal_code: String,
+        town: String,
+        country: String,
+    },
+    /// Address type `K`: two free address lines.
+    Combined {
+        name: String,
+        line1: String,
+        line2: String,
+        country: String,
+    },
+}
+
+impl SwissAddress {
+    /// The seven address elements in payload order.
+    fn lines(&self) -> [&str; 7] {
+        match self {
+            SwissAddress::Structured {
+                name,
+                street,
+                building_number,
+                postal_code,
+                town,
+                country,
+            } => [
+                "S",
+                name,
+                street,
+                building_number,
+                postal_code,
+                town,
+                country,
+            ],
+            SwissAddress::Combined {
+                name,
+                line1,
+                line2,
+                country,
+            } => ["K", name, line1, line2, "", "", country],
+        }
+    }
+
+    fn validate(&self, role: &str) -> Result<(), QRError> {
+        let lines = self.lines();
+        let limits = match self {
+            SwissAddress::Structured { .. } => [1, 70, 70, 16, 16, 35, 2],
+            SwissAddress::Combined { .. } => [1, 70, 70, 70, 0, 0, 2],
+        };
+
+        for (line, limit) in lines.iter().zip(limits).skip(1) {
+            if line.chars().count() > limit {
+                return Err(QRError::InvalidLength(format!(
+                    "{} address field '{}' exceeds {} characters",
+                    role, line, limit
+                )));
+            }
+        }
+
+        let (name, country) = (lines[1], lines[6]);
+        let required_missing = match self {
+            SwissAddress::Structured {
+                postal_code, town, ..
+            } => postal_code.is_empty() || town.is_empty(),
+            SwissAddress::Combined { line2, .. } => line2.is_empty(),
+        };
+        if name.is_empty() || required_missing {
+            return Err(QRError::InvalidInput(format!(
+                "{} address is incomplete",
+                role
+            )));
+        }
+        if country.len() != 2 || !country.chars().all(|c| c.is_ascii_uppercase()) {
+            return Err(QRError::InvalidInput(format!(
+                "{} country '{}' must be a two-letter ISO code",
+                role, country
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwissCurrency {
+    Chf,
+    Eur,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SwissReference {
+    /// 27-digit QR reference, only valid with a QR-IBAN.
+    Qrr(String),
+    /// ISO 11649 creditor reference (`RF...`).
+    Scor(String),
+    None,
+}
+
+/// Swiss QR-bill payment part (Swiss Payments Code, version 0200).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwissQrBill {
+    pub iban: String,
+    pub creditor: SwissAddress,
+    pub amount_cents: Option<u64>,
+    pub currency: SwissCurrency,
+    pub debtor: Option<SwissAddress>,
+    pub reference: SwissReference,
+    pub message: Option<String>,
+    pub billing_information: Option<String>,
+}
+
+impl SwissQrBill {
+    pub fn new(iban: &str, creditor: SwissAddress) -> Self {
+        SwissQrBill {
+            iban: iban.to_string(),
+            creditor,
+            amount_cents: None,
+            currency: SwissCurrency::Chf,
+            debtor: None,
+            reference: SwissReference::None,
+            message: None,
+            billing_information: None,
+        }
+    }
+
+    pub fn amount_cents(mut self, cents: u64) -> Self {
+        self.amount_cents = Some(cents);
+        self
+    }
+
+    pub fn currency(mut self, currency: SwissCurrency) -> Self {
+        self.currency = currency;
+        self
+    }
+
+    pub fn debtor(mut self, debtor: SwissAddress) -> Self {
+        self.debtor = Some(debtor);
+        self
+    }
+
+    pub fn reference(mut self, reference: SwissReference) -> Self {
+        self.reference = reference;
+        self
+    }
+
+    pub fn message(mut self, message: &str) -> Self {
+        self.message = Some(message.to_string());
+        self
+    }
+
+    pub fn billing_information(mut self, information: &str) -> Self {
+        self.billing_information = Some(information.to_string());
+        self
+    }
+
+    fn compact_iban(&self) -> String {
+        self.iban
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_ascii_uppercase()
+    }
+
+    fn validate(&self) -> Result<(), QRError> {
+        let iban = self.compact_iban();
+        let qr_iban = validate_swiss_iban(&iban)?;
+
+        match (&self.reference, qr_iban) {
+            (SwissReference::Qrr(reference), true) => validate_qr_reference(reference)?,
+            (SwissReference::Qrr(_), false) => {
+                return Err(QRError::InvalidInput(
+                    "QR references require a QR-IBAN".to_string(),
+                ))
+            }
+            (_, true) => {
+                return Err(QRError::InvalidInput(
+                    "A QR-IBAN must be used with a QR reference".to_string(),
+                ))
+            }
+            (SwissReference::Scor(reference), false) => validate_creditor_reference(reference)?,
+            (SwissReference::None, false) => {}
+        }
+
+        self.creditor.validate("Creditor")?;
+        if let Some(debtor) = &self.debtor {
+            debtor.validate("Debtor")?;
+        }
+
+        if let Some(cents) = self.amount_cents {
+            if cents == 0 || cents > MAX_AMOUNT_CENTS {
+                return Err(QRError::InvalidInput(
+                    "Amount must be between 0.01 and 999999999.99".to_string(),
+                ));
+            }
+        }
+
+        let message_len = self.message.as_deref().map_or(0, |m| m.chars().count());
+        let billing_len = self
+            .billing_information
+            .as_deref()
+            .map_or(0, |b| b.chars().count());
+        if message_len + billing_len > 140 {
+            return Err(QRError::InvalidLength(
+                "Message and billing information exceed 140 characters together".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Render options for the mandated print size: 46 mm symbol, 5 mm quiet
+    /// zone and the Swiss cross in the centre.
+    pub fn render_options(matrix: &QRMatrix) -> RenderOptions {
+        let module_mm = SWISS_SYMBOL_MM / matrix.get_size() as f64;
+
+        RenderOptions {
+            border: (SWISS_QUIET_ZONE_MM / module_mm).ceil() as u32,
+            module_size_mm: Some(module_mm),
+            overlay: Some(Overlay::SwissCross),
+            ..RenderOptions::default()
+        }
+    }
+}
+
+impl Payload for SwissQrBill {
+    fn to_payload(&self) -> Result<String, QRError> {
+        self.validate()?;
+
+        let empty_address = ["", "", "", "", "", "", ""];
+        let amount = self
+            .amount_cents
+            .map(|cents| format!("{}.{:02}", cents / 100, cents % 100))
+            .unwrap_or_default();
+        let (reference_type, reference) = match &self.reference {
+            SwissReference::Qrr(reference) => ("QRR", compact_reference(reference)),
+            SwissReference::Scor(reference) => ("SCOR", compact_reference(reference)),
+            SwissReference::None => ("NON", String::new()),
+        };
+
+        let mut lines: Vec<&str> = vec!["SPC", "0200", "1"];
+        let iban = self.compact_iban();
+        lines.push(&iban);
+        lines.extend(self.creditor.lines());
+        // The ultimate creditor is reserved for future use and stays empty.
+        lines.extend(empty_address);
+        lines.push(&amount);
+        lines.push(match self.currency {
+            SwissCurrency::Chf => "CHF",
+            SwissCurrency::Eur => "EUR",
+        });
+        lines.extend(
+            self.debtor
+                .as_ref()
+                .map_or(empty_address, |debtor| debtor.lines()),
+        );
+        lines.push(reference_type);
+        lines.push(&reference);
+        lines.push(self.message.as_deref().unwrap_or(""));
+        lines.push("EPD");
+        if let Some(billing) = &self.billing_information {
+            lines.push(billing);
+        }
+
+        let payload = lines.join("\r\n");
+        if payload.chars().count() > MAX_PAYLOAD_CHARS {
+            return Err(QRError::InvalidLength(format!(
+                "QR-bill payload exceeds {} characters",
+                MAX_PAYLOAD_CHARS
+            )));
+        }
+        Ok(payload)
+    }
+
+    /// QR-bills are always encoded at error correction level M.
+    fn to_qr_data(&self) -> Result<QRData, QRError> {
+        let mut qr_data = QRData::new();
+        qr_data.set_content(&self.to_payload()?)?;
+        qr_data.set_ec_level(ErrorCorrectionLevel::M)?;
+        Ok(qr_data)
+    }
+
+    /// One byte segment, like [`SwissQrBill::to_qr_data`]. Generating at
+    /// level M is left to the caller.
+    fn to_segments(&self) -> Result<SegmentBuilder, QRError> {
+        let mut segments = SegmentBuilder::new();
+        segments.push_bytes(self.to_payload()?.as_bytes())?;
+        Ok(segments)
+    }
+}
+
+fn compact_reference(reference: &str) -> String {
+    reference.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// Validates a Swiss or Liechtenstein IBAN and reports whether it is a
+/// QR-IBAN (institution id 30000-31999).
+pub fn validate_swiss_iban(iban: &str) -> Result<bool, QRError> {
+    let valid = iban.len() == 21
+        && (iban.starts_with("CH") || iban.starts_with("LI"))
+        && iban[2..].chars().all(|c| c.is_ascii_alphanumeric())
+        && mod97_remainder(iban) == 1;
+    if !valid {
+        return Err(QRError::InvalidInput(format!(
+            "'{}' is not a valid CH or LI IBAN",
+            iban
+        )));
+    }
+
+    let institution: u32 = iban[4..9].parse().unwrap_or(0);
+    Ok((30000..=31999).contains(&institution))
+}
+
+/// Recursive mod-10 check digit over a string of decimal digits.
+pub fn mod10_check_digit(digits: &str) -> Option<u32> {
+    let mut carry = 0;
+    for c in digits.chars() {
+        carry = MOD10_TABLE[((carry + c.to_digit(10)?) % 10) as usize];
+    }
+    Some((10 - carry) % 10)
+}
+
+/// Checks a 27-digit QR reference, whose last digit is the mod-10 check digit.
+pub fn validate_qr_reference(reference: &str) -> Result<(), QRError> {
+    let reference = compact_reference(reference);
+    let valid = reference.len() == 27
+        && reference.chars().all(|c| c.is_ascii_digit())
+        && mod10_check_digit(&reference[..26]) == reference[26..].parse().ok();
+
+    if !valid {
+        return Err(QRError::InvalidInput(format!(
+            "'{}' is not a valid QR reference",
+            reference
+        )));
+    }
+    Ok(())
+}
+
+/// Checks an ISO 11649 creditor reference: `RF`, two check digits and up to
+/// 21 alphanumeric characters.
+pub fn validate_creditor_reference(reference: &str) -> Result<(), QRError> {
+    let reference = compact_reference(reference).to_ascii_uppercase();
+    // Checked first so the byte slices below fall on character boundaries.
+    let valid = reference.is_ascii()
+        && (5..=25).contains(&reference.len())
+        && reference.starts_with("RF")
+        && reference[2..4].chars().all(|c| c.is_ascii_digit())
+        && reference[4..].chars().all(|c| c.is_ascii_alphanumeric())
+        && mod97_remainder(&reference) == 1;
+
+    if !valid {
+        return Err(QRError::InvalidInput(format!(
+            "'{}' is not a valid creditor reference",
+            reference
+        )));
+    }
+    Ok(())
+}
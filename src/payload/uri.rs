@@ -0,0 +1,391 @@
+use super::{percent_encode, Payload, QUERY_SAFE};
+use crate::error::QRError;
+
+/// Reserved characters (RFC 3986 gen-delims and sub-delims) that keep their
+/// meaning when they appear in a URL given by the caller. `%` is kept only
+/// where it starts an escape, see [`encode_url_part`].
+const URL_RESERVED: &str = ":/?#[]@!$&'()*+,;=";
+
+/// Phone number characters left literal in `tel:` and `sms:` URIs; `#`
+/// would start a fragment, so it is escaped.
+const PHONE_SAFE: &str = "+*";
+
+/// A `geo:` URI (RFC 5870) in WGS-84 coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Altitude in metres.
+    pub altitude: Option<f64>,
+    /// Search query or label, the `?q=` extension understood by Android.
+    pub query: Option<String>,
+}
+
+impl GeoLocation {
+    pub fn new(latitude: f64, longitude: f64) -> Self {
+        GeoLocation {
+            latitude,
+            longitude,
+            altitude: None,
+            query: None,
+        }
+    }
+
+    pub fn altitude(mut self, metres: f64) -> Self {
+        self.altitude = Some(metres);
+        self
+    }
+
+    pub fn query(mut self, query: &str) -> Self {
+        self.query = Some(query.to_string());
+        self
+    }
+}
+
+impl Payload for GeoLocation {
+    fn to_payload(&self) -> Result<String, QRError> {
+        if !(-90.0..=90.0).contains(&self.latitude)
+            || !(-180.0..=180.0).contains(&self.longitude)
+            || !self.altitude.is_none_or(f64::is_finite)
+        {
+            return Err(QRError::InvalidInput(format!(
+                "Coordinates {},{} are out of range",
+                self.latitude, self.longitude
+            )));
+        }
+
+        let mut payload = format!("geo:{},{}", self.latitude, self.longitude);
+        if let Some(altitude) = self.altitude {
+            payload.push_str(&format!(",{}", altitude));
+        }
+        if let Some(query) = &self.query {
+            payload.push_str("?q=");
+            payload.push_str(&percent_encode(query, QUERY_SAFE));
+        }
+        Ok(payload)
+    }
+}
+
+/// Spelling of an SMS payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmsFormat {
+    /// `sms:number?body=text` (RFC 5724).
+    Uri,
+    /// `SMSTO:number:text`, the older form many scanner apps still expect.
+    SmsTo,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmsMessage {
+    pub number: String,
+    pub body: Option<String>,
+    pub format: SmsFormat,
+}
+
+impl SmsMessage {
+    pub fn new(number: &str) -> Self {
+        SmsMessage {
+            number: number.to_string(),
+            body: None,
+            format: SmsFormat::Uri,
+        }
+    }
+
+    pub fn body(mut self, body: &str) -> Self {
+        self.body = Some(body.to_string());
+        self
+    }
+
+    pub fn format(mut self, format: SmsFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+impl Payload for SmsMessage {
+    fn to_payload(&self) -> Result<String, QRError> {
+        let number = normalize_phone_number(&self.number)?;
+
+        Ok(match (self.format, &self.body) {
+            (SmsFormat::Uri, Some(body)) => format!(
+                "sms:{}?body={}",
+                percent_encode(&number, PHONE_SAFE),
+                percent_encode(body, QUERY_SAFE)
+            ),
+            (SmsFormat::Uri, None) => format!("sms:{}", percent_encode(&number, PHONE_SAFE)),
+            // SMSTO: carries the text verbatim after the second colon.
+            (SmsFormat::SmsTo, body) => {
+                format!("SMSTO:{}:{}", number, body.as_deref().unwrap_or(""))
+            }
+        })
+    }
+}
+
+/// A `tel:` URI (RFC 3966).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TelNumber {
+    pub number: String,
+}
+
+impl TelNumber {
+    pub fn new(number: &str) -> Self {
+        TelNumber {
+            number: number.to_string(),
+        }
+    }
+}
+
+impl Payload for TelNumber {
+    fn to_payload(&self) -> Result<String, QRError> {
+        let number = normalize_phone_number(&self.number)?;
+        Ok(format!("tel:{}", percent_encode(&number, PHONE_SAFE)))
+    }
+}
+
+/// A `mailto:` URI (RFC 6068).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EmailMessage {
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub bcc: Vec<String>,
+    pub subject: Option<String>,
+    pub body: Option<String>,
+}
+
+impl EmailMessage {
+    pub fn new(to: &str) -> Self {
+        EmailMessage {
+            to: vec![to.to_string()],
+            ..EmailMessage::default()
+        }
+    }
+
+    pub fn to(mut self, address: &str) -> Self {
+        self.to.push(address.to_string());
+        self
+    }
+
+    pub fn cc(mut self, address: &str) -> Self {
+        self.cc.push(address.to_string());
+        self
+    }
+
+    pub fn bcc(mut self, address: &str) -> Self {
+        self.bcc.push(address.to_string());
+        self
+    }
+
+    pub fn subject(mut self, subject: &str) -> Self {
+        self.subject = Some(subject.to_string());
+        self
+    }
+
+    pub fn body(mut self, body: &str) -> Self {
+        self.body = Some(body.to_string());
+        self
+    }
+}
+
+impl Payload for EmailMessage {
+    fn to_payload(&self) -> Result<String, QRError> {
+        let all = self.to.iter().chain(&self.cc).chain(&self.bcc);
+        if let Some(address) = all.clone().find(|a| !is_email_address(a)) {
+            return Err(QRError::InvalidInput(format!(
+                "'{}' is not an email address",
+                address
+            )));
+        }
+        if all.count() == 0 {
+            return Err(QRError::InvalidInput(
+                "Email needs at least one recipient".to_string(),
+            ));
+        }
+
+        let join = |addresses: &[String]| {
+            addresses
+                .iter()
+                .map(|a| percent_encode(a, "@!$'()*+;:"))
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+
+        let mut fields = Vec::new();
+        if !self.cc.is_empty() {
+            fields.push(format!("cc={}", join(&self.cc)));
+        }
+        if !self.bcc.is_empty() {
+            fields.push(format!("bcc={}", join(&self.bcc)));
+        }
+        if let Some(subject) = &self.subject {
+            fields.push(format!("subject={}", percent_encode(subject, QUERY_SAFE)));
+        }
+        if let Some(body) = &self.body {
+            // RFC 6068 wants line breaks in the body as CRLF.
+            let body = body.replace("\r\n", "\n").replace('\n', "\r\n");
+            fields.push(format!("body={}", percent_encode(&body, QUERY_SAFE)));
+        }
+
+        let mut payload = format!("mailto:{}", join(&self.to));
+        if !fields.is_empty() {
+            payload.push('?');
+            payload.push_str(&fields.join("&"));
+        }
+        Ok(payload)
+    }
+}
+
+/// An `http` or `https` URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Url {
+    pub url: String,
+    /// Query parameters appended after any query already in `url`.
+    pub params: Vec<(String, String)>,
+    /// Upper-cases the scheme and host, which are case-insensitive, so
+    /// [`Payload::to_segments`] can encode the start of the URL in
+    /// alphanumeric mode. Off by default.
+    pub uppercase: bool,
+}
+
+impl Url {
+    pub fn new(url: &str) -> Self {
+        Url {
+            url: url.to_string(),
+            params: Vec::new(),
+            uppercase: false,
+        }
+    }
+
+    pub fn param(mut self, key: &str, value: &str) -> Self {
+        self.params.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn uppercase(mut self, uppercase: bool) -> Self {
+        self.uppercase = uppercase;
+        self
+    }
+}
+
+impl Payload for Url {
+    fn to_payload(&self) -> Result<String, QRError> {
+        let (scheme, rest) = self
+            .url
+            .split_once("://")
+            .filter(|(scheme, _)| {
+                scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https")
+            })
+            .ok_or_else(|| {
+                QRError::InvalidInput(format!("'{}' is not an http(s) URL", self.url))
+            })?;
+
+        let host_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+        let (authority, path) = rest.split_at(host_end);
+        // Credentials are left alone; only the host itself is case-folded.
+        let (userinfo, host) = match authority.rsplit_once('@') {
+            Some((userinfo, host)) => (format!("{}@", userinfo), host),
+            None => (String::new(), authority),
+        };
+        if host.is_empty() || host.contains(char::is_whitespace) {
+            return Err(QRError::InvalidInput(format!(
+                "URL '{}' has no valid host",
+                self.url
+            )));
+        }
+
+        let (scheme, host) = if self.uppercase {
+            (scheme.to_ascii_uppercase(), host.to_ascii_uppercase())
+        } else {
+            (scheme.to_ascii_lowercase(), host.to_ascii_lowercase())
+        };
+
+        let (path, fragment) = match path.split_once('#') {
+            Some((path, fragment)) => (path, Some(fragment)),
+            None => (path, None),
+        };
+        let mut payload = format!(
+            "{}://{}{}{}",
+            scheme,
+            encode_url_part(&userinfo),
+            host,
+            encode_url_part(path)
+        );
+
+        for (i, (key, value)) in self.params.iter().enumerate() {
+            let separator = if i == 0 && !path.contains('?') {
+                '?'
+            } else {
+                '&'
+            };
+            payload.push(separator);
+            payload.push_str(&percent_encode(key, QUERY_SAFE));
+            payload.push('=');
+            payload.push_str(&percent_encode(value, QUERY_SAFE));
+        }
+        if let Some(fragment) = fragment {
+            payload.push('#');
+            payload.push_str(&encode_url_part(fragment));
+        }
+        Ok(payload)
+    }
+}
+
+/// Percent-encodes part of a caller's URL, keeping reserved characters and
+/// existing `%XX` escapes. Any other `%` is escaped as `%25`.
+fn encode_url_part(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(index) = rest.find('%') {
+        encoded.push_str(&percent_encode(&rest[..index], URL_RESERVED));
+        match rest
+            .get(index + 1..index + 3)
+            .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+        {
+            Some(hex) => {
+                encoded.push('%');
+                encoded.push_str(hex);
+                rest = &rest[index + 3..];
+            }
+            None => {
+                encoded.push_str("%25");
+                rest = &rest[index + 1..];
+            }
+        }
+    }
+    encoded.push_str(&percent_encode(rest, URL_RESERVED));
+    encoded
+}
+
+/// Strips visual separators other than `-` from a phone number and checks
+/// what remains is a global (`+`) or local number.
+fn normalize_phone_number(number: &str) -> Result<String, QRError> {
+    let normalized: String = number
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '(' | ')' | '.'))
+        .collect();
+
+    let digits = normalized.strip_prefix('+').unwrap_or(&normalized);
+    let valid = digits.chars().any(|c| c.is_ascii_digit())
+        && digits
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '-' | '*' | '#'));
+    if !valid {
+        return Err(QRError::InvalidInput(format!(
+            "'{}' is not a phone number",
+            number
+        )));
+    }
+    Ok(normalized)
+}
+
+fn is_email_address(address: &str) -> bool {
+    match address.rsplit_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.is_empty()
+                && !address.contains(char::is_whitespace)
+                && !domain.contains(['?', '&', ',', '/'])
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,161 @@
+use super::{escape_with_backslash, split_escaped_fields, Payload};
+use crate::error::QRError;
+use std::str::FromStr;
+
+const SPECIAL_CHARS: [char; 5] = ['\\', ';', ',', ':', '"'];
+
+/// Authentication type of a WiFi network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WifiAuth {
+    /// WPA or WPA2 personal.
+    Wpa,
+    /// WPA3 personal, written as `SAE` in the payload.
+    Wpa3,
+    Wep,
+    /// Open network without a password.
+    NoPass,
+}
+
+impl WifiAuth {
+    fn as_str(self) -> &'static str {
+        match self {
+            WifiAuth::Wpa => "WPA",
+            WifiAuth::Wpa3 => "SAE",
+            WifiAuth::Wep => "WEP",
+            WifiAuth::NoPass => "nopass",
+        }
+    }
+}
+
+impl FromStr for WifiAuth {
+    type Err = QRError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "WPA" | "WPA2" => Ok(WifiAuth::Wpa),
+            "SAE" | "WPA3" => Ok(WifiAuth::Wpa3),
+            "WEP" => Ok(WifiAuth::Wep),
+            "NOPASS" | "" => Ok(WifiAuth::NoPass),
+            _ => Err(QRError::InvalidInput(format!(
+                "Unknown WiFi authentication '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+/// WiFi network configuration, serialised as a `WIFI:` join payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WifiConfig {
+    pub ssid: String,
+    pub password: Option<String>,
+    pub auth: WifiAuth,
+    pub hidden: bool,
+}
+
+impl WifiConfig {
+    /// An open network with the given SSID.
+    pub fn new(ssid: &str) -> Self {
+        WifiConfig {
+            ssid: ssid.to_string(),
+            password: None,
+            auth: WifiAuth::NoPass,
+            hidden: false,
+        }
+    }
+
+    /// Sets the password, switching an open network to WPA.
+    pub fn password(mut self, password: &str) -> Self {
+        self.password = Some(password.to_string());
+        if self.auth == WifiAuth::NoPass {
+            self.auth = WifiAuth::Wpa;
+        }
+        self
+    }
+
+    pub fn auth(mut self, auth: WifiAuth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    fn validate(&self) -> Result<(), QRError> {
+        if self.ssid.is_empty() {
+            return Err(QRError::InvalidInput("SSID cannot be empty".to_string()));
+        }
+
+        match (self.auth, self.password.as_deref()) {
+            (WifiAuth::NoPass, Some(_)) => Err(QRError::InvalidInput(
+                "Open networks cannot have a password".to_string(),
+            )),
+            (WifiAuth::NoPass, None) => Ok(()),
+            (_, None) | (_, Some("")) => Err(QRError::InvalidInput(format!(
+                "{} networks need a password",
+                self.auth.as_str()
+            ))),
+            (_, Some(_)) => Ok(()),
+        }
+    }
+
+    /// Parses a `WIFI:` payload back into its fields.
+    pub fn parse(payload: &str) -> Result<Self, QRError> {
+        let body = payload.strip_prefix("WIFI:").ok_or_else(|| {
+            QRError::InvalidInput("Payload does not start with WIFI:".to_string())
+        })?;
+
+        let mut config = WifiConfig::new("");
+        let mut auth = None;
+        for (key, value) in split_escaped_fields(body)? {
+            match key.as_str() {
+                "S" => config.ssid = value,
+                "P" => config.password = Some(value),
+                "T" => auth = Some(value.parse()?),
+                "H" => config.hidden = value.eq_ignore_ascii_case("true"),
+                _ => {}
+            }
+        }
+
+        config.auth = match auth {
+            Some(auth) => auth,
+            None if config.password.is_some() => WifiAuth::Wpa,
+            None => WifiAuth::NoPass,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+impl Payload for WifiConfig {
+    fn to_payload(&self) -> Result<String, QRError> {
+        self.validate()?;
+
+        let mut payload = format!("WIFI:T:{};", self.auth.as_str());
+        payload.push_str(&format!(
+            "S:{};",
+            escape_with_backslash(&self.ssid, &SPECIAL_CHARS)
+        ));
+        if let Some(password) = &self.password {
+            payload.push_str(&format!(
+                "P:{};",
+                escape_with_backslash(password, &SPECIAL_CHARS)
+            ));
+        }
+        if self.hidden {
+            payload.push_str("H:true;");
+        }
+        payload.push(';');
+        Ok(payload)
+    }
+}
+
+impl FromStr for WifiConfig {
+    type Err = QRError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        WifiConfig::parse(s)
+    }
+}
@@ -0,0 +1,138 @@
+use crate::input::ALPHANUMERIC_CHARS;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Small deterministic pseudo-random generator (SplitMix64). The same seed
+/// always gives the same sequence, so generated inputs and simulated damage
+/// can be reproduced from a failure report.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0..bound`; `bound` must not be zero.
+    pub fn below(&mut self, bound: usize) -> usize {
+        // Multiply-shift keeps the bias negligible for the small bounds used
+        // here without a rejection loop.
+        ((self.next_u64() as u128 * bound as u128) >> 64) as usize
+    }
+
+    /// Uniform value in `0.0..1.0`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// `count` distinct values from `0..bound`, in random order.
+    pub fn choose(&mut self, bound: usize, count: usize) -> Vec<usize> {
+        let mut values: Vec<usize> = (0..bound).collect();
+        let count = count.min(bound);
+        for i in 0..count {
+            let j = i + self.below(bound - i);
+            values.swap(i, j);
+        }
+        values.truncate(count);
+        values
+    }
+}
+
+/// Character sets the input generator draws from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alphabet {
+    Digits,
+    /// The alphanumeric mode set.
+    Alphanumeric,
+    /// Printable ASCII and the Latin-1 supplement.
+    Latin1,
+    /// CJK unified ideographs, kana and hangul.
+    Cjk,
+    Emoji,
+    /// Any Unicode scalar value, control characters included; the widest
+    /// byte-mode content a `&str` can hold.
+    Bytes,
+}
+
+impl Alphabet {
+    pub const ALL: [Alphabet; 6] = [
+        Alphabet::Digits,
+        Alphabet::Alphanumeric,
+        Alphabet::Latin1,
+        Alphabet::Cjk,
+        Alphabet::Emoji,
+        Alphabet::Bytes,
+    ];
+
+    pub fn sample(self, rng: &mut Rng) -> char {
+        let pick = |rng: &mut Rng, ranges: &[(u32, u32)]| {
+            let total: u32 = ranges.iter().map(|(start, end)| end - start + 1).sum();
+            let mut index = rng.below(total as usize) as u32;
+            for &(start, end) in ranges {
+                if index <= end - start {
+                    return char::from_u32(start + index).unwrap_or('?');
+                }
+                index -= end - start + 1;
+            }
+            '?'
+        };
+
+        match self {
+            Alphabet::Digits => (b'0' + rng.below(10) as u8) as char,
+            Alphabet::Alphanumeric => {
+                ALPHANUMERIC_CHARS.as_bytes()[rng.below(ALPHANUMERIC_CHARS.len())] as char
+            }
+            Alphabet::Latin1 => pick(rng, &[(0x20, 0x7E), (0xA0, 0xFF)]),
+            Alphabet::Cjk => pick(rng, &[(0x4E00, 0x9FFF), (0x3040, 0x30FF), (0xAC00, 0xD7A3)]),
+            Alphabet::Emoji => pick(rng, &[(0x1F300, 0x1F5FF), (0x1F600, 0x1F64F)]),
+            Alphabet::Bytes => pick(rng, &[(0x0, 0xD7FF), (0xE000, 0x10FFFF)]),
+        }
+    }
+}
+
+/// Generates reproducible random content for round-trip testing.
+#[derive(Debug, Clone)]
+pub struct InputGenerator {
+    rng: Rng,
+}
+
+impl InputGenerator {
+    pub fn new(seed: u64) -> Self {
+        InputGenerator {
+            rng: Rng::new(seed),
+        }
+    }
+
+    pub fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+
+    /// Text from `alphabet` of at most `max_bytes` UTF-8 bytes, with the
+    /// length drawn uniformly. It is never empty while `max_bytes` is at
+    /// least four, the longest UTF-8 character.
+    pub fn text(&mut self, alphabet: Alphabet, max_bytes: usize) -> String {
+        let target = (1 + self.rng.below(max_bytes.max(1))).max(max_bytes.min(4));
+        let mut text = String::new();
+        // Multi-byte alphabets may overshoot, so a few misses end the text.
+        let mut misses = 0;
+        while text.len() < target && misses < 4 {
+            let c = alphabet.sample(&mut self.rng);
+            if text.len() + c.len_utf8() <= target {
+                text.push(c);
+            } else {
+                misses += 1;
+            }
+        }
+        text
+    }
+}
@@ -0,0 +1,63 @@
+use super::svg::format_number;
+use super::{print_layers, PrintColor, RenderOptions};
+use crate::matrix::QRMatrix;
+
+/// Renders the symbol as an Encapsulated PostScript file.
+///
+/// Each run of dark modules becomes one filled rectangle, sized by
+/// [`RenderOptions::module_size_pt`]. Module shapes are an SVG feature;
+/// EPS uses plain squares. Finder colours get a layer of their own and
+/// gradients take one solid colour per module, as in PNG output.
+pub fn to_eps(matrix: &QRMatrix, options: &RenderOptions) -> String {
+    let dimension = matrix.get_size() + options.border as usize * 2;
+    let module = options.module_size_pt();
+    let extent = dimension as f64 * module;
+
+    let mut eps = String::new();
+    eps.push_str("%!PS-Adobe-3.0 EPSF-3.0\n");
+    eps.push_str(&format!(
+        "%%BoundingBox: 0 0 {} {}\n",
+        extent.ceil(),
+        extent.ceil()
+    ));
+    eps.push_str(&format!(
+        "%%HiResBoundingBox: 0 0 {} {}\n",
+        format_number(extent),
+        format_number(extent)
+    ));
+    eps.push_str("%%Creator: qrgen\n%%Pages: 1\n%%EndComments\n");
+    eps.push_str("gsave\n/R { rectfill } bind def\n");
+
+    for (color, rects) in print_layers(matrix, options) {
+        eps.push_str(&set_color(color));
+        for [x, y, width, height] in rects {
+            eps.push_str(&format!(
+                "{} {} {} {} R\n",
+                format_number(x * module),
+                format_number(y * module),
+                format_number(width * module),
+                format_number(height * module)
+            ));
+        }
+    }
+    eps.push_str("grestore\nshowpage\n%%EOF\n");
+    eps
+}
+
+fn set_color(color: PrintColor) -> String {
+    match color {
+        PrintColor::Rgb(rgb) => format!(
+            "{} {} {} setrgbcolor\n",
+            format_number(rgb.r as f64 / 255.0),
+            format_number(rgb.g as f64 / 255.0),
+            format_number(rgb.b as f64 / 255.0)
+        ),
+        PrintColor::Cmyk(cmyk) => format!(
+            "{} {} {} {} setcmykcolor\n",
+            format_number(cmyk.c),
+            format_number(cmyk.m),
+            format_number(cmyk.y),
+            format_number(cmyk.k)
+        ),
+    }
+}
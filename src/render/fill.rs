@@ -0,0 +1,89 @@
+use super::Color;
+use crate::error::QRError;
+use std::str::FromStr;
+
+/// How dark modules are painted. Gradients span the symbol itself, not the
+/// quiet zone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fill {
+    /// The `dark` colour of the render options.
+    Solid,
+    /// Runs from `start` to `end` along `angle` degrees, 0 pointing right
+    /// and 90 pointing down.
+    LinearGradient {
+        start: Color,
+        end: Color,
+        angle: f64,
+    },
+    /// Runs from `centre` in the middle of the symbol to `edge` at its
+    /// corners.
+    RadialGradient { centre: Color, edge: Color },
+}
+
+impl Fill {
+    /// Every colour the fill can produce at its extremes.
+    pub fn stops(&self, solid: Color) -> Vec<Color> {
+        match *self {
+            Fill::Solid => vec![solid],
+            Fill::LinearGradient { start, end, .. } => vec![start, end],
+            Fill::RadialGradient { centre, edge } => vec![centre, edge],
+        }
+    }
+
+    /// Colour at a point in module units, relative to the symbol's top-left
+    /// corner, for a symbol of `size` modules.
+    pub(crate) fn color_at(&self, solid: Color, x: f64, y: f64, size: f64) -> Color {
+        match *self {
+            Fill::Solid => solid,
+            Fill::LinearGradient { start, end, angle } => {
+                let (dx, dy, extent) = linear_axis(angle, size);
+                let t = ((x - size / 2.0) * dx + (y - size / 2.0) * dy) / extent + 0.5;
+                start.mix(end, t)
+            }
+            Fill::RadialGradient { centre, edge } => {
+                let distance = (x - size / 2.0).hypot(y - size / 2.0);
+                centre.mix(edge, distance / radial_extent(size))
+            }
+        }
+    }
+}
+
+/// Unit direction of a linear gradient and the length of the symbol
+/// projected onto it, so the end colours land on the outermost corners.
+pub(crate) fn linear_axis(angle: f64, size: f64) -> (f64, f64, f64) {
+    let (dy, dx) = angle.to_radians().sin_cos();
+    (dx, dy, size * (dx.abs() + dy.abs()))
+}
+
+/// Distance from the centre of the symbol to its corners.
+pub(crate) fn radial_extent(size: f64) -> f64 {
+    size / std::f64::consts::SQRT_2
+}
+
+impl FromStr for Fill {
+    type Err = QRError;
+
+    /// Parses `solid`, `linear:<start>:<end>[:<angle>]` or
+    /// `radial:<centre>:<edge>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        let invalid = || QRError::InvalidInput(format!("Invalid fill '{}'", s));
+
+        match (parts[0].to_ascii_lowercase().as_str(), parts.len()) {
+            ("solid", 1) => Ok(Fill::Solid),
+            ("linear", 3) | ("linear", 4) => Ok(Fill::LinearGradient {
+                start: parts[1].parse()?,
+                end: parts[2].parse()?,
+                angle: match parts.get(3) {
+                    Some(angle) => angle.parse().map_err(|_| invalid())?,
+                    None => 0.0,
+                },
+            }),
+            ("radial", 3) => Ok(Fill::RadialGradient {
+                centre: parts[1].parse()?,
+                edge: parts[2].parse()?,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use super::Overlay;
+use crate::decoder::{decode_with_erasures, erasures_per_block};
+use crate::detector::{decode_image, GrayImage};
+use crate::encoder::ErrorCorrectionLevel;
+use crate::error::QRError;
+use crate::error_correction::BlockLayout;
+use crate::matrix::QRMatrix;
+use crate::QRData;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Finder patterns, separators and format information take up this many
+/// modules along each edge; the logo stays clear of them.
+const RESERVED_EDGE: usize = 9;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogoImage {
+    /// A complete `<svg>` document.
+    Svg(String),
+    /// The bytes of a PNG file.
+    Png(Vec<u8>),
+}
+
+impl LogoImage {
+    /// Reads an `.svg` or `.png` file.
+    pub fn from_file(path: &str) -> Result<Self, QRError> {
+        let read_error = |e: std::io::Error| {
+            QRError::InvalidInput(format!("Cannot read logo '{}': {}", path, e))
+        };
+        let extension = path.rsplit_once('.').map(|(_, e)| e.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("svg") => Ok(LogoImage::Svg(
+                std::fs::read_to_string(path).map_err(read_error)?,
+            )),
+            Some("png") => Ok(LogoImage::Png(std::fs::read(path).map_err(read_error)?)),
+            _ => Err(QRError::InvalidInput(format!(
+                "Logo '{}' must be an .svg or .png file",
+                path
+            ))),
+        }
+    }
+
+    /// The image as a `data:` URI for an SVG `<image>` element.
+    pub(crate) fn data_uri(&self) -> String {
+        match self {
+            LogoImage::Svg(markup) => {
+                format!("data:image/svg+xml;base64,{}", base64(markup.as_bytes()))
+            }
+            LogoImage::Png(bytes) => format!("data:image/png;base64,{}", base64(bytes)),
+        }
+    }
+}
+
+/// What to do when the logo would hide more than the allowed share of the
+/// error correction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogoPolicy {
+    Refuse,
+    /// Try the next higher error correction level until the logo fits.
+    RaiseLevel,
+}
+
+/// A logo placed over the centre of the symbol.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Logo {
+    pub image: LogoImage,
+    /// Width of the cleared square as a fraction of the symbol width.
+    pub size: f64,
+    /// Largest share of any block's EC codewords the logo may erase. The
+    /// rest stays available for real damage.
+    pub max_erasure_fraction: f64,
+}
+
+/// Module-aligned square cleared for the logo, in symbol coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogoArea {
+    pub x: usize,
+    pub y: usize,
+    pub side: usize,
+}
+
+impl LogoArea {
+    pub fn modules(&self) -> Vec<(usize, usize)> {
+        (self.y..self.y + self.side)
+            .flat_map(|y| (self.x..self.x + self.side).map(move |x| (x, y)))
+            .collect()
+    }
+}
+
+/// A symbol with room cleared for a logo, and the overlay drawing it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogoPlacement {
+    /// The symbol with the covered modules set light.
+    pub matrix: QRMatrix,
+    pub area: LogoArea,
+    /// Erased codewords per block.
+    pub erasures: Vec<usize>,
+    pub overlay: Overlay,
+}
+
+impl Logo {
+    pub fn new(image: LogoImage) -> Self {
+        Logo {
+            image,
+            size: 0.2,
+            max_erasure_fraction: 0.5,
+        }
+    }
+
+    pub fn size(mut self, size: f64) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn max_erasure_fraction(mut self, fraction: f64) -> Self {
+        self.max_erasure_fraction = fraction;
+        self
+    }
+
+    /// The centred square the logo covers. Its side has the same parity as
+    /// the symbol so it sits exactly in the middle.
+    pub fn area(&self, symbol_size: usize) -> Result<LogoArea, QRError> {
+        if !(self.size > 0.0 && self.size < 1.0) {
+            return Err(QRError::InvalidInput(format!(
+                "Logo size {} must be between 0 and 1",
+                self.size
+            )));
+        }
+        let mut side = (symbol_size as f64 * self.size).round().max(1.0) as usize;
+        if side % 2 != symbol_size % 2 {
+            side += 1;
+        }
+
+        if side + RESERVED_EDGE * 2 > symbol_size {
+            return Err(QRError::InvalidInput(format!(
+                "A logo of {} modules does not fit a symbol of {} modules",
+                side, symbol_size
+            )));
+        }
+        let offset = (symbol_size - side) / 2;
+        Ok(LogoArea {
+            x: offset,
+            y: offset,
+            side,
+        })
+    }
+
+    /// Generates the symbol and clears the logo area, checking that the
+    /// erased codewords stay within budget and that the result still decodes,
+    /// both with those modules treated as erasures and from an image the way
+    /// a scanner sees it. From version 7 the area can hide alignment
+    /// patterns, which only the latter notices.
+    ///
+    /// With [`LogoPolicy::RaiseLevel`] higher levels are tried in turn;
+    /// `qr_data` is left at the level that was used.
+    pub fn place(
+        &self,
+        qr_data: &mut QRData,
+        mask: Option<u8>,
+        policy: LogoPolicy,
+    ) -> Result<LogoPlacement, QRError> {
+        let levels = [
+            ErrorCorrectionLevel::L,
+            ErrorCorrectionLevel::M,
+            ErrorCorrectionLevel::Q,
+            ErrorCorrectionLevel::H,
+        ];
+        let start = levels
+            .iter()
+            .position(|&l| l == qr_data.get_ec_level())
+            .unwrap_or(0);
+        let candidates = match policy {
+            LogoPolicy::Refuse => &levels[start..=start],
+            LogoPolicy::RaiseLevel => &levels[start..],
+        };
+
+        let mut last_error = None;
+        for &level in candidates {
+            let mut candidate = qr_data.clone();
+            let attempt = candidate
+                .set_ec_level(level)
+                .and_then(|_| self.place_at_level(&mut candidate, mask));
+            match attempt {
+                Ok(placement) => {
+                    *qr_data = candidate;
+                    return Ok(placement);
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            QRError::InvalidInput("No error correction level to try".to_string())
+        }))
+    }
+
+    fn place_at_level(
+        &self,
+        qr_data: &mut QRData,
+        mask: Option<u8>,
+    ) -> Result<LogoPlacement, QRError> {
+        if qr_data.get_version().is_none() {
+            return Err(QRError::InvalidLength(format!(
+                "Content does not fit at error correction level {:?}",
+                qr_data.get_ec_level()
+            )));
+        }
+
+        let mut matrix = qr_data.generate(mask)?;
+        let area = self.area(matrix.get_size())?;
+        let covered = area.modules();
+
+        let layout = BlockLayout::new(matrix.get_version(), matrix.get_ec_level())?;
+        let erasures = erasures_per_block(&matrix, &covered)?;
+        let allowed = (layout.ecc_per_block as f64 * self.max_erasure_fraction).floor() as usize;
+        if let Some(&worst) = erasures.iter().max().filter(|&&worst| worst > allowed) {
+            return Err(QRError::InvalidInput(format!(
+                "Logo erases {} of {} EC codewords in a block at level {:?}, the limit is {}",
+                worst,
+                layout.ecc_per_block,
+                matrix.get_ec_level(),
+                allowed
+            )));
+        }
+
+        for &(x, y) in &covered {
+            matrix.set_module(x, y, false);
+        }
+        let content = qr_data.get_input().get_content().as_bytes();
+        let decoded = decode_with_erasures(&matrix, &covered)?;
+        let scanned = decode_image(&rasterize(&matrix)?).map_err(|e| {
+            QRError::EncodingError(format!("Symbol with logo cannot be scanned: {}", e))
+        })?;
+        if decoded.content != content || scanned.content != content {
+            return Err(QRError::EncodingError(
+                "Symbol with logo decodes to different content".to_string(),
+            ));
+        }
+
+        Ok(LogoPlacement {
+            matrix,
+            area,
+            erasures,
+            overlay: Overlay::Logo {
+                image: self.image.clone(),
+                area,
+            },
+        })
+    }
+}
+
+/// The symbol at a few pixels per module inside a quiet zone, with the
+/// logo area left blank.
+fn rasterize(matrix: &QRMatrix) -> Result<GrayImage, QRError> {
+    const SCALE: usize = 4;
+    const QUIET_ZONE: usize = 4;
+    let width = (matrix.get_size() + QUIET_ZONE * 2) * SCALE;
+    let pixels = (0..width * width)
+        .map(|i| {
+            let x = (i % width / SCALE).checked_sub(QUIET_ZONE);
+            let y = (i / width / SCALE).checked_sub(QUIET_ZONE);
+            match (x, y) {
+                (Some(x), Some(y))
+                    if x < matrix.get_size() && y < matrix.get_size() && matrix.is_dark(x, y) =>
+                {
+                    0
+                }
+                _ => 255,
+            }
+        })
+        .collect();
+    GrayImage::new(width, width, pixels)
+}
+
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let value = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - i * 8));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[(value >> (18 - i * 6)) as usize & 0x3F] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
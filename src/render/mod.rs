@@ -0,0 +1,437 @@
+pub mod eps;
+pub mod fill;
+pub mod logo;
+pub mod overlay;
+pub mod pdf;
+pub mod png;
+pub mod style;
+pub mod svg;
+pub mod terminal;
+
+use crate::error::QRError;
+use crate::matrix::{ModuleKind, QRMatrix};
+use std::str::FromStr;
+
+pub use eps::to_eps;
+pub use fill::Fill;
+pub use logo::{Logo, LogoArea, LogoImage, LogoPlacement, LogoPolicy};
+pub use overlay::Overlay;
+pub use pdf::to_pdf;
+pub use png::to_png;
+pub use style::{FinderShape, ModuleShape, Style};
+pub use svg::to_svg;
+pub use terminal::{to_annotated, to_terminal};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const BLACK: Color = Color { r: 0, g: 0, b: 0 };
+    pub const WHITE: Color = Color {
+        r: 255,
+        g: 255,
+        b: 255,
+    };
+
+    pub fn to_hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    /// WCAG relative luminance, from 0 for black to 1 for white.
+    pub fn relative_luminance(self) -> f64 {
+        let linear = |channel: u8| {
+            let c = channel as f64 / 255.0;
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+
+    /// WCAG contrast ratio between two colours, from 1 to 21.
+    pub fn contrast_ratio(self, other: Color) -> f64 {
+        let (a, b) = (self.relative_luminance(), other.relative_luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// Linear blend towards `other`; `t` is clamped to 0-1.
+    pub fn mix(self, other: Color, t: f64) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let blend = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        Color {
+            r: blend(self.r, other.r),
+            g: blend(self.g, other.g),
+            b: blend(self.b, other.b),
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = QRError;
+
+    /// Parses `#rrggbb`, `#rgb` (the `#` is optional), `black` or `white`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "black" => return Ok(Color::BLACK),
+            "white" => return Ok(Color::WHITE),
+            _ => {}
+        }
+
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        let invalid = || QRError::InvalidInput(format!("Invalid colour '{}'", s));
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let channel = |digits: &str| u8::from_str_radix(digits, 16).map_err(|_| invalid());
+        match hex.len() {
+            6 => Ok(Color {
+                r: channel(&hex[0..2])?,
+                g: channel(&hex[2..4])?,
+                b: channel(&hex[4..6])?,
+            }),
+            3 => Ok(Color {
+                r: channel(&hex[0..1])? * 17,
+                g: channel(&hex[1..2])? * 17,
+                b: channel(&hex[2..3])? * 17,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// A process colour for print output, each component from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cmyk {
+    pub c: f64,
+    pub m: f64,
+    pub y: f64,
+    pub k: f64,
+}
+
+impl From<Color> for Cmyk {
+    /// Naive conversion without a colour profile; give exact values for
+    /// brand colours.
+    fn from(color: Color) -> Self {
+        let (r, g, b) = (
+            color.r as f64 / 255.0,
+            color.g as f64 / 255.0,
+            color.b as f64 / 255.0,
+        );
+        let k = 1.0 - r.max(g).max(b);
+        if k >= 1.0 {
+            return Cmyk {
+                c: 0.0,
+                m: 0.0,
+                y: 0.0,
+                k: 1.0,
+            };
+        }
+        Cmyk {
+            c: (1.0 - r - k) / (1.0 - k),
+            m: (1.0 - g - k) / (1.0 - k),
+            y: (1.0 - b - k) / (1.0 - k),
+            k,
+        }
+    }
+}
+
+impl FromStr for Cmyk {
+    type Err = QRError;
+
+    /// Parses four percentages such as `0,0,0,100`, optionally prefixed with
+    /// `cmyk:`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || QRError::InvalidInput(format!("Invalid CMYK colour '{}'", s));
+        let body = s.strip_prefix("cmyk:").unwrap_or(s);
+        let values = body
+            .split(',')
+            .map(|part| {
+                part.trim()
+                    .trim_end_matches('%')
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|v| (0.0..=100.0).contains(v))
+                    .map(|v| v / 100.0)
+                    .ok_or_else(invalid)
+            })
+            .collect::<Result<Vec<f64>, QRError>>()?;
+
+        match values[..] {
+            [c, m, y, k] => Ok(Cmyk { c, m, y, k }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Colour as written by the print formats.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PrintColor {
+    Rgb(Color),
+    Cmyk(Cmyk),
+}
+
+/// Points per millimetre.
+pub const POINTS_PER_MM: f64 = 72.0 / 25.4;
+
+/// Contrast below this ratio between dark and light modules is rejected;
+/// many phone scanners fail to binarise such symbols.
+pub const MIN_CONTRAST_RATIO: f64 = 3.0;
+
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Pixels (or SVG user units) per module.
+    pub scale: u32,
+    /// Quiet zone width in modules.
+    pub border: u32,
+    pub dark: Color,
+    pub light: Color,
+    /// Physical module size. Sets the SVG dimensions in millimetres, the
+    /// PNG resolution and the EPS and PDF page size so the symbol prints at
+    /// the intended size.
+    pub module_size_mm: Option<f64>,
+    pub overlay: Option<Overlay>,
+    /// Module and finder shapes. Only the SVG renderer draws them; other
+    /// formats always use squares.
+    pub style: Style,
+    /// Paint of the dark modules; [`Fill::Solid`] uses `dark`.
+    pub fill: Fill,
+    /// Separate colour for the finder patterns.
+    pub finder_color: Option<Color>,
+    /// Accept light modules that are darker than dark ones. Not every
+    /// scanner reads such inverted symbols.
+    pub allow_inverted: bool,
+    /// CMYK values for the dark and light modules in EPS and PDF output.
+    /// When only one is given the other is converted from its RGB colour.
+    pub dark_cmyk: Option<Cmyk>,
+    pub light_cmyk: Option<Cmyk>,
+}
+
+impl RenderOptions {
+    /// Checks every dark colour in use against the light colour: the
+    /// contrast must reach [`MIN_CONTRAST_RATIO`] and, unless
+    /// `allow_inverted` is set, the dark colour must be the darker one.
+    pub fn check_contrast(&self) -> Result<(), QRError> {
+        let mut colors = self.fill.stops(self.dark);
+        colors.extend(self.finder_color);
+
+        for color in colors {
+            let ratio = color.contrast_ratio(self.light);
+            if ratio < MIN_CONTRAST_RATIO {
+                return Err(QRError::InvalidInput(format!(
+                    "Contrast between {} and {} is {:.2}:1, at least {}:1 is needed",
+                    color.to_hex(),
+                    self.light.to_hex(),
+                    ratio,
+                    MIN_CONTRAST_RATIO
+                )));
+            }
+            if !self.allow_inverted && color.relative_luminance() > self.light.relative_luminance()
+            {
+                return Err(QRError::InvalidInput(format!(
+                    "Dark colour {} is lighter than light colour {}; allow inverted symbols to use it",
+                    color.to_hex(),
+                    self.light.to_hex()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Colour of the dark module at matrix position (`x`, `y`).
+    pub(crate) fn dark_color_at(&self, matrix: &QRMatrix, x: usize, y: usize) -> Color {
+        match self.finder_color {
+            Some(color) if matrix.get_kind(x, y) == ModuleKind::FinderPattern => color,
+            _ => self.fill.color_at(
+                self.dark,
+                x as f64 + 0.5,
+                y as f64 + 0.5,
+                matrix.get_size() as f64,
+            ),
+        }
+    }
+
+    /// Size of a module in the print formats: `module_size_mm` when set,
+    /// otherwise `scale` points.
+    pub fn module_size_pt(&self) -> f64 {
+        match self.module_size_mm {
+            Some(mm) => mm * POINTS_PER_MM,
+            None => self.scale as f64,
+        }
+    }
+
+    /// Colour space and values used for `color` in the print formats.
+    pub(crate) fn print_color(&self, color: Color) -> PrintColor {
+        if self.dark_cmyk.is_none() && self.light_cmyk.is_none() {
+            return PrintColor::Rgb(color);
+        }
+        let exact = if color == self.dark {
+            self.dark_cmyk
+        } else if color == self.light {
+            self.light_cmyk
+        } else {
+            None
+        };
+        PrintColor::Cmyk(exact.unwrap_or_else(|| Cmyk::from(color)))
+    }
+
+    /// Overlay rectangles for a symbol of the given size, if any.
+    pub(crate) fn overlay_rects(&self, symbol_size: usize) -> Vec<overlay::FilledRect> {
+        self.overlay
+            .as_ref()
+            .map(|overlay| overlay.rects(symbol_size, self.border, self.light))
+            .unwrap_or_default()
+    }
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            scale: 8,
+            border: 4,
+            dark: Color::BLACK,
+            light: Color::WHITE,
+            module_size_mm: None,
+            overlay: None,
+            style: Style::default(),
+            fill: Fill::Solid,
+            finder_color: None,
+            allow_inverted: false,
+            dark_cmyk: None,
+            light_cmyk: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Svg,
+    Png,
+    Eps,
+    Pdf,
+}
+
+impl ImageFormat {
+    /// Infers the format from the extension of a file name.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let (_, extension) = path.rsplit_once('.')?;
+        match extension.to_ascii_lowercase().as_str() {
+            "svg" => Some(ImageFormat::Svg),
+            "png" => Some(ImageFormat::Png),
+            "eps" => Some(ImageFormat::Eps),
+            "pdf" => Some(ImageFormat::Pdf),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Svg => "svg",
+            ImageFormat::Png => "png",
+            ImageFormat::Eps => "eps",
+            ImageFormat::Pdf => "pdf",
+        }
+    }
+}
+
+/// Renders the symbol into the bytes of an image file of the given format.
+pub fn render(matrix: &QRMatrix, format: ImageFormat, options: &RenderOptions) -> Vec<u8> {
+    match format {
+        ImageFormat::Svg => to_svg(matrix, options).into_bytes(),
+        ImageFormat::Png => to_png(matrix, options),
+        ImageFormat::Eps => to_eps(matrix, options).into_bytes(),
+        ImageFormat::Pdf => to_pdf(matrix, options),
+    }
+}
+
+/// Module colour at a position relative to the outer edge of the quiet zone.
+pub(crate) fn is_dark_with_border(matrix: &QRMatrix, border: u32, x: usize, y: usize) -> bool {
+    let border = border as usize;
+    let size = matrix.get_size();
+
+    if x < border || y < border || x >= size + border || y >= size + border {
+        return false;
+    }
+    matrix.is_dark(x - border, y - border)
+}
+
+/// Horizontal runs of dark modules of one colour as `(x, y, length,
+/// colour)` in symbol coordinates, so vector formats draw one rectangle per
+/// run. Gradients are sampled at the centre of each module.
+pub(crate) fn dark_runs(
+    matrix: &QRMatrix,
+    options: &RenderOptions,
+) -> Vec<(usize, usize, usize, Color)> {
+    let size = matrix.get_size();
+    let mut runs = Vec::new();
+    for y in 0..size {
+        let mut x = 0;
+        while x < size {
+            if !matrix.is_dark(x, y) {
+                x += 1;
+                continue;
+            }
+            let start = x;
+            let color = options.dark_color_at(matrix, x, y);
+            while x < size && matrix.is_dark(x, y) && options.dark_color_at(matrix, x, y) == color {
+                x += 1;
+            }
+            runs.push((start, y, x - start, color));
+        }
+    }
+    runs
+}
+
+/// Filled rectangles `[x, y, width, height]` for the print formats, grouped
+/// by colour in painting order. Units are modules with the origin at the
+/// bottom-left corner of the quiet zone, as in PostScript and PDF.
+pub(crate) fn print_layers(
+    matrix: &QRMatrix,
+    options: &RenderOptions,
+) -> Vec<(PrintColor, Vec<[f64; 4]>)> {
+    let size = matrix.get_size();
+    let border = options.border as usize;
+    let dimension = (size + border * 2) as f64;
+
+    let mut dark_layers: Vec<(Color, Vec<[f64; 4]>)> = Vec::new();
+    for (x, y, length, color) in dark_runs(matrix, options) {
+        let rect = [
+            (x + border) as f64,
+            dimension - (y + border) as f64 - 1.0,
+            length as f64,
+            1.0,
+        ];
+        match dark_layers.iter_mut().find(|(layer, _)| *layer == color) {
+            Some((_, rects)) => rects.push(rect),
+            None => dark_layers.push((color, vec![rect])),
+        }
+    }
+
+    let mut layers = vec![(
+        options.print_color(options.light),
+        vec![[0.0, 0.0, dimension, dimension]],
+    )];
+    layers.extend(
+        dark_layers
+            .into_iter()
+            .map(|(color, rects)| (options.print_color(color), rects)),
+    );
+    for rect in options.overlay_rects(size) {
+        layers.push((
+            options.print_color(rect.color),
+            vec![[
+                rect.x,
+                dimension - rect.y - rect.height,
+                rect.width,
+                rect.height,
+            ]],
+        ));
+    }
+    layers
+}
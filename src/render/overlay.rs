@@ -0,0 +1,78 @@
+use super::logo::{LogoArea, LogoImage};
+use super::Color;
+
+// The Swiss cross is 7 mm wide on a 46 mm symbol.
+const SWISS_CROSS_RATIO: f64 = 7.0 / 46.0;
+
+/// Artwork drawn over the centre of the symbol after the modules.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Overlay {
+    /// The Swiss cross required on QR-bill payment codes.
+    SwissCross,
+    /// A logo over a cleared, module-aligned area; see [`super::Logo`].
+    Logo { image: LogoImage, area: LogoArea },
+}
+
+/// A filled rectangle in module units, measured from the outer edge of the
+/// quiet zone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct FilledRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub color: Color,
+}
+
+impl Overlay {
+    /// Rectangles making up the overlay, in drawing order.
+    pub(crate) fn rects(&self, symbol_size: usize, border: u32, light: Color) -> Vec<FilledRect> {
+        match self {
+            Overlay::SwissCross => swiss_cross(symbol_size, border),
+            Overlay::Logo { area, .. } => vec![FilledRect {
+                x: (area.x + border as usize) as f64,
+                y: (area.y + border as usize) as f64,
+                width: area.side as f64,
+                height: area.side as f64,
+                color: light,
+            }],
+        }
+    }
+}
+
+/// White frame, black square and white cross, with the cross arms in the
+/// 6:7 proportion of the Swiss flag.
+fn swiss_cross(symbol_size: usize, border: u32) -> Vec<FilledRect> {
+    let outer = symbol_size as f64 * SWISS_CROSS_RATIO;
+    let centre = border as f64 + symbol_size as f64 / 2.0;
+    let square = |size: f64, color: Color| FilledRect {
+        x: centre - size / 2.0,
+        y: centre - size / 2.0,
+        width: size,
+        height: size,
+        color,
+    };
+
+    let inner = outer * 6.0 / 7.0;
+    let arm_width = inner * 6.0 / 32.0;
+    let arm_length = inner * 20.0 / 32.0;
+
+    vec![
+        square(outer, Color::WHITE),
+        square(inner, Color::BLACK),
+        FilledRect {
+            x: centre - arm_width / 2.0,
+            y: centre - arm_length / 2.0,
+            width: arm_width,
+            height: arm_length,
+            color: Color::WHITE,
+        },
+        FilledRect {
+            x: centre - arm_length / 2.0,
+            y: centre - arm_width / 2.0,
+            width: arm_length,
+            height: arm_width,
+            color: Color::WHITE,
+        },
+    ]
+}
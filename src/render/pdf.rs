@@ -0,0 +1,86 @@
+use super::svg::format_number;
+use super::{print_layers, PrintColor, RenderOptions};
+use crate::matrix::QRMatrix;
+
+/// Renders the symbol as a single-page PDF whose page is exactly the
+/// symbol plus its quiet zone.
+///
+/// Each run of dark modules becomes one filled rectangle, sized by
+/// [`RenderOptions::module_size_pt`]. Module shapes are an SVG feature;
+/// PDF uses plain squares. Finder colours get a layer of their own and
+/// gradients take one solid colour per module, as in PNG output.
+pub fn to_pdf(matrix: &QRMatrix, options: &RenderOptions) -> Vec<u8> {
+    let dimension = matrix.get_size() + options.border as usize * 2;
+    let module = options.module_size_pt();
+    let extent = format_number(dimension as f64 * module);
+
+    let mut content = String::new();
+    for (color, rects) in print_layers(matrix, options) {
+        content.push_str(&fill_color(color));
+        for [x, y, width, height] in rects {
+            content.push_str(&format!(
+                "{} {} {} {} re\n",
+                format_number(x * module),
+                format_number(y * module),
+                format_number(width * module),
+                format_number(height * module)
+            ));
+        }
+        content.push_str("f\n");
+    }
+
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Contents 4 0 R /Resources << >> >>",
+            extent, extent
+        ),
+        format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ),
+    ];
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.push_str(&format!("{} 0 obj\n{}\nendobj\n", i + 1, object));
+    }
+
+    // Cross-reference entries are exactly 20 bytes each.
+    let xref = pdf.len();
+    pdf.push_str(&format!(
+        "xref\n0 {}\n0000000000 65535 f \n",
+        objects.len() + 1
+    ));
+    for offset in offsets {
+        pdf.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    pdf.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    ));
+    pdf.into_bytes()
+}
+
+fn fill_color(color: PrintColor) -> String {
+    match color {
+        PrintColor::Rgb(rgb) => format!(
+            "{} {} {} rg\n",
+            format_number(rgb.r as f64 / 255.0),
+            format_number(rgb.g as f64 / 255.0),
+            format_number(rgb.b as f64 / 255.0)
+        ),
+        PrintColor::Cmyk(cmyk) => format!(
+            "{} {} {} {} k\n",
+            format_number(cmyk.c),
+            format_number(cmyk.m),
+            format_number(cmyk.y),
+            format_number(cmyk.k)
+        ),
+    }
+}
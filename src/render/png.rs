@@ -0,0 +1,130 @@
+use super::{is_dark_with_border, RenderOptions};
+use crate::matrix::QRMatrix;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+// Stored deflate blocks carry at most this many bytes each.
+const MAX_STORED_BLOCK: usize = 65535;
+
+/// Renders the symbol as an 8-bit RGB PNG image.
+pub fn to_png(matrix: &QRMatrix, options: &RenderOptions) -> Vec<u8> {
+    let scale = options.scale.max(1) as usize;
+    let dimension = matrix.get_size() + options.border as usize * 2;
+    let pixels = dimension * scale;
+
+    let border = options.border as usize;
+    let mut canvas = Vec::with_capacity(pixels * pixels);
+    for y in 0..pixels {
+        for x in 0..pixels {
+            let (module_x, module_y) = (x / scale, y / scale);
+            canvas.push(
+                if is_dark_with_border(matrix, options.border, module_x, module_y) {
+                    options.dark_color_at(matrix, module_x - border, module_y - border)
+                } else {
+                    options.light
+                },
+            );
+        }
+    }
+
+    // Overlays cover every pixel whose centre lies inside them.
+    for rect in options.overlay_rects(matrix.get_size()) {
+        let to_pixels = |value: f64| ((value * scale as f64) - 0.5).ceil().max(0.0) as usize;
+        let (x0, x1) = (
+            to_pixels(rect.x),
+            to_pixels(rect.x + rect.width).min(pixels),
+        );
+        let (y0, y1) = (
+            to_pixels(rect.y),
+            to_pixels(rect.y + rect.height).min(pixels),
+        );
+        for y in y0..y1 {
+            for x in x0..x1 {
+                canvas[y * pixels + x] = rect.color;
+            }
+        }
+    }
+
+    let mut raw = Vec::with_capacity(pixels * (pixels * 3 + 1));
+    for row in canvas.chunks(pixels) {
+        // Filter type 0 (none) for every scanline.
+        raw.push(0);
+        for color in row {
+            raw.extend_from_slice(&[color.r, color.g, color.b]);
+        }
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(pixels as u32).to_be_bytes());
+    header.extend_from_slice(&(pixels as u32).to_be_bytes());
+    // Bit depth 8, colour type 2 (RGB), default compression, filter and interlace.
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut png = PNG_SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    if let Some(mm) = options.module_size_mm {
+        let pixels_per_metre = (scale as f64 / mm * 1000.0).round() as u32;
+        let mut physical = Vec::with_capacity(9);
+        physical.extend_from_slice(&pixels_per_metre.to_be_bytes());
+        physical.extend_from_slice(&pixels_per_metre.to_be_bytes());
+        // Unit specifier 1: the values are pixels per metre.
+        physical.push(1);
+        write_chunk(&mut png, b"pHYs", &physical);
+    }
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+
+    let mut crc_input = kind.to_vec();
+    crc_input.extend_from_slice(data);
+    png.extend_from_slice(&crc32(&crc_input).to_be_bytes());
+}
+
+/// Wraps `data` in a zlib stream made of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut output = vec![0x78, 0x01];
+    let mut chunks = data.chunks(MAX_STORED_BLOCK).peekable();
+
+    if chunks.peek().is_none() {
+        output.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        let len = chunk.len() as u16;
+        output.push(last as u8);
+        output.extend_from_slice(&len.to_le_bytes());
+        output.extend_from_slice(&(!len).to_le_bytes());
+        output.extend_from_slice(chunk);
+    }
+
+    output.extend_from_slice(&adler32(data).to_be_bytes());
+    output
+}
+
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+pub(crate) fn adler32(data: &[u8]) -> u32 {
+    let mut a = 1u32;
+    let mut b = 0u32;
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
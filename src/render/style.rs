@@ -0,0 +1,118 @@
+use crate::error::QRError;
+use std::str::FromStr;
+
+/// Shape of the dark data modules in SVG output.
+///
+/// Function patterns other than the finders are always drawn as plain
+/// squares so timing and alignment stay readable whatever the style.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModuleShape {
+    Square,
+    /// Square with every corner rounded; the radius is in modules, up to 0.5.
+    Rounded {
+        radius: f64,
+    },
+    /// Circle filling the module.
+    Circle,
+    /// Squares that merge with dark neighbours, rounding only the outer
+    /// corners of each run.
+    Connected {
+        radius: f64,
+    },
+}
+
+/// Shape of the outer ring or the inner eye of a finder pattern.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FinderShape {
+    Square,
+    /// Rounded corners, radius in modules.
+    Rounded {
+        radius: f64,
+    },
+    Circle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Style {
+    pub module: ModuleShape,
+    /// The 7x7 ring of each finder pattern.
+    pub finder_ring: FinderShape,
+    /// The 3x3 centre of each finder pattern.
+    pub finder_eye: FinderShape,
+}
+
+impl Style {
+    /// Whether every module is drawn as a plain square.
+    pub fn is_plain(&self) -> bool {
+        *self == Style::default()
+    }
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            module: ModuleShape::Square,
+            finder_ring: FinderShape::Square,
+            finder_eye: FinderShape::Square,
+        }
+    }
+}
+
+impl FromStr for ModuleShape {
+    type Err = QRError;
+
+    /// Parses `square`, `rounded`, `circle` or `connected`, with an optional
+    /// radius such as `rounded:0.3`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, radius) = parse_shape(s)?;
+        match name.as_str() {
+            "square" => Ok(ModuleShape::Square),
+            "rounded" => Ok(ModuleShape::Rounded {
+                radius: radius.unwrap_or(0.3),
+            }),
+            "circle" | "dot" => Ok(ModuleShape::Circle),
+            "connected" => Ok(ModuleShape::Connected {
+                radius: radius.unwrap_or(0.5),
+            }),
+            _ => Err(QRError::InvalidInput(format!(
+                "Unknown module shape '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+impl FromStr for FinderShape {
+    type Err = QRError;
+
+    /// Parses `square`, `rounded` (optionally `rounded:<radius>`) or `circle`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, radius) = parse_shape(s)?;
+        match name.as_str() {
+            "square" => Ok(FinderShape::Square),
+            "rounded" => Ok(FinderShape::Rounded {
+                radius: radius.unwrap_or(1.0),
+            }),
+            "circle" => Ok(FinderShape::Circle),
+            _ => Err(QRError::InvalidInput(format!(
+                "Unknown finder shape '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+fn parse_shape(s: &str) -> Result<(String, Option<f64>), QRError> {
+    let lower = s.to_ascii_lowercase();
+    match lower.split_once(':') {
+        Some((name, radius)) => {
+            let radius = radius
+                .parse::<f64>()
+                .ok()
+                .filter(|r| r.is_finite() && *r >= 0.0)
+                .ok_or_else(|| QRError::InvalidInput(format!("Invalid radius in '{}'", s)))?;
+            Ok((name.to_string(), Some(radius)))
+        }
+        None => Ok((lower, None)),
+    }
+}
@@ -0,0 +1,272 @@
+use super::fill::{linear_axis, radial_extent, Fill};
+use super::style::{FinderShape, ModuleShape};
+use super::{Overlay, RenderOptions};
+use crate::matrix::{ModuleKind, QRMatrix};
+
+/// Renders the symbol as a standalone SVG document.
+///
+/// The drawing uses one user unit per module so the output stays crisp at any
+/// size; `scale` only sets the default width and height.
+pub fn to_svg(matrix: &QRMatrix, options: &RenderOptions) -> String {
+    let size = matrix.get_size();
+    let border = options.border as usize;
+    let dimension = size + border * 2;
+    let (width, height) = match options.module_size_mm {
+        Some(mm) => {
+            let size = format!("{}mm", format_number(dimension as f64 * mm));
+            (size.clone(), size)
+        }
+        None => {
+            let pixels = (dimension as u32 * options.scale).to_string();
+            (pixels.clone(), pixels)
+        }
+    };
+
+    let style = &options.style;
+    let plain_finders =
+        style.finder_ring == FinderShape::Square && style.finder_eye == FinderShape::Square;
+    // Finders get their own path when they are shaped or coloured apart.
+    let separate_finders = !plain_finders || options.finder_color.is_some();
+
+    let mut path = String::new();
+    for y in 0..size {
+        for x in 0..size {
+            if !matrix.is_dark(x, y) {
+                continue;
+            }
+            let kind = matrix.get_kind(x, y);
+            if kind == ModuleKind::FinderPattern && separate_finders {
+                continue;
+            }
+            // Only data modules take the styled shape; timing, alignment and
+            // the other function patterns keep their square outline.
+            let shape = if kind.is_function() {
+                ModuleShape::Square
+            } else {
+                style.module
+            };
+
+            if !path.is_empty() {
+                path.push(' ');
+            }
+            path.push_str(&module_path(matrix, x, y, border, shape));
+        }
+    }
+
+    let mut svg = String::new();
+    svg.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    svg.push_str(&format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\"{}>\n",
+        width,
+        height,
+        dimension,
+        dimension,
+        // Curved shapes need anti-aliasing.
+        if style.is_plain() {
+            " shape-rendering=\"crispEdges\""
+        } else {
+            ""
+        }
+    ));
+    let dark_paint = match gradient_def(options.fill, size, border) {
+        Some(def) => {
+            svg.push_str(&format!("<defs>\n{}</defs>\n", def));
+            format!("url(#{})", FILL_ID)
+        }
+        None => options.dark.to_hex(),
+    };
+    svg.push_str(&format!(
+        "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n",
+        options.light.to_hex()
+    ));
+    svg.push_str(&format!("<path d=\"{}\" fill=\"{}\"/>\n", path, dark_paint));
+    if separate_finders {
+        svg.push_str(&format!(
+            "<path d=\"{}\" fill=\"{}\" fill-rule=\"evenodd\"/>\n",
+            finder_path(size, border, style.finder_ring, style.finder_eye),
+            options
+                .finder_color
+                .map(|c| c.to_hex())
+                .unwrap_or_else(|| dark_paint.clone())
+        ));
+    }
+    for rect in options.overlay_rects(size) {
+        svg.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+            format_number(rect.x),
+            format_number(rect.y),
+            format_number(rect.width),
+            format_number(rect.height),
+            rect.color.to_hex()
+        ));
+    }
+    if let Some(Overlay::Logo { image, area }) = &options.overlay {
+        svg.push_str(&format!(
+            "<image xmlns:xlink=\"http://www.w3.org/1999/xlink\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" preserveAspectRatio=\"xMidYMid meet\" xlink:href=\"{}\"/>\n",
+            area.x + border,
+            area.y + border,
+            area.side,
+            area.side,
+            image.data_uri()
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Id of the gradient element referenced by the dark modules.
+const FILL_ID: &str = "qr-fill";
+
+/// The gradient element for a non-solid fill, spanning the symbol without
+/// its quiet zone.
+fn gradient_def(fill: Fill, size: usize, border: usize) -> Option<String> {
+    let centre = border as f64 + size as f64 / 2.0;
+    let stops = |from: super::Color, to: super::Color| {
+        format!(
+            "<stop offset=\"0\" stop-color=\"{}\"/>\n<stop offset=\"1\" stop-color=\"{}\"/>\n",
+            from.to_hex(),
+            to.to_hex()
+        )
+    };
+
+    match fill {
+        Fill::Solid => None,
+        Fill::LinearGradient { start, end, angle } => {
+            let (dx, dy, extent) = linear_axis(angle, size as f64);
+            let (half_x, half_y) = (dx * extent / 2.0, dy * extent / 2.0);
+            Some(format!(
+                "<linearGradient id=\"{}\" gradientUnits=\"userSpaceOnUse\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\">\n{}</linearGradient>\n",
+                FILL_ID,
+                format_number(centre - half_x),
+                format_number(centre - half_y),
+                format_number(centre + half_x),
+                format_number(centre + half_y),
+                stops(start, end)
+            ))
+        }
+        Fill::RadialGradient { centre: inner, edge } => Some(format!(
+            "<radialGradient id=\"{}\" gradientUnits=\"userSpaceOnUse\" cx=\"{}\" cy=\"{}\" r=\"{}\">\n{}</radialGradient>\n",
+            FILL_ID,
+            format_number(centre),
+            format_number(centre),
+            format_number(radial_extent(size as f64)),
+            stops(inner, edge)
+        )),
+    }
+}
+
+/// Outline of one dark module at matrix position (`x`, `y`).
+fn module_path(matrix: &QRMatrix, x: usize, y: usize, border: usize, shape: ModuleShape) -> String {
+    let (left, top) = ((x + border) as f64, (y + border) as f64);
+    match shape {
+        ModuleShape::Square => format!("M{},{}h1v1h-1z", x + border, y + border),
+        ModuleShape::Rounded { radius } => rect_path(left, top, 1.0, [radius; 4]),
+        ModuleShape::Circle => circle_path(left + 0.5, top + 0.5, 0.5),
+        ModuleShape::Connected { radius } => {
+            let size = matrix.get_size() as isize;
+            let dark = |dx: isize, dy: isize| {
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                nx >= 0
+                    && ny >= 0
+                    && nx < size
+                    && ny < size
+                    && matrix.is_dark(nx as usize, ny as usize)
+            };
+            // A corner is rounded only where neither neighbour beside it is
+            // dark, so runs of modules merge into one rounded shape.
+            let corner = |dx: isize, dy: isize| {
+                if dark(dx, 0) || dark(0, dy) {
+                    0.0
+                } else {
+                    radius
+                }
+            };
+            rect_path(
+                left,
+                top,
+                1.0,
+                [corner(-1, -1), corner(1, -1), corner(1, 1), corner(-1, 1)],
+            )
+        }
+    }
+}
+
+/// The three finder patterns as one even-odd path: ring outline, ring hole
+/// and eye for each.
+fn finder_path(size: usize, border: usize, ring: FinderShape, eye: FinderShape) -> String {
+    let shape_path = |left: f64, top: f64, side: f64, shape: FinderShape, inset: f64| match shape {
+        FinderShape::Square => rect_path(left, top, side, [0.0; 4]),
+        FinderShape::Rounded { radius } => {
+            rect_path(left, top, side, [(radius - inset).max(0.0); 4])
+        }
+        FinderShape::Circle => circle_path(left + side / 2.0, top + side / 2.0, side / 2.0),
+    };
+
+    let corners = [(0, 0), (size - 7, 0), (0, size - 7)];
+    corners
+        .iter()
+        .map(|&(x, y)| {
+            let (left, top) = ((x + border) as f64, (y + border) as f64);
+            [
+                shape_path(left, top, 7.0, ring, 0.0),
+                shape_path(left + 1.0, top + 1.0, 5.0, ring, 1.0),
+                shape_path(left + 2.0, top + 2.0, 3.0, eye, 0.0),
+            ]
+            .join(" ")
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// A square with individually rounded corners, clockwise from the top left.
+/// Radii are clamped to half the side.
+fn rect_path(left: f64, top: f64, side: f64, radii: [f64; 4]) -> String {
+    let [tl, tr, br, bl] = radii.map(|r| r.clamp(0.0, side / 2.0));
+    let (right, bottom) = (left + side, top + side);
+    let n = format_number;
+
+    let arc = |radius: f64, x: f64, y: f64| {
+        if radius > 0.0 {
+            format!("A{},{} 0 0 1 {},{}", n(radius), n(radius), n(x), n(y))
+        } else {
+            String::new()
+        }
+    };
+
+    [
+        format!("M{},{}H{}", n(left + tl), n(top), n(right - tr)),
+        arc(tr, right, top + tr),
+        format!("V{}", n(bottom - br)),
+        arc(br, right - br, bottom),
+        format!("H{}", n(left + bl)),
+        arc(bl, left, bottom - bl),
+        format!("V{}", n(top + tl)),
+        arc(tl, left + tl, top),
+        "z".to_string(),
+    ]
+    .concat()
+}
+
+fn circle_path(cx: f64, cy: f64, radius: f64) -> String {
+    let n = format_number;
+    format!(
+        "M{},{}a{},{} 0 1 0 {},0a{},{} 0 1 0 {},0z",
+        n(cx - radius),
+        n(cy),
+        n(radius),
+        n(radius),
+        n(radius * 2.0),
+        n(radius),
+        n(radius),
+        n(-radius * 2.0)
+    )
+}
+
+/// Formats a coordinate with at most four decimals and no trailing zeros.
+pub(crate) fn format_number(value: f64) -> String {
+    let formatted = format!("{:.4}", value);
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
@@ -0,0 +1,58 @@
+use super::is_dark_with_border;
+use crate::matrix::{ModuleKind, QRMatrix};
+
+/// Renders the symbol with Unicode half blocks, two module rows per line.
+///
+/// Light modules are drawn as blocks so the preview scans on the usual
+/// light-on-dark terminal.
+pub fn to_terminal(matrix: &QRMatrix, border: u32) -> String {
+    let dimension = matrix.get_size() + border as usize * 2;
+    let mut output = String::new();
+
+    for y in (0..dimension).step_by(2) {
+        for x in 0..dimension {
+            let top = !is_dark_with_border(matrix, border, x, y);
+            let bottom = y + 1 < dimension && !is_dark_with_border(matrix, border, x, y + 1);
+            output.push(match (top, bottom) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            });
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Text view of the symbol with every module labelled by its role.
+///
+/// Function modules use a letter per pattern, upper case when dark and lower
+/// case when light; data modules are `#` (dark) or `.` (light).
+pub fn to_annotated(matrix: &QRMatrix) -> String {
+    let size = matrix.get_size();
+    let mut output = String::new();
+
+    for y in 0..size {
+        for x in 0..size {
+            let label = match matrix.get_kind(x, y) {
+                ModuleKind::FinderPattern => 'F',
+                ModuleKind::Separator => 'S',
+                ModuleKind::TimingPattern => 'T',
+                ModuleKind::AlignmentPattern => 'A',
+                ModuleKind::DarkModule => 'K',
+                ModuleKind::FormatInfo => 'I',
+                ModuleKind::VersionInfo => 'V',
+                ModuleKind::Data => '#',
+            };
+            let dark = matrix.is_dark(x, y);
+            output.push(match (label, dark) {
+                ('#', false) => '.',
+                (_, true) => label,
+                (_, false) => label.to_ascii_lowercase(),
+            });
+        }
+        output.push('\n');
+    }
+    output
+}
@@ -0,0 +1,469 @@
+use crate::capacity::{eci_bits, SegmentMode};
+use crate::error::EncodeError;
+use crate::input::ALPHANUMERIC_CHARS;
+#[cfg(feature = "alloc")]
+use crate::{
+    capacity::data_capacity_bits, encoder::ErrorCorrectionLevel, error::QRError,
+    error_correction::add_error_correction, matrix::QRMatrix,
+};
+#[cfg(feature = "alloc")]
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// Mode indicator of an ECI header.
+const ECI_INDICATOR: u32 = 0b0111;
+
+/// Pad codewords appended in turn after the terminator.
+const PAD_CODEWORDS: [u32; 2] = [0xEC, 0x11];
+
+/// Somewhere to write bits, most significant first.
+pub(crate) trait BitWriter {
+    /// Appends the low `count` bits of `value`.
+    fn push(&mut self, value: u32, count: usize);
+
+    fn bit_len(&self) -> usize;
+}
+
+/// Bits packed into a growing byte vector.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct BitBuffer {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl BitBuffer {
+    pub(crate) fn new() -> Self {
+        BitBuffer::default()
+    }
+
+    pub(crate) fn bit(&self, index: usize) -> bool {
+        (self.bytes[index / 8] >> (7 - index % 8)) & 1 == 1
+    }
+
+    pub(crate) fn append(&mut self, other: &BitBuffer) {
+        for i in 0..other.len {
+            self.push(other.bit(i) as u32, 1);
+        }
+    }
+
+    /// The bits as a string of `0` and `1`.
+    pub(crate) fn to_bit_string(&self) -> String {
+        (0..self.len)
+            .map(|i| if self.bit(i) { '1' } else { '0' })
+            .collect()
+    }
+
+    /// The bytes so far, the last one padded with zero bits.
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl BitWriter for BitBuffer {
+    fn push(&mut self, value: u32, count: usize) {
+        for i in (0..count).rev() {
+            if self.len.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if (value >> i) & 1 == 1 {
+                self.bytes[self.len / 8] |= 0x80 >> (self.len % 8);
+            }
+            self.len += 1;
+        }
+    }
+
+    fn bit_len(&self) -> usize {
+        self.len
+    }
+}
+
+/// Writes the terminator: up to four zero bits, fewer when the data
+/// capacity of `capacity` bits is nearly full.
+pub(crate) fn write_terminator(output: &mut impl BitWriter, capacity: usize) {
+    output.push(0, capacity.saturating_sub(output.bit_len()).min(4));
+}
+
+/// Writes the zero bits up to a codeword boundary and the pad codewords
+/// that fill `capacity` bits.
+pub(crate) fn write_pad_codewords(output: &mut impl BitWriter, capacity: usize) {
+    output.push(0, (8 - output.bit_len() % 8) % 8);
+    for pad in PAD_CODEWORDS.iter().cycle() {
+        if output.bit_len() >= capacity {
+            break;
+        }
+        output.push(*pad, 8);
+    }
+}
+
+/// One segment of content, borrowed from the caller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment<'a> {
+    /// ASCII digits.
+    Numeric(&'a str),
+    /// Digits, upper case letters and ` $%*+-./:`.
+    Alphanumeric(&'a str),
+    Bytes(&'a [u8]),
+    /// Shift JIS double-byte characters in the ranges 0x8140-0x9FFC and
+    /// 0xE040-0xEBBF, with trail bytes 0x40-0xFC other than 0x7F.
+    Kanji(&'a [u8]),
+    /// An ECI header switching the interpretation of the segments that
+    /// follow.
+    Eci(u32),
+}
+
+impl Segment<'_> {
+    /// Checks that every character can be encoded in the segment's mode.
+    pub fn check(&self) -> Result<(), EncodeError> {
+        match *self {
+            Segment::Numeric(digits) => {
+                check_not_empty(digits.len())?;
+                match digits.chars().find(|c| !c.is_ascii_digit()) {
+                    Some(c) => Err(EncodeError::InvalidCharacter(c, SegmentMode::Numeric)),
+                    None => Ok(()),
+                }
+            }
+            Segment::Alphanumeric(text) => {
+                check_not_empty(text.len())?;
+                match text.chars().find(|&c| !ALPHANUMERIC_CHARS.contains(c)) {
+                    Some(c) => Err(EncodeError::InvalidCharacter(c, SegmentMode::Alphanumeric)),
+                    None => Ok(()),
+                }
+            }
+            Segment::Bytes(bytes) => check_not_empty(bytes.len()),
+            Segment::Kanji(shift_jis) => {
+                check_not_empty(shift_jis.len())?;
+                if !shift_jis.len().is_multiple_of(2) {
+                    return Err(EncodeError::PartialKanji);
+                }
+                for pair in shift_jis.chunks(2) {
+                    let code = u16::from_be_bytes([pair[0], pair[1]]);
+                    if kanji_offset(code).is_none() {
+                        return Err(EncodeError::InvalidKanji(code));
+                    }
+                }
+                Ok(())
+            }
+            Segment::Eci(assignment) => eci_bits(assignment).map(|_| ()),
+        }
+    }
+
+    /// The mode and character count, or `None` for an ECI header.
+    pub(crate) fn mode_and_count(&self) -> Option<(SegmentMode, usize)> {
+        match *self {
+            Segment::Numeric(digits) => Some((SegmentMode::Numeric, digits.len())),
+            Segment::Alphanumeric(text) => Some((SegmentMode::Alphanumeric, text.len())),
+            Segment::Bytes(bytes) => Some((SegmentMode::Byte, bytes.len())),
+            Segment::Kanji(shift_jis) => Some((SegmentMode::Kanji, shift_jis.len() / 2)),
+            Segment::Eci(_) => None,
+        }
+    }
+
+    /// Bits of the whole segment at this version, or `None` when it is
+    /// longer than its count indicator allows.
+    pub fn bit_len(&self, version: u8) -> Option<usize> {
+        match self.mode_and_count() {
+            Some((mode, count)) => counted_bit_len(mode, count, version),
+            None => {
+                let mut bits = BitCounter(0);
+                self.write_data(&mut bits);
+                Some(bits.0)
+            }
+        }
+    }
+
+    /// Writes the mode indicator, character count and data. The segment
+    /// must have passed [`Segment::check`].
+    pub(crate) fn write(&self, version: u8, output: &mut impl BitWriter) {
+        if let Some((mode, count)) = self.mode_and_count() {
+            output.push(mode.indicator(), 4);
+            output.push(count as u32, mode.count_bits(version));
+        }
+        self.write_data(output);
+    }
+
+    /// Writes the data bits alone, or the whole of an ECI header.
+    pub(crate) fn write_data(&self, output: &mut impl BitWriter) {
+        match *self {
+            Segment::Numeric(digits) => {
+                for group in digits.as_bytes().chunks(3) {
+                    let value = group
+                        .iter()
+                        .fold(0u32, |acc, &d| acc * 10 + (d - b'0') as u32);
+                    output.push(value, [0, 4, 7, 10][group.len()]);
+                }
+            }
+            Segment::Alphanumeric(text) => {
+                let value =
+                    |c: u8| ALPHANUMERIC_CHARS.bytes().position(|a| a == c).unwrap_or(0) as u32;
+                for pair in text.as_bytes().chunks(2) {
+                    match *pair {
+                        [first, second] => output.push(value(first) * 45 + value(second), 11),
+                        [single] => output.push(value(single), 6),
+                        _ => {}
+                    }
+                }
+            }
+            Segment::Bytes(bytes) => {
+                for &byte in bytes {
+                    output.push(byte as u32, 8);
+                }
+            }
+            Segment::Kanji(shift_jis) => {
+                for pair in shift_jis.chunks(2) {
+                    let offset = kanji_offset(u16::from_be_bytes([pair[0], pair[1]])).unwrap_or(0);
+                    output.push(((offset >> 8) * 0xC0 + (offset & 0xFF)) as u32, 13);
+                }
+            }
+            Segment::Eci(assignment) => {
+                output.push(ECI_INDICATOR, 4);
+                match assignment {
+                    0..=127 => output.push(assignment, 8),
+                    128..=16383 => output.push(0b10 << 14 | assignment, 16),
+                    _ => output.push(0b110 << 21 | assignment, 24),
+                }
+            }
+        }
+    }
+}
+
+/// Counts bits without storing them.
+struct BitCounter(usize);
+
+impl BitWriter for BitCounter {
+    fn push(&mut self, _value: u32, count: usize) {
+        self.0 += count;
+    }
+
+    fn bit_len(&self) -> usize {
+        self.0
+    }
+}
+
+/// Bits of a segment of `count` characters in `mode` at this version, or
+/// `None` when its count indicator cannot announce that many.
+fn counted_bit_len(mode: SegmentMode, count: usize, version: u8) -> Option<usize> {
+    (count <= mode.max_count(version)).then(|| mode.segment_bits(count, version))
+}
+
+/// Distance of a Shift JIS code from the start of its Kanji mode range.
+/// The trail byte must be 0x40-0xFC other than 0x7F; others are not Shift
+/// JIS and would collide with valid codes once packed into 13 bits.
+fn kanji_offset(code: u16) -> Option<u16> {
+    let trail = code & 0xFF;
+    if !(0x40..=0xFC).contains(&trail) || trail == 0x7F {
+        return None;
+    }
+    match code {
+        0x8140..=0x9FFC => Some(code - 0x8140),
+        0xE040..=0xEBBF => Some(code - 0xC140),
+        _ => None,
+    }
+}
+
+/// A pushed segment converted to bits: the data bits, or the whole header
+/// of an ECI segment.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Encoded {
+    mode_and_count: Option<(SegmentMode, usize)>,
+    bits: BitBuffer,
+}
+
+#[cfg(feature = "alloc")]
+impl Encoded {
+    fn bit_len(&self, version: u8) -> Option<usize> {
+        match self.mode_and_count {
+            Some((mode, count)) => counted_bit_len(mode, count, version),
+            None => Some(self.bits.bit_len()),
+        }
+    }
+
+    fn write(&self, version: u8, output: &mut BitBuffer) {
+        if let Some((mode, count)) = self.mode_and_count {
+            output.push(mode.indicator(), 4);
+            output.push(count as u32, mode.count_bits(version));
+        }
+        output.append(&self.bits);
+    }
+}
+
+/// Content assembled one segment at a time, each in its own mode.
+///
+/// Every push checks its characters against the mode, and the running bit
+/// length and smallest version can be queried between pushes.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SegmentBuilder {
+    segments: Vec<Encoded>,
+}
+
+#[cfg(feature = "alloc")]
+impl SegmentBuilder {
+    pub fn new() -> Self {
+        SegmentBuilder::default()
+    }
+
+    /// Appends a segment of any kind.
+    pub fn push(&mut self, segment: Segment) -> Result<&mut Self, QRError> {
+        segment.check()?;
+        let mut bits = BitBuffer::new();
+        segment.write_data(&mut bits);
+        self.segments.push(Encoded {
+            mode_and_count: segment.mode_and_count(),
+            bits,
+        });
+        Ok(self)
+    }
+
+    /// Appends a numeric segment of ASCII digits.
+    pub fn push_numeric(&mut self, digits: &str) -> Result<&mut Self, QRError> {
+        self.push(Segment::Numeric(digits))
+    }
+
+    /// Appends an alphanumeric segment: digits, upper case letters and
+    /// ` $%*+-./:`.
+    pub fn push_alphanumeric(&mut self, text: &str) -> Result<&mut Self, QRError> {
+        self.push(Segment::Alphanumeric(text))
+    }
+
+    /// Appends a byte segment. Any bytes are valid.
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> Result<&mut Self, QRError> {
+        self.push(Segment::Bytes(bytes))
+    }
+
+    /// Appends a Kanji segment from Shift JIS encoded double-byte
+    /// characters in the ranges 0x8140-0x9FFC and 0xE040-0xEBBF.
+    pub fn push_kanji(&mut self, shift_jis: &[u8]) -> Result<&mut Self, QRError> {
+        self.push(Segment::Kanji(shift_jis))
+    }
+
+    /// Appends text as byte segments, moving runs of alphanumeric
+    /// characters long enough to pay for their own header into alphanumeric
+    /// segments. Text that is alphanumeric throughout becomes one segment.
+    pub fn push_text(&mut self, text: &str) -> Result<&mut Self, QRError> {
+        if text.chars().all(|c| ALPHANUMERIC_CHARS.contains(c)) {
+            return self.push_alphanumeric(text);
+        }
+
+        // A run saves 2.5 bits per character over byte mode. At either end
+        // it costs one 13-bit header; inside, it also splits the byte
+        // segment, costing another 12 (header sizes of versions 1-9).
+        let is_alphanumeric = |b: &u8| ALPHANUMERIC_CHARS.as_bytes().contains(b);
+        let bytes = text.as_bytes();
+        let mut start = 0;
+        let mut pending = 0;
+        while start < bytes.len() {
+            let run = bytes[start..]
+                .iter()
+                .take_while(|b| is_alphanumeric(b))
+                .count();
+            let at_edge = start == 0 || start + run == bytes.len();
+            if run >= if at_edge { 6 } else { 11 } {
+                if pending < start {
+                    self.push_bytes(&bytes[pending..start])?;
+                }
+                self.push_alphanumeric(&text[start..start + run])?;
+                pending = start + run;
+            }
+            start += run.max(1);
+        }
+        if pending < bytes.len() {
+            self.push_bytes(&bytes[pending..])?;
+        }
+        Ok(self)
+    }
+
+    /// Appends an ECI header switching the interpretation of the segments
+    /// that follow.
+    pub fn push_eci(&mut self, assignment: u32) -> Result<&mut Self, QRError> {
+        self.push(Segment::Eci(assignment))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Bits of all segments at this version, without terminator or padding.
+    /// `None` when a segment is longer than its count indicator allows.
+    pub fn bit_len(&self, version: u8) -> Option<usize> {
+        self.segments.iter().map(|s| s.bit_len(version)).sum()
+    }
+
+    /// Whether the segments fit this version and level.
+    pub fn fits(&self, version: u8, ec_level: ErrorCorrectionLevel) -> bool {
+        (1..=40).contains(&version)
+            && self
+                .bit_len(version)
+                .is_some_and(|bits| bits <= data_capacity_bits(version, ec_level))
+    }
+
+    /// The smallest version holding the segments at this level.
+    pub fn smallest_version(&self, ec_level: ErrorCorrectionLevel) -> Option<u8> {
+        (1..=40).find(|&version| self.fits(version, ec_level))
+    }
+
+    /// The segments followed by the terminator and pad codewords, filling
+    /// the data capacity of the version.
+    pub fn data_codewords(
+        &self,
+        version: u8,
+        ec_level: ErrorCorrectionLevel,
+    ) -> Result<Vec<u8>, QRError> {
+        if !self.fits(version, ec_level) {
+            return Err(QRError::InvalidLength(format!(
+                "Segments do not fit into version {} at error correction level {:?}",
+                version, ec_level
+            )));
+        }
+
+        let mut bits = BitBuffer::new();
+        for segment in &self.segments {
+            segment.write(version, &mut bits);
+        }
+        let capacity = data_capacity_bits(version, ec_level);
+        write_terminator(&mut bits, capacity);
+        write_pad_codewords(&mut bits, capacity);
+        Ok(bits.into_bytes())
+    }
+
+    /// Encodes the segments into a finished symbol. `version` defaults to
+    /// the smallest that fits and `mask` to the lowest penalty.
+    pub fn generate(
+        &self,
+        ec_level: ErrorCorrectionLevel,
+        version: Option<u8>,
+        mask: Option<u8>,
+    ) -> Result<QRMatrix, QRError> {
+        if self.is_empty() {
+            return Err(QRError::InvalidInput(
+                "Input text cannot be empty".to_string(),
+            ));
+        }
+        let version = match version.or_else(|| self.smallest_version(ec_level)) {
+            Some(version) => version,
+            None => {
+                return Err(QRError::InvalidLength(format!(
+                    "Segments do not fit any version at error correction level {:?}",
+                    ec_level
+                )))
+            }
+        };
+
+        let data = self.data_codewords(version, ec_level)?;
+        let codewords = add_error_correction(&data, version, ec_level)?;
+        QRMatrix::build(version, ec_level, &codewords, mask)
+    }
+}
+
+fn check_not_empty(len: usize) -> Result<(), EncodeError> {
+    if len == 0 {
+        return Err(EncodeError::EmptySegment);
+    }
+    Ok(())
+}
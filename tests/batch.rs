@@ -0,0 +1,70 @@
+#![cfg(feature = "std")]
+
+use qrcodegenerator::batch::{generate_batch, parse_records, BatchFormat, BatchOptions};
+
+#[test]
+fn duplicate_file_names_are_failures() {
+    let input = "content,filename\nfirst,tag.svg\nsecond,tag.svg\nthird,other\n";
+    let (records, failures) = parse_records(input, BatchFormat::Csv);
+    assert_eq!(records.len(), 2);
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].line, 3);
+    assert!(
+        failures[0].error.contains("line 2"),
+        "{}",
+        failures[0].error
+    );
+}
+
+#[test]
+fn names_colliding_after_the_default_extension_are_not_written_twice() {
+    let input = "content,filename\nfirst,tag\nsecond,tag.svg\n";
+    let (records, failures) = parse_records(input, BatchFormat::Csv);
+    assert!(failures.is_empty());
+
+    let dir = std::env::temp_dir().join(format!("qrgen-batch-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let report = generate_batch(&records, &dir, &BatchOptions::default());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(report.written, [dir.join("tag.svg")]);
+    assert_eq!(report.failures.len(), 1);
+    assert_eq!(report.failures[0].line, 3);
+}
+
+#[test]
+fn json_lines_follow_the_json_grammar() {
+    let input = concat!(
+        r#"{"content": "a", "filename": "a", "version": 2}"#,
+        "\n",
+        r#"{"content": "b", "filename": "b", "version": -1.5e+3}"#,
+        "\n",
+        r#"{"content": "é😀", "filename": "c"}"#,
+        "\n",
+    );
+    let (records, failures) = parse_records(input, BatchFormat::JsonLines);
+    assert_eq!(records.len(), 2, "{:?}", failures);
+    assert_eq!(records[1].content, "é😀");
+    // The number is well formed but not a version.
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].line, 2);
+
+    for bad in [
+        r#"{"content": "x", "filename": "x", "version": NaN}"#,
+        r#"{"content": "x", "filename": "x", "version": inf}"#,
+        r#"{"content": "x", "filename": "x", "version": 01}"#,
+        r#"{"content": "x", "filename": "x", "version": 1.}"#,
+        r#"{"content": "x", "filename": "x", "version": +1}"#,
+        r#"{"content": "\u+123", "filename": "x"}"#,
+        r#"{"content": "\u12g4", "filename": "x"}"#,
+    ] {
+        let (records, failures) = parse_records(bad, BatchFormat::JsonLines);
+        assert!(records.is_empty(), "{}", bad);
+        assert!(
+            failures[0].error.contains("Invalid JSON"),
+            "{}: {}",
+            bad,
+            failures[0].error
+        );
+    }
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "alloc")]
+
+use qrcodegenerator::capacity::{
+    data_capacity_bits, fits, max_length, smallest_version, ContentPlan, SegmentMode, LEVELS,
+};
+use qrcodegenerator::{ErrorCorrectionLevel, VERSION_CAPACITIES};
+
+/// The published table: one `version level data numeric alphanumeric byte
+/// kanji` row per version and level.
+const PUBLISHED: &str = include_str!("fixtures/capacities.txt");
+
+#[test]
+fn derived_capacities_match_the_published_table() {
+    let plan = ContentPlan::new();
+    let mut rows = 0;
+    for line in PUBLISHED.lines().filter(|l| !l.starts_with('#')) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let version: u8 = fields[0].parse().unwrap();
+        let level: ErrorCorrectionLevel = fields[1].parse().unwrap();
+        let numbers: Vec<usize> = fields[2..].iter().map(|f| f.parse().unwrap()).collect();
+        let info = &VERSION_CAPACITIES[version as usize - 1];
+        let capacity = &info.capacity_by_ec[level as usize];
+
+        assert_eq!(info.size, version as u32 * 4 + 17);
+        assert_eq!(
+            data_capacity_bits(version, level),
+            numbers[0] * 8,
+            "{}-{:?}",
+            version,
+            level
+        );
+        let derived = [
+            capacity.numeric,
+            capacity.alphanumeric,
+            capacity.byte,
+            capacity.kanji,
+        ];
+        assert_eq!(derived[..], numbers[1..], "{}-{:?}", version, level);
+        for (mode, &expected) in SegmentMode::ALL.iter().zip(&numbers[1..]) {
+            assert_eq!(max_length(version, level, *mode, &plan).unwrap(), expected);
+        }
+        rows += 1;
+    }
+    assert_eq!(rows, 40 * LEVELS.len());
+}
+
+#[test]
+fn fits_reports_remaining_bits() {
+    // 11 alphanumeric characters: 4 + 9 + 5 * 11 + 6 = 74 bits.
+    let plan = ContentPlan::for_content("HELLO WORLD").unwrap();
+    let all = fits(&plan).unwrap();
+    let first = all[0];
+    assert_eq!(
+        (first.version, first.ec_level),
+        (1, ErrorCorrectionLevel::L)
+    );
+    assert_eq!(first.used_bits, 74);
+    assert_eq!(first.remaining_bits(), 152 - 74);
+    // Version 1-H holds 72 bits, so H starts at version 2.
+    assert_eq!(
+        smallest_version(&plan, ErrorCorrectionLevel::H).unwrap(),
+        Some(2)
+    );
+    assert_eq!(all.len(), 40 * 4 - 1);
+}
+
+#[test]
+fn eci_and_extra_segments_reduce_capacity() {
+    let level = ErrorCorrectionLevel::M;
+    let plain = max_length(5, level, SegmentMode::Byte, &ContentPlan::new()).unwrap();
+    assert_eq!(plain, 84);
+    let with_eci = ContentPlan::new().eci(26);
+    let with_segment = ContentPlan::new().segment(SegmentMode::Numeric, 6);
+
+    // 688 data bits less the 12-bit byte header hold 84 bytes with 4 bits to
+    // spare, so the 12-bit ECI header costs one byte.
+    assert_eq!(
+        max_length(5, level, SegmentMode::Byte, &with_eci).unwrap(),
+        plain - 1
+    );
+    // The numeric segment takes 4 + 10 + 20 = 34 bits, leaving 642 for bytes.
+    assert_eq!(
+        max_length(5, level, SegmentMode::Byte, &with_segment).unwrap(),
+        80
+    );
+    assert!(ContentPlan::new().eci(1_000_000).bits(1).is_err());
+}
@@ -0,0 +1,106 @@
+#![cfg(feature = "std")]
+
+use qrcodegenerator::damage::{robustness, simulate_damage, Corner, Damage};
+use qrcodegenerator::{ErrorCorrectionLevel, QRData, QRMatrix};
+
+fn symbol(level: ErrorCorrectionLevel) -> QRMatrix {
+    let mut qr_data = QRData::new();
+    qr_data
+        .set_content("https://example.com/product/12345")
+        .unwrap();
+    qr_data.set_ec_level(level).unwrap();
+    qr_data.generate(None).unwrap()
+}
+
+#[test]
+fn undamaged_symbol_keeps_its_whole_budget() {
+    let matrix = symbol(ErrorCorrectionLevel::M);
+    let report = simulate_damage(&matrix, &[], 0).unwrap();
+    assert!(report.success);
+    assert_eq!(report.changed_modules, 0);
+    assert!(report
+        .remaining_budget
+        .iter()
+        .all(|&b| b == report.ecc_per_block));
+    assert_eq!(report.margin(), 1.0);
+}
+
+#[test]
+fn same_seed_gives_the_same_report() {
+    let matrix = symbol(ErrorCorrectionLevel::Q);
+    let damage = [Damage::Flips { fraction: 0.02 }];
+    let a = simulate_damage(&matrix, &damage, 9).unwrap();
+    let b = simulate_damage(&matrix, &damage, 9).unwrap();
+    assert_eq!(a.matrix, b.matrix);
+    assert_eq!(a.remaining_budget, b.remaining_budget);
+}
+
+#[test]
+fn small_blot_spends_part_of_the_budget() {
+    let matrix = symbol(ErrorCorrectionLevel::H);
+    let centre = matrix.get_size() as f64 / 2.0;
+    let report = simulate_damage(
+        &matrix,
+        &[Damage::Blot {
+            x: centre,
+            y: centre,
+            radius: 2.0,
+        }],
+        0,
+    )
+    .unwrap();
+    assert!(report.success, "{:?}", report.error);
+    assert!(report.changed_modules > 0);
+    assert!(report.margin() > 0.0 && report.margin() < 1.0);
+}
+
+#[test]
+fn heavy_damage_is_reported_as_failure() {
+    let matrix = symbol(ErrorCorrectionLevel::L);
+    let size = matrix.get_size();
+    let report = simulate_damage(
+        &matrix,
+        &[
+            Damage::Stripe {
+                vertical: true,
+                offset: 9,
+                width: size / 2,
+            },
+            Damage::MissingCorner {
+                corner: Corner::BottomRight,
+                size: size / 2,
+            },
+        ],
+        0,
+    )
+    .unwrap();
+    assert!(!report.success);
+    assert!(report.error.is_some());
+    assert!(report.remaining_budget.is_empty());
+    assert_eq!(report.margin(), 0.0);
+}
+
+#[test]
+fn stripes_past_the_edge_are_clipped() {
+    let matrix = symbol(ErrorCorrectionLevel::M);
+    let size = matrix.get_size();
+    let stripe = |offset, width| {
+        let damage = [Damage::Stripe {
+            vertical: false,
+            offset,
+            width,
+        }];
+        simulate_damage(&matrix, &damage, 0).unwrap().matrix
+    };
+
+    assert_eq!(stripe(size - 2, usize::MAX), stripe(size - 2, 2));
+    assert_eq!(stripe(usize::MAX, usize::MAX), matrix);
+}
+
+#[test]
+fn higher_levels_score_at_least_as_well() {
+    let low = robustness(&symbol(ErrorCorrectionLevel::L), 1).unwrap();
+    let high = robustness(&symbol(ErrorCorrectionLevel::H), 1).unwrap();
+    assert!(high.score >= low.score, "{} < {}", high.score, low.score);
+    assert_eq!(low.kinds.len(), 4);
+}
@@ -0,0 +1,44 @@
+#![cfg(feature = "std")]
+
+use qrcodegenerator::detector::decode_image;
+use qrcodegenerator::image::read_png;
+use qrcodegenerator::render::{to_png, RenderOptions};
+use qrcodegenerator::{ErrorCorrectionLevel, QRData};
+
+#[test]
+fn decodes_rendered_pngs() {
+    let cases = [
+        ("HELLO WORLD", ErrorCorrectionLevel::Q, 1, 3),
+        (
+            "https://example.com/detector",
+            ErrorCorrectionLevel::M,
+            5,
+            4,
+        ),
+        (
+            "Grüße aus dem Detektor, 0123456789",
+            ErrorCorrectionLevel::H,
+            10,
+            2,
+        ),
+    ];
+    for (content, level, version, scale) in cases {
+        let mut qr_data = QRData::new();
+        qr_data.set_content(content).unwrap();
+        qr_data.set_ec_level(level).unwrap();
+        qr_data.set_version(Some(version)).unwrap();
+        let matrix = qr_data.generate(None).unwrap();
+
+        let options = RenderOptions {
+            scale,
+            ..RenderOptions::default()
+        };
+        let image = read_png(&to_png(&matrix, &options)).unwrap();
+        let decoded = decode_image(&image).unwrap();
+        assert_eq!(decoded.content, content.as_bytes());
+        assert_eq!(
+            (decoded.version, decoded.ec_level, decoded.mask),
+            (version, level, matrix.get_mask().unwrap())
+        );
+    }
+}
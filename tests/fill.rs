@@ -0,0 +1,161 @@
+#![cfg(feature = "std")]
+
+use qrcodegenerator::render::{to_svg, Color, Fill, FinderShape, RenderOptions, Style};
+use qrcodegenerator::QRData;
+
+fn color(hex: &str) -> Color {
+    hex.parse().unwrap()
+}
+
+fn hello_world_svg(options: &RenderOptions) -> String {
+    let mut qr_data = QRData::new();
+    qr_data.set_content("HELLO WORLD").unwrap();
+    to_svg(&qr_data.generate(None).unwrap(), options)
+}
+
+#[test]
+fn fills_parse_from_colour_stops() {
+    assert_eq!("solid".parse::<Fill>().unwrap(), Fill::Solid);
+    assert_eq!(
+        "linear:#000:#00f:45".parse::<Fill>().unwrap(),
+        Fill::LinearGradient {
+            start: Color::BLACK,
+            end: color("#0000ff"),
+            angle: 45.0
+        }
+    );
+    assert_eq!(
+        "radial:black:123456".parse::<Fill>().unwrap(),
+        Fill::RadialGradient {
+            centre: Color::BLACK,
+            edge: color("#123456")
+        }
+    );
+    assert!("linear:#000".parse::<Fill>().is_err());
+    assert!("linear:#000:#00f:steep".parse::<Fill>().is_err());
+    assert!("radial:#000:#nothex".parse::<Fill>().is_err());
+    assert!("conic:#000:#00f".parse::<Fill>().is_err());
+}
+
+#[test]
+fn low_contrast_is_rejected() {
+    let options = |dark: &str| RenderOptions {
+        dark: color(dark),
+        ..RenderOptions::default()
+    };
+    assert!(RenderOptions::default().check_contrast().is_ok());
+    // 4.48:1 passes, 2.32:1 does not.
+    assert!(options("#777777").check_contrast().is_ok());
+    assert_eq!(
+        options("#aaaaaa").check_contrast().unwrap_err().to_string(),
+        "Invalid input: Contrast between #aaaaaa and #ffffff is 2.32:1, at least 3:1 is needed"
+    );
+
+    // Every gradient stop and the finder colour are checked too.
+    let gradient = RenderOptions {
+        fill: Fill::LinearGradient {
+            start: Color::BLACK,
+            end: color("#dddddd"),
+            angle: 0.0,
+        },
+        ..RenderOptions::default()
+    };
+    assert!(gradient.check_contrast().is_err());
+    let finders = RenderOptions {
+        finder_color: Some(color("#ffff00")),
+        ..RenderOptions::default()
+    };
+    assert!(finders.check_contrast().is_err());
+}
+
+#[test]
+fn inverted_colours_need_opting_in() {
+    let inverted = RenderOptions {
+        dark: Color::WHITE,
+        light: Color::BLACK,
+        ..RenderOptions::default()
+    };
+    assert_eq!(
+        inverted.check_contrast().unwrap_err().to_string(),
+        "Invalid input: Dark colour #ffffff is lighter than light colour #000000; allow inverted symbols to use it"
+    );
+    let allowed = RenderOptions {
+        allow_inverted: true,
+        ..inverted
+    };
+    assert!(allowed.check_contrast().is_ok());
+
+    // Opting in does not lift the contrast minimum.
+    let faint = RenderOptions {
+        light: color("#333333"),
+        dark: color("#444444"),
+        ..allowed
+    };
+    assert!(faint.check_contrast().is_err());
+}
+
+#[test]
+fn svg_gradients_span_the_symbol_without_the_quiet_zone() {
+    let linear = RenderOptions {
+        fill: Fill::LinearGradient {
+            start: Color::BLACK,
+            end: color("#000080"),
+            angle: 0.0,
+        },
+        ..RenderOptions::default()
+    };
+    let svg = hello_world_svg(&linear);
+    // 21 modules after a quiet zone of 4: from x = 4 to x = 25.
+    assert!(svg.contains(concat!(
+        "<defs>\n",
+        "<linearGradient id=\"qr-fill\" gradientUnits=\"userSpaceOnUse\" x1=\"4\" y1=\"14.5\" x2=\"25\" y2=\"14.5\">\n",
+        "<stop offset=\"0\" stop-color=\"#000000\"/>\n",
+        "<stop offset=\"1\" stop-color=\"#000080\"/>\n",
+        "</linearGradient>\n",
+        "</defs>\n"
+    )));
+    assert!(svg.contains("\" fill=\"url(#qr-fill)\"/>\n"));
+
+    let radial = RenderOptions {
+        fill: Fill::RadialGradient {
+            centre: color("#000080"),
+            edge: Color::BLACK,
+        },
+        ..RenderOptions::default()
+    };
+    let svg = hello_world_svg(&radial);
+    assert!(svg.contains(
+        "<radialGradient id=\"qr-fill\" gradientUnits=\"userSpaceOnUse\" cx=\"14.5\" cy=\"14.5\" r=\"14.8492\">\n"
+    ));
+    assert!(svg.contains("<stop offset=\"0\" stop-color=\"#000080\"/>\n"));
+
+    assert!(!hello_world_svg(&RenderOptions::default()).contains("<defs>"));
+}
+
+#[test]
+fn finder_colours_get_their_own_path() {
+    let options = RenderOptions {
+        fill: Fill::RadialGradient {
+            centre: Color::BLACK,
+            edge: color("#000080"),
+        },
+        finder_color: Some(color("#cc0000")),
+        ..RenderOptions::default()
+    };
+    let svg = hello_world_svg(&options);
+    assert_eq!(svg.matches("<path ").count(), 2);
+    assert!(svg.contains("\" fill=\"url(#qr-fill)\"/>\n"));
+    assert!(svg.contains("\" fill=\"#cc0000\" fill-rule=\"evenodd\"/>\n"));
+
+    // Without a colour of their own, shaped finders share the gradient.
+    let options = RenderOptions {
+        finder_color: None,
+        style: Style {
+            finder_eye: FinderShape::Circle,
+            ..Default::default()
+        },
+        ..options
+    };
+    let svg = hello_world_svg(&options);
+    assert!(svg.contains("\" fill=\"url(#qr-fill)\" fill-rule=\"evenodd\"/>\n"));
+}
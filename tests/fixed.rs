@@ -0,0 +1,227 @@
+#![cfg(feature = "alloc")]
+
+use qrcodegenerator::capacity::SegmentMode;
+use qrcodegenerator::error::{EncodeError, QRError};
+use qrcodegenerator::fixed::{buffer_len, encode_segments, encode_text, FixedSymbol, QrBuffer};
+use qrcodegenerator::segment::{Segment, SegmentBuilder};
+use qrcodegenerator::{ErrorCorrectionLevel, QRData, QRMatrix};
+
+fn assert_same(symbol: &FixedSymbol, matrix: &QRMatrix) {
+    assert_eq!(symbol.version(), matrix.get_version());
+    assert_eq!(Some(symbol.mask()), matrix.get_mask());
+    for y in 0..matrix.get_size() {
+        for x in 0..matrix.get_size() {
+            assert_eq!(
+                symbol.is_dark(x, y),
+                matrix.is_dark(x, y),
+                "version {} module ({}, {})",
+                matrix.get_version(),
+                x,
+                y
+            );
+        }
+    }
+}
+
+#[test]
+fn matches_the_allocating_encoder_at_every_version() {
+    let mut modules = [0u8; buffer_len(40)];
+    let mut scratch = [0u8; buffer_len(40)];
+    let levels = [
+        ErrorCorrectionLevel::L,
+        ErrorCorrectionLevel::M,
+        ErrorCorrectionLevel::Q,
+        ErrorCorrectionLevel::H,
+    ];
+
+    for version in 1..=40u8 {
+        let level = levels[version as usize % 4];
+        let content = ["31415926535", "HELLO WORLD", "Grüße, world!"][version as usize % 3];
+        let mut qr_data = QRData::new();
+        qr_data.set_content(content).unwrap();
+        qr_data.set_ec_level(level).unwrap();
+        qr_data.set_version(Some(version)).unwrap();
+        let matrix = qr_data.generate(None).unwrap();
+
+        let symbol = encode_text(
+            content,
+            level,
+            Some(version),
+            None,
+            &mut modules,
+            &mut scratch,
+        )
+        .unwrap();
+        assert_same(&symbol, &matrix);
+    }
+}
+
+#[test]
+fn mixed_segments_match_the_segment_builder() {
+    let segments = [
+        Segment::Eci(26),
+        Segment::Alphanumeric("SN:"),
+        Segment::Numeric("0012345678"),
+        Segment::Kanji(&[0x93, 0x5F, 0xE4, 0xAA]),
+        Segment::Bytes(b"/ok"),
+    ];
+    let mut builder = SegmentBuilder::new();
+    for segment in segments {
+        builder.push(segment).unwrap();
+    }
+    let matrix = builder
+        .generate(ErrorCorrectionLevel::Q, None, Some(3))
+        .unwrap();
+
+    let mut modules = [0u8; buffer_len(5)];
+    let mut scratch = [0u8; buffer_len(5)];
+    let symbol = encode_segments(
+        &segments,
+        ErrorCorrectionLevel::Q,
+        None,
+        Some(3),
+        &mut modules,
+        &mut scratch,
+    )
+    .unwrap();
+    assert_same(&symbol, &matrix);
+}
+
+#[test]
+fn buffers_limit_the_version() {
+    let mut modules = [0u8; buffer_len(2)];
+    let mut scratch = [0u8; buffer_len(2)];
+    // 20 bytes need version 2 at level L and version 3 at level H.
+    let text = "twenty bytes of text";
+    let symbol = encode_text(
+        text,
+        ErrorCorrectionLevel::L,
+        None,
+        None,
+        &mut modules,
+        &mut scratch,
+    )
+    .unwrap();
+    assert_eq!(symbol.version(), 2);
+    assert_eq!(symbol.modules().len(), buffer_len(2));
+
+    assert_eq!(
+        encode_text(
+            text,
+            ErrorCorrectionLevel::H,
+            None,
+            None,
+            &mut modules,
+            &mut scratch
+        )
+        .unwrap_err(),
+        EncodeError::TooLongUpTo(2, ErrorCorrectionLevel::H)
+    );
+    assert_eq!(
+        encode_text(
+            "1",
+            ErrorCorrectionLevel::L,
+            Some(3),
+            None,
+            &mut modules,
+            &mut scratch
+        )
+        .unwrap_err(),
+        EncodeError::BufferTooShort {
+            version: 3,
+            needed: buffer_len(3),
+            got: buffer_len(2)
+        }
+    );
+}
+
+#[test]
+fn errors_are_values() {
+    let mut modules = [0u8; buffer_len(1)];
+    let mut scratch = [0u8; buffer_len(1)];
+    let mut encode = |segments: &[Segment], mask| {
+        encode_segments(
+            segments,
+            ErrorCorrectionLevel::M,
+            None,
+            mask,
+            &mut modules,
+            &mut scratch,
+        )
+        .map(|symbol| symbol.version())
+    };
+    assert_eq!(encode(&[], None), Err(EncodeError::Empty));
+    assert_eq!(
+        encode(&[Segment::Numeric("")], None),
+        Err(EncodeError::EmptySegment)
+    );
+    assert_eq!(
+        encode(&[Segment::Alphanumeric("abc")], None),
+        Err(EncodeError::InvalidCharacter(
+            'a',
+            SegmentMode::Alphanumeric
+        ))
+    );
+    assert_eq!(
+        encode(&[Segment::Kanji(&[0x93])], None),
+        Err(EncodeError::PartialKanji)
+    );
+    assert_eq!(
+        encode(&[Segment::Eci(1_000_000)], None),
+        Err(EncodeError::InvalidEci(1_000_000))
+    );
+    assert_eq!(
+        encode(&[Segment::Numeric("1")], Some(8)),
+        Err(EncodeError::InvalidMask(8))
+    );
+    assert_eq!(encode(&[Segment::Numeric("1")], Some(0)), Ok(1));
+
+    // The allocating API reports the same messages.
+    let error = QRError::from(EncodeError::InvalidMask(8));
+    assert_eq!(error.to_string(), "Invalid input: Mask 8 is outside 0-7");
+}
+
+#[test]
+fn qr_buffer_is_sized_by_its_maximum_version() {
+    assert_eq!(QrBuffer::<2>::LEN, buffer_len(2));
+    let mut modules = [0u8; QrBuffer::<2>::LEN];
+    let mut scratch = [0u8; QrBuffer::<2>::LEN];
+    let mut qr = QrBuffer::<2>::new(&mut modules, &mut scratch).ec_level(ErrorCorrectionLevel::L);
+    assert!(qr.symbol().is_none());
+
+    let text = "twenty bytes of text";
+    qr.encode_text(text).unwrap();
+    let mut qr_data = QRData::new();
+    qr_data.set_content(text).unwrap();
+    qr_data.set_ec_level(ErrorCorrectionLevel::L).unwrap();
+    assert_same(&qr.symbol().unwrap(), &qr_data.generate(None).unwrap());
+
+    // Larger arrays do not raise the limit past `MAX_VERSION`.
+    let mut modules = [0u8; buffer_len(40)];
+    let mut scratch = [0u8; buffer_len(40)];
+    let mut qr = QrBuffer::<2>::new(&mut modules, &mut scratch)
+        .ec_level(ErrorCorrectionLevel::H)
+        .mask(5);
+    assert!(qr.encode_text(text).is_err());
+    assert_eq!(qr.get_version(), 0);
+    qr.encode_segments(&[Segment::Numeric("0123")]).unwrap();
+    assert_eq!((qr.get_version(), qr.get_mask()), (1, Some(5)));
+}
+
+#[test]
+fn qr_buffer_reports_the_level_it_encoded_with() {
+    let mut modules = [0u8; QrBuffer::<3>::LEN];
+    let mut scratch = [0u8; QrBuffer::<3>::LEN];
+    let mut qr = QrBuffer::<3>::new(&mut modules, &mut scratch).ec_level(ErrorCorrectionLevel::L);
+    qr.encode_text("HELLO WORLD").unwrap();
+
+    let qr = qr.ec_level(ErrorCorrectionLevel::H);
+    assert_eq!(qr.get_ec_level(), ErrorCorrectionLevel::L);
+    let symbol = qr.symbol().unwrap();
+    assert_eq!(symbol.ec_level(), ErrorCorrectionLevel::L);
+
+    let mut qr_data = QRData::new();
+    qr_data.set_content("HELLO WORLD").unwrap();
+    qr_data.set_ec_level(ErrorCorrectionLevel::L).unwrap();
+    assert_same(&symbol, &qr_data.generate(None).unwrap());
+}
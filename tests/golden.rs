@@ -0,0 +1,172 @@
+//! Conformance against worked examples stored as text fixtures in
+//! `tests/fixtures`. Each stage of the encoder is compared separately so a
+//! failure points at the stage that drifted.
+#![cfg(feature = "alloc")]
+
+use qrcodegenerator::decoder::decode_matrix;
+use qrcodegenerator::{ErrorCorrectionLevel, QRData, QRMatrix};
+use std::collections::HashMap;
+
+struct Fixture {
+    name: &'static str,
+    fields: HashMap<String, String>,
+    matrix: Vec<String>,
+}
+
+impl Fixture {
+    /// `key: value` lines, `#` comments and blank lines, then `matrix:`
+    /// followed by one row per line with `#` dark and `.` light.
+    fn parse(name: &'static str, text: &str) -> Self {
+        let mut fields = HashMap::new();
+        let mut lines = text.lines();
+        for line in lines.by_ref() {
+            let line = line.trim();
+            if line == "matrix:" {
+                break;
+            }
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once(':')
+                .unwrap_or_else(|| panic!("{}: malformed line '{}'", name, line));
+            fields.insert(key.to_string(), value.trim().to_string());
+        }
+        let matrix = lines
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect();
+        Fixture {
+            name,
+            fields,
+            matrix,
+        }
+    }
+
+    fn field(&self, key: &str) -> &str {
+        self.fields
+            .get(key)
+            .unwrap_or_else(|| panic!("{}: missing '{}'", self.name, key))
+    }
+
+    fn numbers<T: std::str::FromStr>(&self, key: &str) -> Vec<T> {
+        self.field(key)
+            .split_whitespace()
+            .map(|n| {
+                n.parse()
+                    .unwrap_or_else(|_| panic!("{}: bad number '{}' in {}", self.name, n, key))
+            })
+            .collect()
+    }
+
+    fn level(&self) -> ErrorCorrectionLevel {
+        self.field("level").parse().unwrap()
+    }
+
+    fn qr_data(&self) -> QRData {
+        let mut qr_data = QRData::new();
+        qr_data.set_content(self.field("content")).unwrap();
+        qr_data.set_ec_level(self.level()).unwrap();
+        assert_eq!(
+            qr_data.get_version(),
+            Some(self.field("version").parse().unwrap()),
+            "{}: version",
+            self.name
+        );
+        qr_data
+    }
+}
+
+fn fixtures() -> Vec<Fixture> {
+    vec![
+        Fixture::parse("01234567-1-M", include_str!("fixtures/01234567-1-M.txt")),
+        Fixture::parse(
+            "HELLO-WORLD-1-Q",
+            include_str!("fixtures/HELLO-WORLD-1-Q.txt"),
+        ),
+    ]
+}
+
+fn render(matrix: &QRMatrix) -> Vec<String> {
+    let size = matrix.get_size();
+    (0..size)
+        .map(|y| {
+            (0..size)
+                .map(|x| if matrix.is_dark(x, y) { '#' } else { '.' })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn data_bit_stream() {
+    for fixture in fixtures() {
+        let expected: String = fixture.field("bits").split_whitespace().collect();
+        assert_eq!(
+            fixture.qr_data().get_final_data().unwrap(),
+            expected,
+            "{}",
+            fixture.name
+        );
+    }
+}
+
+#[test]
+fn data_and_error_correction_codewords() {
+    for fixture in fixtures() {
+        let mut qr_data = fixture.qr_data();
+        let data = qr_data.get_data_codewords().unwrap();
+        assert_eq!(
+            data,
+            fixture.numbers::<u8>("data"),
+            "{}: data",
+            fixture.name
+        );
+
+        let codewords = qr_data.get_codewords().unwrap();
+        assert_eq!(
+            codewords[data.len()..],
+            fixture.numbers::<u8>("ec")[..],
+            "{}: EC codewords",
+            fixture.name
+        );
+    }
+}
+
+#[test]
+fn mask_penalties() {
+    for fixture in fixtures() {
+        let mut qr_data = fixture.qr_data();
+        let version = qr_data.get_version().unwrap();
+        let codewords = qr_data.get_codewords().unwrap();
+        let unmasked = QRMatrix::with_data(version, fixture.level(), &codewords).unwrap();
+        let penalties = fixture.numbers::<i32>("penalties");
+        assert_eq!(
+            unmasked.mask_penalties()[..],
+            penalties[..],
+            "{}: penalties",
+            fixture.name
+        );
+
+        // The lowest penalty wins, the first mask on a tie.
+        let lowest = (0..8u8).min_by_key(|&m| penalties[m as usize]);
+        let matrix = qr_data.generate(None).unwrap();
+        assert_eq!(matrix.get_mask(), lowest, "{}: selected mask", fixture.name);
+    }
+}
+
+#[test]
+fn final_matrix() {
+    for fixture in fixtures() {
+        let mask = fixture.field("mask").parse().unwrap();
+        let matrix = fixture.qr_data().generate(Some(mask)).unwrap();
+        let actual = render(&matrix);
+        for (y, (row, expected)) in actual.iter().zip(&fixture.matrix).enumerate() {
+            assert_eq!(row, expected, "{}: row {}", fixture.name, y);
+        }
+        assert_eq!(actual.len(), fixture.matrix.len(), "{}: rows", fixture.name);
+
+        let decoded = decode_matrix(&matrix).unwrap();
+        assert_eq!(decoded.text(), fixture.field("content"), "{}", fixture.name);
+    }
+}
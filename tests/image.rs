@@ -0,0 +1,225 @@
+#![cfg(feature = "std")]
+
+use qrcodegenerator::image::{read_netpbm, read_png};
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// A zlib stream of one stored block.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01, 0x01];
+    let length = data.len() as u16;
+    out.extend_from_slice(&length.to_le_bytes());
+    out.extend_from_slice(&(!length).to_le_bytes());
+    out.extend_from_slice(data);
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    out.extend_from_slice(&((b << 16) | a).to_be_bytes());
+    out
+}
+
+/// A PNG of the given IHDR fields and extra chunks around already filtered
+/// image data.
+fn png(
+    size: (u32, u32),
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+    chunks: &[(&[u8; 4], Vec<u8>)],
+    filtered: &[u8],
+) -> Vec<u8> {
+    let mut header = Vec::new();
+    header.extend_from_slice(&size.0.to_be_bytes());
+    header.extend_from_slice(&size.1.to_be_bytes());
+    header.extend_from_slice(&[bit_depth, color_type, 0, 0, interlaced as u8]);
+
+    let mut all: Vec<(&[u8; 4], Vec<u8>)> = vec![(b"IHDR", header)];
+    all.extend(chunks.iter().cloned());
+    all.push((b"IDAT", zlib_stored(filtered)));
+    all.push((b"IEND", Vec::new()));
+
+    let mut out = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+    for (kind, data) in all {
+        out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        let start = out.len();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(&data);
+        let crc = crc32(&out[start..]);
+        out.extend_from_slice(&crc.to_be_bytes());
+    }
+    out
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Filters one-byte-per-pixel rows, each with the given filter type.
+fn filter_rows(rows: &[Vec<u8>], filters: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut previous = vec![0u8; rows[0].len()];
+    for (row, &filter) in rows.iter().zip(filters) {
+        out.push(filter);
+        for i in 0..row.len() {
+            let left = if i > 0 { row[i - 1] } else { 0 };
+            let up_left = if i > 0 { previous[i - 1] } else { 0 };
+            let predictor = match filter {
+                0 => 0,
+                1 => left,
+                2 => previous[i],
+                3 => ((left as u16 + previous[i] as u16) / 2) as u8,
+                _ => paeth(left, previous[i], up_left),
+            };
+            out.push(row[i].wrapping_sub(predictor));
+        }
+        previous = row.clone();
+    }
+    out
+}
+
+fn pixels(width: usize, height: usize) -> Vec<Vec<u8>> {
+    (0..height)
+        .map(|y| {
+            (0..width)
+                .map(|x| ((x * 37 + y * 91) % 256) as u8)
+                .collect()
+        })
+        .collect()
+}
+
+fn luma(image: &qrcodegenerator::detector::GrayImage) -> Vec<Vec<u8>> {
+    (0..image.height())
+        .map(|y| (0..image.width()).map(|x| image.get(x, y)).collect())
+        .collect()
+}
+
+#[test]
+fn png_reverses_every_filter_type() {
+    let rows = pixels(7, 5);
+    let filtered = filter_rows(&rows, &[0, 1, 2, 3, 4]);
+    let image = read_png(&png((7, 5), 8, 0, false, &[], &filtered)).unwrap();
+    assert_eq!(luma(&image), rows);
+}
+
+#[test]
+fn png_reads_sixteen_bit_samples() {
+    let samples: [u16; 3] = [0x0000, 0xFFFF, 0x8080];
+    let mut filtered = vec![0];
+    for sample in samples {
+        filtered.extend_from_slice(&sample.to_be_bytes());
+    }
+    let image = read_png(&png((3, 1), 16, 0, false, &[], &filtered)).unwrap();
+    assert_eq!(luma(&image), [vec![0, 255, 128]]);
+}
+
+#[test]
+fn png_applies_palette_transparency() {
+    let palette = vec![0, 0, 0, 255, 255, 255, 255, 0, 0, 0, 0, 0];
+    // The last entry is black but fully transparent, so it shows as white.
+    let alpha = vec![255, 255, 255, 0];
+    // Two bits per index: 0, 1, 2, 3.
+    let filtered = [0, 0b0001_1011];
+    let image = read_png(&png(
+        (4, 1),
+        2,
+        3,
+        false,
+        &[(b"PLTE", palette), (b"tRNS", alpha)],
+        &filtered,
+    ))
+    .unwrap();
+    assert_eq!(luma(&image), [vec![0, 255, 76, 255]]);
+}
+
+#[test]
+fn png_deinterlaces_adam7() {
+    let (width, height) = (9, 10);
+    let rows = pixels(width, height);
+    let passes = [
+        (0, 0, 8, 8),
+        (4, 0, 8, 8),
+        (0, 4, 4, 8),
+        (2, 0, 4, 4),
+        (0, 2, 2, 4),
+        (1, 0, 2, 2),
+        (0, 1, 1, 2),
+    ];
+    let mut filtered = Vec::new();
+    for (x0, y0, dx, dy) in passes {
+        let pass: Vec<Vec<u8>> = (y0..height)
+            .step_by(dy)
+            .map(|y| (x0..width).step_by(dx).map(|x| rows[y][x]).collect())
+            .collect();
+        if pass.is_empty() || pass[0].is_empty() {
+            continue;
+        }
+        // Paeth everywhere, so rows within a pass depend on each other.
+        filtered.extend(filter_rows(&pass, &vec![4; pass.len()]));
+    }
+    let image = read_png(&png(
+        (width as u32, height as u32),
+        8,
+        0,
+        true,
+        &[],
+        &filtered,
+    ))
+    .unwrap();
+    assert_eq!(luma(&image), rows);
+}
+
+#[test]
+fn png_rejects_headers_the_data_cannot_back() {
+    // 2^30 x 2^30 RGBA16 with a single byte of image data.
+    let huge = png((0x4000_0000, 0x4000_0000), 16, 6, false, &[], &[0]);
+    assert!(read_png(&huge).is_err());
+    let short = png((7, 5), 8, 0, true, &[], &[0; 20]);
+    assert!(read_png(&short).is_err());
+}
+
+#[test]
+fn pgm_reads_plain_and_binary_rasters() {
+    let plain = read_netpbm(b"P2\n# comment\n3 1\n4\n0 2 4\n").unwrap();
+    assert_eq!(luma(&plain), [vec![0, 127, 255]]);
+
+    let mut binary = b"P5 2 1 65535\n".to_vec();
+    binary.extend_from_slice(&[0xFF, 0xFF, 0x00, 0x00]);
+    assert_eq!(luma(&read_netpbm(&binary).unwrap()), [vec![255, 0]]);
+
+    let bitmap = read_netpbm(b"P4 3 2\n\xA0\x40").unwrap();
+    assert_eq!(luma(&bitmap), [vec![0, 255, 0], vec![255, 0, 255]]);
+}
+
+#[test]
+fn pgm_rejects_oversized_headers() {
+    assert!(read_netpbm(b"P5 99999999999 99999999999 255\n\0").is_err());
+    assert!(read_netpbm(b"P5 4 4 255\n\0\0\0").is_err());
+    assert!(read_netpbm(b"P2 1000000 1000000 255\n1 2 3").is_err());
+}
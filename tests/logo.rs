@@ -0,0 +1,52 @@
+#![cfg(feature = "std")]
+
+use qrcodegenerator::render::{Logo, LogoImage, LogoPolicy};
+use qrcodegenerator::{ErrorCorrectionLevel, QRData};
+
+fn logo(size: f64) -> Logo {
+    Logo::new(LogoImage::Svg("<svg/>".to_string())).size(size)
+}
+
+fn qr_data(version: u8) -> QRData {
+    let mut qr_data = QRData::new();
+    qr_data.set_content("https://example.com/logo").unwrap();
+    qr_data.set_ec_level(ErrorCorrectionLevel::H).unwrap();
+    qr_data.set_version(Some(version)).unwrap();
+    qr_data
+}
+
+#[test]
+fn sizes_outside_the_unit_interval_are_rejected() {
+    for size in [
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        f64::NAN,
+        0.0,
+        -0.2,
+        1.0,
+        2.5,
+    ] {
+        assert!(logo(size).area(57).is_err(), "{}", size);
+    }
+    let area = logo(0.2).area(57).unwrap();
+    assert_eq!((area.x, area.y, area.side), (23, 23, 11));
+}
+
+#[test]
+fn placements_must_survive_a_scan() {
+    for version in [3, 7, 15] {
+        let placement = logo(0.2)
+            .place(&mut qr_data(version), None, LogoPolicy::Refuse)
+            .unwrap();
+        assert_eq!(placement.matrix.get_version(), version);
+    }
+
+    // Erasing every EC codeword is within budget here, and the erasure
+    // decoder copes, but with the cleared modules read as errors the symbol
+    // no longer scans.
+    let error = logo(0.5)
+        .max_erasure_fraction(1.0)
+        .place(&mut qr_data(7), None, LogoPolicy::Refuse)
+        .unwrap_err();
+    assert!(error.to_string().contains("cannot be scanned"), "{}", error);
+}
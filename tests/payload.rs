@@ -0,0 +1,536 @@
+#![cfg(feature = "std")]
+
+use qrcodegenerator::payload::crypto::{eip55_checksum, validate_bitcoin_address};
+use qrcodegenerator::payload::epc::{validate_bic, validate_iban, EPC_MAX_BYTES};
+use qrcodegenerator::payload::otp::base32_encode;
+use qrcodegenerator::payload::swiss::{
+    mod10_check_digit, validate_creditor_reference, validate_qr_reference, validate_swiss_iban,
+};
+use qrcodegenerator::payload::{
+    Address, BitcoinAddressKind, BitcoinPayment, CalendarEvent, Contact, ContactFormat, EpcCharset,
+    EpcPayment, EpcVersion, EventTime, OtpAuth, Payload, PhoneType, SmsMessage, TelNumber, Url,
+    WifiAuth, WifiConfig,
+};
+use qrcodegenerator::ErrorCorrectionLevel;
+
+#[test]
+fn wifi_round_trips_escaped_fields() {
+    let configs = [
+        WifiConfig::new(r#"café;net,"5G":\"#)
+            .password(r#"p\a;s,s:"w""#)
+            .hidden(true),
+        WifiConfig::new("Guest").auth(WifiAuth::NoPass),
+        WifiConfig::new("Home")
+            .password("secret")
+            .auth(WifiAuth::Wpa3),
+        WifiConfig::new(";;;").password("\\\\").auth(WifiAuth::Wep),
+    ];
+    for config in configs {
+        let payload = config.to_payload().unwrap();
+        assert_eq!(WifiConfig::parse(&payload).unwrap(), config, "{}", payload);
+    }
+
+    let payload = WifiConfig::new("a;b")
+        .password(r#"x"y"#)
+        .to_payload()
+        .unwrap();
+    assert_eq!(payload, r#"WIFI:T:WPA;S:a\;b;P:x\"y;;"#);
+}
+
+#[test]
+fn wifi_parse_rejects_malformed_payloads() {
+    assert!(WifiConfig::parse("S:net;;").is_err());
+    assert!(WifiConfig::parse("WIFI:T:WPA;S:net;;").is_err());
+    assert!(WifiConfig::parse("WIFI:T:FOO;S:net;P:x;;").is_err());
+    assert!(WifiConfig::parse("WIFI:T:nopass;P:x;;").is_err());
+}
+
+#[test]
+fn iban_mod97_known_answers() {
+    for iban in [
+        "DE89370400440532013000",
+        "GB82WEST12345698765432",
+        "NL91ABNA0417164300",
+        "BE71096123456769",
+        "CH9300762011623852957",
+        "MT84MALT011000012345MTLCAST001S",
+    ] {
+        assert!(validate_iban(iban).is_ok(), "{}", iban);
+    }
+    for iban in [
+        "DE89370400440532013001",
+        "GB28WEST12345698765432",
+        "de89370400440532013000",
+        "DE8937040044",
+        "DE89 3704 0044 0532 0130 00",
+    ] {
+        assert!(validate_iban(iban).is_err(), "{}", iban);
+    }
+    assert!(validate_bic("DEUTDEFF").is_ok());
+    assert!(validate_bic("DEUTDEFF500").is_ok());
+    assert!(validate_bic("DEUT1EFF").is_err());
+}
+
+#[test]
+fn swiss_reference_known_answers() {
+    assert_eq!(mod10_check_digit("21000000000313947143000901"), Some(7));
+    assert_eq!(mod10_check_digit("12a"), None);
+    assert!(validate_qr_reference("210000000003139471430009017").is_ok());
+    assert!(validate_qr_reference("21 00000 00003 13947 14300 09017").is_ok());
+    assert!(validate_qr_reference("210000000003139471430009016").is_err());
+    assert!(validate_qr_reference("21000000000313947143000901").is_err());
+
+    // ISO 11649 examples.
+    assert!(validate_creditor_reference("RF18539007547034").is_ok());
+    assert!(validate_creditor_reference("RF18 5390 0754 7034").is_ok());
+    assert!(validate_creditor_reference("rf18539007547034").is_ok());
+    assert!(validate_creditor_reference("RF18000000000539007547034").is_ok());
+    assert!(validate_creditor_reference("RF19539007547034").is_err());
+    assert!(validate_creditor_reference("RF1é2345678").is_err());
+    assert!(validate_creditor_reference("RFé").is_err());
+
+    assert!(validate_swiss_iban("CH4431999123000889012").unwrap());
+    assert!(!validate_swiss_iban("CH9300762011623852957").unwrap());
+    assert!(validate_swiss_iban("DE89370400440532013000").is_err());
+}
+
+#[test]
+fn base32_known_answers() {
+    // RFC 4648 section 10, without padding.
+    for (input, expected) in [
+        ("", ""),
+        ("f", "MY"),
+        ("fo", "MZXQ"),
+        ("foo", "MZXW6"),
+        ("foob", "MZXW6YQ"),
+        ("fooba", "MZXW6YTB"),
+        ("foobar", "MZXW6YTBOI"),
+    ] {
+        assert_eq!(base32_encode(input.as_bytes()), expected);
+    }
+}
+
+/// Bits of the payload as one byte segment, as `QRData` encodes it.
+fn byte_mode_bits(payload: &str) -> usize {
+    4 + 8 + 8 * payload.len()
+}
+
+#[test]
+fn upper_case_payloads_shrink_when_split_into_segments() {
+    let otp = OtpAuth::totp(b"12345678901234567890", "alice@example.com").issuer("ACME");
+    let payload = otp.to_payload().unwrap();
+    assert_eq!(
+        payload,
+        "OTPAUTH://totp/ACME:alice@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACME"
+    );
+    let lower = otp.clone().uppercase(false).to_payload().unwrap();
+    assert!(lower.starts_with("otpauth://totp/"));
+    assert!(otp.to_segments().unwrap().bit_len(1).unwrap() < byte_mode_bits(&payload));
+}
+
+#[test]
+fn upper_case_urls_are_opt_in() {
+    let url = Url::new("https://Example.com/a").param("q", "1");
+    assert_eq!(url.to_payload().unwrap(), "https://example.com/a?q=1");
+
+    let url = url.uppercase(true);
+    let payload = url.to_payload().unwrap();
+    assert_eq!(payload, "HTTPS://EXAMPLE.COM/a?q=1");
+    assert!(url.to_segments().unwrap().bit_len(1).unwrap() < byte_mode_bits(&payload));
+}
+
+#[test]
+fn uris_escape_fragment_and_percent_characters() {
+    assert_eq!(TelNumber::new("*31#").to_payload().unwrap(), "tel:*31%23");
+    assert_eq!(
+        TelNumber::new("+41 (44) 668.18.00").to_payload().unwrap(),
+        "tel:+41446681800"
+    );
+    assert_eq!(
+        SmsMessage::new("#100").body("a&b").to_payload().unwrap(),
+        "sms:%23100?body=a%26b"
+    );
+
+    for (url, expected) in [
+        ("https://x.com/?x=50%off", "https://x.com/?x=50%25off"),
+        ("https://x.com/a%20b%2f", "https://x.com/a%20b%2f"),
+        ("https://x.com/100%", "https://x.com/100%25"),
+        ("https://x.com/%4g#50%", "https://x.com/%254g#50%25"),
+        ("https://x.com/é", "https://x.com/%C3%A9"),
+    ] {
+        assert_eq!(Url::new(url).to_payload().unwrap(), expected);
+    }
+}
+
+#[test]
+fn bitcoin_address_known_answers() {
+    for (address, kind) in [
+        // Base58Check P2PKH (the genesis block) and P2SH.
+        (
+            "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa",
+            BitcoinAddressKind::Base58,
+        ),
+        (
+            "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy",
+            BitcoinAddressKind::Base58,
+        ),
+        // BIP173.
+        (
+            "BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4",
+            BitcoinAddressKind::Bech32,
+        ),
+        (
+            "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+            BitcoinAddressKind::Bech32,
+        ),
+        // BIP350.
+        (
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+            BitcoinAddressKind::Bech32m,
+        ),
+    ] {
+        assert_eq!(
+            validate_bitcoin_address(address).unwrap(),
+            kind,
+            "{}",
+            address
+        );
+    }
+
+    for address in [
+        "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNb",
+        "1A1zP1eP5QGefi2DMPTfTL5SLmv7Divf0a",
+        "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5",
+        "bc1qw508d6qejxtdg4y5r3zarvary0C5xw7kv8f3t4",
+        // Bech32m checksum on a version 0 program.
+        "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kemeawh",
+        // Bech32 checksum on a version 1 program.
+        "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd",
+    ] {
+        assert!(validate_bitcoin_address(address).is_err(), "{}", address);
+    }
+}
+
+#[test]
+fn bitcoin_payment_keeps_the_address_alphanumeric() {
+    let payment = BitcoinPayment::new("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4")
+        .amount_sats(150_000)
+        .label("Café");
+    let payload = payment.to_payload().unwrap();
+    assert_eq!(
+        payload,
+        "BITCOIN:BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4?amount=0.0015&label=Caf%C3%A9"
+    );
+    assert!(payment.to_segments().unwrap().bit_len(1).unwrap() < byte_mode_bits(&payload));
+}
+
+#[test]
+fn eip55_known_answers() {
+    for address in [
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ] {
+        assert_eq!(eip55_checksum(address).unwrap(), address);
+        assert_eq!(
+            eip55_checksum(&address.to_ascii_lowercase()).unwrap(),
+            address
+        );
+    }
+    assert!(eip55_checksum("0x5AAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").is_err());
+    assert!(eip55_checksum("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAe").is_err());
+}
+
+#[test]
+fn epc_payments_stay_in_one_byte_segment() {
+    let payment = EpcPayment::new("Red Cross", "BE72000000001616")
+        .bic("BPOTBEB1")
+        .amount_cents(100);
+    let payload = payment.to_payload().unwrap();
+    assert_eq!(
+        payment.to_segments().unwrap().bit_len(1).unwrap(),
+        byte_mode_bits(&payload)
+    );
+}
+
+#[test]
+fn epc_payments_write_every_field_in_order() {
+    let payment = EpcPayment::new("Red Cross", "BE72 0000 0000 1616")
+        .version(EpcVersion::V001)
+        .bic("BPOTBEB1")
+        .amount_cents(12345)
+        .purpose("CHAR")
+        .text("Donation")
+        .information("Thanks");
+    assert_eq!(
+        payment.to_payload().unwrap(),
+        "BCD\n001\n1\nSCT\nBPOTBEB1\nRed Cross\nBE72000000001616\nEUR123.45\nCHAR\n\nDonation\nThanks"
+    );
+
+    // Trailing empty elements are dropped.
+    let payment = EpcPayment::new("Red Cross", "BE72000000001616").reference("RF18539007547034");
+    assert_eq!(
+        payment.to_payload().unwrap(),
+        "BCD\n002\n1\nSCT\n\nRed Cross\nBE72000000001616\n\n\nRF18539007547034"
+    );
+    assert!(EpcPayment::new("Red Cross", "BE72000000001616")
+        .version(EpcVersion::V001)
+        .to_payload()
+        .is_err());
+}
+
+#[test]
+fn epc_payloads_are_limited_to_331_bytes_in_their_charset() {
+    let payment = |information: &str| {
+        EpcPayment::new(&"N".repeat(70), "BE72000000001616")
+            .bic("BPOTBEB1XXX")
+            .amount_cents(99_999_999_999)
+            .purpose("CHAR")
+            .text(&"T".repeat(140))
+            .information(information)
+    };
+    let payload = payment(&"I".repeat(54)).to_payload().unwrap();
+    assert_eq!(payload.len(), EPC_MAX_BYTES);
+    assert!(payment(&"I".repeat(55)).to_payload().is_err());
+
+    // 30 characters of two UTF-8 bytes each overflow, but fit as Latin-1.
+    let information = "é".repeat(30);
+    assert!(payment(&information).to_payload().is_err());
+    let latin1 = payment(&information).charset(EpcCharset::Iso8859_1);
+    assert_eq!(latin1.to_bytes().unwrap().len(), EPC_MAX_BYTES - 24);
+}
+
+#[test]
+fn epc_text_is_transcoded_into_the_chosen_charset() {
+    let payment = EpcPayment::new("Łódź", "BE72000000001616").charset(EpcCharset::Iso8859_2);
+    let payload = payment.to_payload().unwrap();
+    assert!(payload.starts_with("BCD\n002\n3\nSCT\n\nŁódź\n"));
+
+    let bytes = payment.to_bytes().unwrap();
+    assert_eq!(&bytes[..20], b"BCD\n002\n3\nSCT\n\n\xA3\xF3d\xBC\n");
+    assert_eq!(
+        payment.to_segments().unwrap().bit_len(1).unwrap(),
+        4 + 8 + 8 * bytes.len()
+    );
+    // QRData holds UTF-8, so only ASCII text goes through it.
+    assert!(payment.to_qr_data().is_err());
+    assert!(EpcPayment::new("Lodz", "BE72000000001616")
+        .charset(EpcCharset::Iso8859_2)
+        .to_qr_data()
+        .is_ok());
+
+    assert!(EpcPayment::new("Ωmega", "BE72000000001616")
+        .charset(EpcCharset::Iso8859_2)
+        .to_payload()
+        .is_err());
+    assert!(EpcPayment::new("Ωmega", "BE72000000001616")
+        .charset(EpcCharset::Iso8859_7)
+        .to_payload()
+        .is_ok());
+    assert!(EpcPayment::new("€", "BE72000000001616")
+        .charset(EpcCharset::Iso8859_1)
+        .to_payload()
+        .is_err());
+    assert_eq!(
+        EpcCharset::Iso8859_15.encode("€uro").unwrap(),
+        b"\xA4uro".to_vec()
+    );
+
+    for code in 1..=8 {
+        assert_eq!(EpcCharset::from_code(code).unwrap().code(), code);
+    }
+    assert!(EpcCharset::from_code(0).is_err());
+    assert!(EpcCharset::from_code(9).is_err());
+}
+
+fn sample_contact() -> Contact {
+    Contact::new("Jane", "Doe, Jr.")
+        .organization(r"A;B\C")
+        .phone("+1 555 0100", &[PhoneType::Cell, PhoneType::Work])
+        .email("jane@example.com")
+        .note("line one\nline two")
+}
+
+#[test]
+fn vcards_escape_text_and_end_every_line_in_crlf() {
+    let contact = sample_contact();
+    assert_eq!(
+        contact.to_vcard(ContactFormat::VCard3).unwrap(),
+        concat!(
+            "BEGIN:VCARD\r\n",
+            "VERSION:3.0\r\n",
+            "N:Doe\\, Jr.;Jane;;;\r\n",
+            "FN:Jane Doe\\, Jr.\r\n",
+            "ORG:A\\;B\\\\C\r\n",
+            "TEL;TYPE=CELL,WORK:+1 555 0100\r\n",
+            "EMAIL;TYPE=INTERNET:jane@example.com\r\n",
+            "NOTE:line one\\nline two\r\n",
+            "END:VCARD\r\n",
+        )
+    );
+    assert_eq!(
+        contact.to_vcard(ContactFormat::VCard4).unwrap(),
+        concat!(
+            "BEGIN:VCARD\r\n",
+            "VERSION:4.0\r\n",
+            "N:Doe\\, Jr.;Jane;;;\r\n",
+            "FN:Jane Doe\\, Jr.\r\n",
+            "ORG:A\\;B\\\\C\r\n",
+            "TEL;VALUE=uri;TYPE=\"cell,work\":tel:+1-555-0100\r\n",
+            "EMAIL:jane@example.com\r\n",
+            "NOTE:line one\\nline two\r\n",
+            "END:VCARD\r\n",
+        )
+    );
+    assert!(contact
+        .clone()
+        .format(ContactFormat::MeCard)
+        .to_vcard(ContactFormat::MeCard)
+        .is_err());
+}
+
+#[test]
+fn vcard_lines_fold_at_75_octets_between_characters() {
+    let note = "é".repeat(50);
+    let vcard = Contact::new("Jane", "Doe")
+        .note(&note)
+        .to_vcard(ContactFormat::VCard3)
+        .unwrap();
+    assert!(vcard.ends_with("\r\n"));
+    for line in vcard.split_terminator("\r\n") {
+        assert!(line.len() <= 75, "{:?}", line);
+    }
+    // "NOTE:" and 35 two-octet characters fill the first line exactly.
+    assert!(vcard.contains(&format!(
+        "NOTE:{}\r\n {}\r\n",
+        "é".repeat(35),
+        "é".repeat(15)
+    )));
+    assert!(vcard
+        .replace("\r\n ", "")
+        .contains(&format!("NOTE:{}\r\n", note)));
+}
+
+#[test]
+fn mecards_escape_special_characters() {
+    let mecard = sample_contact().to_mecard().unwrap();
+    assert_eq!(
+        mecard,
+        "MECARD:N:Doe\\, Jr.,Jane;ORG:A\\;B\\\\C;TEL:+1 555 0100;\
+         EMAIL:jane@example.com;NOTE:line one\nline two;;"
+    );
+    let quoted = Contact::new("a:b", "\"x\"").to_mecard().unwrap();
+    assert_eq!(quoted, "MECARD:N:\\\"x\\\",a\\:b;;");
+}
+
+#[test]
+fn size_report_suggests_mecard_when_only_it_fits() {
+    let contact = Contact::new("Jane", "Doe")
+        .organization("Example Corporation")
+        .title("Chief Executive Officer")
+        .phone("+1 555 0100", &[PhoneType::Cell])
+        .phone("+1 555 0101", &[PhoneType::Work])
+        .email("jane.doe@example.com")
+        .url("https://example.com/jane")
+        .address(Address {
+            street: "1 Main Street".to_string(),
+            locality: "Springfield".to_string(),
+            postal_code: "12345".to_string(),
+            country: "USA".to_string(),
+            ..Address::default()
+        })
+        .photo_uri("https://example.com/jane.jpg");
+
+    let report = contact.size_report(ErrorCorrectionLevel::M, 10).unwrap();
+    assert_eq!(
+        report.vcard_bytes,
+        contact.to_vcard(ContactFormat::VCard3).unwrap().len()
+    );
+    assert_eq!(report.mecard_bytes, contact.to_mecard().unwrap().len());
+    assert!(report.mecard_version < report.vcard_version);
+    assert!(report.suggest_mecard);
+
+    // Neither fits a version below the MeCard's, and both fit a large one.
+    let below = report.mecard_version.unwrap() - 1;
+    let report_below = contact.size_report(ErrorCorrectionLevel::M, below).unwrap();
+    assert!(!report_below.suggest_mecard);
+    let report_large = contact.size_report(ErrorCorrectionLevel::M, 40).unwrap();
+    assert!(!report_large.suggest_mecard);
+}
+
+#[test]
+fn events_escape_text_and_end_every_line_in_crlf() {
+    let event = CalendarEvent::new("Launch; v2, final", EventTime::utc(2026, 3, 9, 14, 0, 0))
+        .end(EventTime::utc(2026, 3, 9, 15, 30, 0))
+        .location(r"Room 4\B")
+        .description("Agenda:\r\n1. Demo");
+    assert_eq!(
+        event.to_payload().unwrap(),
+        concat!(
+            "BEGIN:VEVENT\r\n",
+            "SUMMARY:Launch\\; v2\\, final\r\n",
+            "DTSTART:20260309T140000Z\r\n",
+            "DTEND:20260309T153000Z\r\n",
+            "LOCATION:Room 4\\\\B\r\n",
+            "DESCRIPTION:Agenda:\\n1. Demo\r\n",
+            "END:VEVENT\r\n",
+        )
+    );
+
+    let floating = CalendarEvent::new("Lunch", EventTime::floating(2026, 3, 9, 12, 0, 0));
+    assert!(floating
+        .to_payload()
+        .unwrap()
+        .contains("\r\nDTSTART:20260309T120000\r\n"));
+}
+
+#[test]
+fn event_lines_fold_at_75_octets() {
+    let description = "0123456789".repeat(20);
+    let payload = CalendarEvent::new("Talk", EventTime::utc(2026, 1, 1, 9, 0, 0))
+        .description(&description)
+        .to_payload()
+        .unwrap();
+    for line in payload.split_terminator("\r\n") {
+        assert!(line.len() <= 75, "{:?}", line);
+    }
+    // "DESCRIPTION:" and 63 digits fill the first line exactly.
+    assert!(payload.contains(&format!(
+        "\r\nDESCRIPTION:{}\r\n {}",
+        &description[..63],
+        &description[63..137]
+    )));
+    assert!(payload
+        .replace("\r\n ", "")
+        .contains(&format!("\r\nDESCRIPTION:{}\r\n", description)));
+}
+
+#[test]
+fn events_reject_invalid_times() {
+    let start = EventTime::utc(2026, 3, 9, 14, 0, 0);
+    let event = |start: EventTime| CalendarEvent::new("x", start).to_payload();
+
+    assert!(event(EventTime::utc(2024, 2, 29, 0, 0, 0)).is_ok());
+    assert!(event(EventTime::utc(2000, 2, 29, 0, 0, 0)).is_ok());
+    assert!(event(EventTime::utc(2016, 12, 31, 23, 59, 60)).is_ok());
+    for invalid in [
+        EventTime::utc(2026, 2, 29, 0, 0, 0),
+        EventTime::utc(1900, 2, 29, 0, 0, 0),
+        EventTime::utc(2026, 4, 31, 0, 0, 0),
+        EventTime::utc(2026, 13, 1, 0, 0, 0),
+        EventTime::utc(2026, 1, 0, 0, 0, 0),
+        EventTime::utc(2026, 1, 1, 24, 0, 0),
+        EventTime::utc(2026, 1, 1, 0, 60, 0),
+        EventTime::utc(2026, 1, 1, 0, 0, 61),
+    ] {
+        assert!(event(invalid).is_err(), "{}", invalid.to_ical());
+    }
+
+    let ends = |end: EventTime| CalendarEvent::new("x", start).end(end).to_payload();
+    assert!(ends(start).is_ok());
+    assert!(ends(EventTime::utc(2026, 3, 9, 13, 59, 59)).is_err());
+    assert!(ends(EventTime::utc(2025, 12, 31, 23, 0, 0)).is_err());
+    assert!(ends(EventTime::floating(2026, 3, 9, 15, 0, 0)).is_err());
+    assert!(CalendarEvent::new("", start).to_payload().is_err());
+}
@@ -0,0 +1,211 @@
+#![cfg(feature = "std")]
+
+use qrcodegenerator::matrix::ModuleKind;
+use qrcodegenerator::render::{to_eps, to_pdf, Cmyk, Color, Fill, RenderOptions};
+use qrcodegenerator::{QRData, QRMatrix};
+
+fn hello_world() -> QRMatrix {
+    let mut qr_data = QRData::new();
+    qr_data.set_content("HELLO WORLD").unwrap();
+    qr_data.generate(None).unwrap()
+}
+
+/// Colour operators and the rectangles filled after each, in points.
+fn layers(program: &str, color_op: &str, rect_op: &str) -> Vec<(String, Vec<[f64; 4]>)> {
+    let mut layers: Vec<(String, Vec<[f64; 4]>)> = Vec::new();
+    for line in program.lines() {
+        if line.ends_with(color_op) {
+            layers.push((line.to_string(), Vec::new()));
+        } else if let Some(rect) = line.strip_suffix(rect_op) {
+            let values: Vec<f64> = rect
+                .split_whitespace()
+                .map(|v| v.parse().unwrap())
+                .collect();
+            layers
+                .last_mut()
+                .unwrap()
+                .1
+                .push([values[0], values[1], values[2], values[3]]);
+        }
+    }
+    layers
+}
+
+fn eps_layers(matrix: &QRMatrix, options: &RenderOptions) -> Vec<(String, Vec<[f64; 4]>)> {
+    layers(&to_eps(matrix, options), "color", " R")
+}
+
+fn pdf_layers(matrix: &QRMatrix, options: &RenderOptions) -> Vec<(String, Vec<[f64; 4]>)> {
+    let pdf = String::from_utf8(to_pdf(matrix, options)).unwrap();
+    layers(&pdf, " rg", " re")
+}
+
+fn area(rects: &[[f64; 4]]) -> f64 {
+    rects
+        .iter()
+        .map(|[_, _, width, height]| width * height)
+        .sum()
+}
+
+fn dark_modules(matrix: &QRMatrix) -> usize {
+    let size = matrix.get_size();
+    (0..size)
+        .flat_map(|y| (0..size).map(move |x| (x, y)))
+        .filter(|&(x, y)| matrix.is_dark(x, y))
+        .count()
+}
+
+fn horizontal_runs(matrix: &QRMatrix) -> usize {
+    let size = matrix.get_size();
+    (0..size)
+        .flat_map(|y| (0..size).map(move |x| (x, y)))
+        .filter(|&(x, y)| matrix.is_dark(x, y) && (x == 0 || !matrix.is_dark(x - 1, y)))
+        .count()
+}
+
+#[test]
+fn print_size_follows_the_module_size() {
+    let matrix = hello_world();
+    let options = RenderOptions {
+        module_size_mm: Some(0.5),
+        ..RenderOptions::default()
+    };
+
+    // 21 modules plus a quiet zone of 4 on each side at 0.5 mm is 14.5 mm.
+    let eps = to_eps(&matrix, &options);
+    assert!(eps.contains("%%BoundingBox: 0 0 42 42\n"));
+    assert!(eps.contains("%%HiResBoundingBox: 0 0 41.1024 41.1024\n"));
+    let pdf = String::from_utf8(to_pdf(&matrix, &options)).unwrap();
+    assert!(pdf.contains("/MediaBox [0 0 41.1024 41.1024]"));
+
+    // Without a physical size a module is `scale` points.
+    let eps = to_eps(&matrix, &RenderOptions::default());
+    assert!(eps.contains("%%BoundingBox: 0 0 232 232\n"));
+    let pdf = String::from_utf8(to_pdf(&matrix, &RenderOptions::default())).unwrap();
+    assert!(pdf.contains("/MediaBox [0 0 232 232]"));
+}
+
+#[test]
+fn cmyk_values_select_the_cmyk_operators() {
+    let matrix = hello_world();
+    let options = RenderOptions {
+        dark_cmyk: Some("100,0,0,20".parse().unwrap()),
+        ..RenderOptions::default()
+    };
+    assert_eq!(
+        options.dark_cmyk,
+        Some(Cmyk {
+            c: 1.0,
+            m: 0.0,
+            y: 0.0,
+            k: 0.2
+        })
+    );
+
+    let eps = to_eps(&matrix, &options);
+    assert!(eps.contains("0 0 0 0 setcmykcolor\n"));
+    assert!(eps.contains("1 0 0 0.2 setcmykcolor\n"));
+    assert!(!eps.contains("setrgbcolor"));
+
+    let pdf = String::from_utf8(to_pdf(&matrix, &options)).unwrap();
+    assert!(pdf.contains("0 0 0 0 k\n"));
+    assert!(pdf.contains("1 0 0 0.2 k\n"));
+    assert!(!pdf.contains(" rg\n"));
+
+    let eps = to_eps(&matrix, &RenderOptions::default());
+    assert!(eps.contains("1 1 1 setrgbcolor\n0 0 232 232 R\n"));
+    assert!(eps.contains("0 0 0 setrgbcolor\n"));
+}
+
+#[test]
+fn runs_of_dark_modules_are_merged() {
+    let matrix = hello_world();
+    let options = RenderOptions::default();
+
+    for layers in [eps_layers(&matrix, &options), pdf_layers(&matrix, &options)] {
+        assert_eq!(layers.len(), 2);
+        let dark = &layers[1].1;
+        assert_eq!(dark.len(), horizontal_runs(&matrix));
+        assert_eq!(area(dark), dark_modules(&matrix) as f64 * 64.0);
+        assert!(dark.iter().all(|rect| rect[3] == 8.0));
+    }
+}
+
+#[test]
+fn dark_modules_stay_out_of_the_quiet_zone() {
+    let matrix = hello_world();
+    for border in [0, 4] {
+        let options = RenderOptions {
+            scale: 1,
+            border,
+            ..RenderOptions::default()
+        };
+        let (low, high) = (border as f64, (border as usize + 21) as f64);
+
+        for layers in [eps_layers(&matrix, &options), pdf_layers(&matrix, &options)] {
+            let extent = high + border as f64;
+            assert_eq!(layers[0].1, vec![[0.0, 0.0, extent, extent]]);
+            for [x, y, width, height] in &layers[1].1 {
+                assert!(*x >= low && x + width <= high);
+                assert!(*y >= low && y + height <= high);
+            }
+        }
+    }
+}
+
+#[test]
+fn finder_colours_get_a_layer_of_their_own() {
+    let matrix = hello_world();
+    let options = RenderOptions {
+        scale: 1,
+        finder_color: Some(Color { r: 204, g: 0, b: 0 }),
+        ..RenderOptions::default()
+    };
+    let finder_modules = (0..21)
+        .flat_map(|y| (0..21).map(move |x| (x, y)))
+        .filter(|&(x, y)| {
+            matrix.is_dark(x, y) && matrix.get_kind(x, y) == ModuleKind::FinderPattern
+        })
+        .count();
+    assert_eq!(finder_modules, 3 * 33);
+
+    for layers in [eps_layers(&matrix, &options), pdf_layers(&matrix, &options)] {
+        assert_eq!(layers.len(), 3);
+        let finder = layers
+            .iter()
+            .find(|(color, _)| color.starts_with("0.8 0 0 "))
+            .unwrap();
+        assert_eq!(area(&finder.1), finder_modules as f64);
+        assert_eq!(
+            area(&layers[1].1) + area(&layers[2].1),
+            dark_modules(&matrix) as f64
+        );
+    }
+}
+
+#[test]
+fn gradients_take_one_colour_per_module() {
+    let matrix = hello_world();
+    let options = RenderOptions {
+        scale: 1,
+        fill: Fill::LinearGradient {
+            start: Color::BLACK,
+            end: Color { r: 0, g: 0, b: 128 },
+            angle: 0.0,
+        },
+        ..RenderOptions::default()
+    };
+
+    for layers in [eps_layers(&matrix, &options), pdf_layers(&matrix, &options)] {
+        // A horizontal gradient changes colour in every column.
+        assert!(layers.len() > 10);
+        let dark: Vec<[f64; 4]> = layers[1..]
+            .iter()
+            .flat_map(|(_, rects)| rects.iter().copied())
+            .collect();
+        assert_eq!(area(&dark), dark_modules(&matrix) as f64);
+        assert!(dark.iter().all(|rect| rect[2] == 1.0));
+        assert!(layers.iter().any(|(color, _)| color.starts_with("0 0 0.0")));
+        assert!(layers.iter().any(|(color, _)| color.starts_with("0 0 0.4")));
+    }
+}
@@ -0,0 +1,183 @@
+#![cfg(feature = "alloc")]
+
+use qrcodegenerator::decoder::{decode_matrix, decode_with_erasures};
+use qrcodegenerator::error_correction::{interleave_order, BlockLayout};
+use qrcodegenerator::random::{Alphabet, InputGenerator, Rng};
+use qrcodegenerator::{ErrorCorrectionLevel, QRData, QRMatrix, VERSION_CAPACITIES};
+
+const LEVELS: [ErrorCorrectionLevel; 4] = [
+    ErrorCorrectionLevel::L,
+    ErrorCorrectionLevel::M,
+    ErrorCorrectionLevel::Q,
+    ErrorCorrectionLevel::H,
+];
+
+/// Largest content in bytes the alphabet's mode holds at this version and level.
+fn capacity(version: u8, level: ErrorCorrectionLevel, alphabet: Alphabet) -> usize {
+    let capacity = &VERSION_CAPACITIES[version as usize - 1].capacity_by_ec[level as usize];
+    match alphabet {
+        Alphabet::Digits => capacity.numeric,
+        Alphabet::Alphanumeric => capacity.alphanumeric,
+        _ => capacity.byte,
+    }
+}
+
+fn encode(text: &str, version: u8, level: ErrorCorrectionLevel, mask: u8) -> QRMatrix {
+    let mut qr_data = QRData::new();
+    qr_data.set_content(text).unwrap();
+    qr_data.set_ec_level(level).unwrap();
+    qr_data.set_version(Some(version)).unwrap();
+    qr_data.generate(Some(mask)).unwrap()
+}
+
+/// Flips a random non-empty subset of the eight modules of the codeword at
+/// `index` in the interleaved stream, returning the flipped positions.
+/// `positions` is the matrix's data module order.
+fn corrupt_codeword(
+    matrix: &mut QRMatrix,
+    positions: &[(usize, usize)],
+    index: usize,
+    rng: &mut Rng,
+) -> Vec<(usize, usize)> {
+    let pattern = 1 + rng.below(255);
+    let mut flipped = Vec::new();
+    for (bit, &(x, y)) in positions[index * 8..index * 8 + 8].iter().enumerate() {
+        if pattern >> bit & 1 == 1 {
+            matrix.set_module(x, y, !matrix.is_dark(x, y));
+            flipped.push((x, y));
+        }
+    }
+    flipped
+}
+
+/// Stream indices of every codeword, grouped by block.
+fn codewords_by_block(layout: &BlockLayout) -> Vec<Vec<usize>> {
+    let mut blocks = vec![Vec::new(); layout.num_blocks];
+    for (i, (block, _)) in interleave_order(layout).into_iter().enumerate() {
+        blocks[block].push(i);
+    }
+    blocks
+}
+
+#[test]
+fn generator_is_deterministic() {
+    let mut a = InputGenerator::new(42);
+    let mut b = InputGenerator::new(42);
+    for alphabet in Alphabet::ALL {
+        assert_eq!(a.text(alphabet, 100), b.text(alphabet, 100));
+    }
+    assert_ne!(
+        InputGenerator::new(1).text(Alphabet::Bytes, 100),
+        InputGenerator::new(2).text(Alphabet::Bytes, 100)
+    );
+}
+
+#[test]
+fn generated_text_respects_the_byte_limit() {
+    let mut generator = InputGenerator::new(7);
+    for alphabet in Alphabet::ALL {
+        for _ in 0..200 {
+            let text = generator.text(alphabet, 17);
+            assert!(!text.is_empty() && text.len() <= 17, "{:?}", text);
+        }
+    }
+}
+
+#[test]
+fn round_trips_every_version_level_and_mask() {
+    let mut generator = InputGenerator::new(0x5EED);
+    for version in 1..=40u8 {
+        for (l, &level) in LEVELS.iter().enumerate() {
+            for mask in 0..8u8 {
+                let alphabet = Alphabet::ALL[(version as usize + l + mask as usize) % 6];
+                let text = generator.text(alphabet, capacity(version, level, alphabet));
+                let matrix = encode(&text, version, level, mask);
+
+                let decoded = decode_matrix(&matrix).unwrap_or_else(|e| {
+                    panic!(
+                        "{}-{:?} mask {}: {} for {:?}",
+                        version, level, mask, e, text
+                    )
+                });
+                assert_eq!(decoded.content, text.as_bytes());
+                assert_eq!(
+                    (decoded.version, decoded.ec_level, decoded.mask),
+                    (version, level, mask)
+                );
+                assert!(decoded.corrections.iter().all(|c| c.errors == 0));
+            }
+        }
+    }
+}
+
+#[test]
+fn corrects_errors_up_to_the_limit() {
+    let mut generator = InputGenerator::new(0xE770);
+    for version in 1..=40u8 {
+        for &level in &LEVELS {
+            let alphabet = Alphabet::ALL[version as usize % 6];
+            let text = generator.text(alphabet, capacity(version, level, alphabet));
+            let mask = generator.rng().below(8) as u8;
+            let mut matrix = encode(&text, version, level, mask);
+
+            let positions = matrix.data_positions();
+            let layout = BlockLayout::new(version, level).unwrap();
+            let limit = layout.ecc_per_block / 2;
+            for codewords in codewords_by_block(&layout) {
+                for pick in generator.rng().choose(codewords.len(), limit) {
+                    corrupt_codeword(&mut matrix, &positions, codewords[pick], generator.rng());
+                }
+            }
+
+            let decoded = decode_matrix(&matrix).unwrap_or_else(|e| {
+                panic!(
+                    "{}-{:?} with {} errors per block: {}",
+                    version, level, limit, e
+                )
+            });
+            assert_eq!(decoded.content, text.as_bytes());
+            for correction in &decoded.corrections {
+                assert_eq!(correction.errors, limit);
+                assert_eq!(
+                    correction.remaining_budget(layout.ecc_per_block),
+                    layout.ecc_per_block % 2
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn corrects_mixed_errors_and_erasures_up_to_the_limit() {
+    let mut generator = InputGenerator::new(0xE7A5E);
+    for version in 1..=40u8 {
+        for &level in &LEVELS {
+            let text = generator.text(Alphabet::Latin1, capacity(version, level, Alphabet::Latin1));
+            let mut matrix = encode(&text, version, level, version % 8);
+
+            let positions = matrix.data_positions();
+            let layout = BlockLayout::new(version, level).unwrap();
+            let mut erased = Vec::new();
+            for codewords in codewords_by_block(&layout) {
+                // Any split with 2 * errors + erasures = ecc must be repairable.
+                let errors = generator.rng().below(layout.ecc_per_block / 2 + 1);
+                let erasures = layout.ecc_per_block - 2 * errors;
+                let picks = generator.rng().choose(codewords.len(), errors + erasures);
+                for (n, &pick) in picks.iter().enumerate() {
+                    let flipped =
+                        corrupt_codeword(&mut matrix, &positions, codewords[pick], generator.rng());
+                    if n >= errors {
+                        erased.extend(flipped);
+                    }
+                }
+            }
+
+            let decoded = decode_with_erasures(&matrix, &erased)
+                .unwrap_or_else(|e| panic!("{}-{:?}: {}", version, level, e));
+            assert_eq!(decoded.content, text.as_bytes());
+            for correction in &decoded.corrections {
+                assert_eq!(correction.remaining_budget(layout.ecc_per_block), 0);
+            }
+        }
+    }
+}
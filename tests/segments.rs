@@ -0,0 +1,147 @@
+#![cfg(feature = "alloc")]
+
+use qrcodegenerator::decoder::decode_matrix;
+use qrcodegenerator::segment::SegmentBuilder;
+use qrcodegenerator::{ErrorCorrectionLevel, QRData};
+
+#[test]
+fn single_segment_matches_qr_data() {
+    let mut numeric = SegmentBuilder::new();
+    numeric.push_numeric("01234567").unwrap();
+    let mut alphanumeric = SegmentBuilder::new();
+    alphanumeric.push_alphanumeric("HELLO WORLD").unwrap();
+    let mut bytes = SegmentBuilder::new();
+    bytes.push_bytes(b"https://example.com/?q=1").unwrap();
+
+    for (content, builder) in [
+        ("01234567", numeric),
+        ("HELLO WORLD", alphanumeric),
+        ("https://example.com/?q=1", bytes),
+    ] {
+        let mut qr_data = QRData::new();
+        qr_data.set_content(content).unwrap();
+        qr_data.set_ec_level(ErrorCorrectionLevel::Q).unwrap();
+
+        let version = qr_data.get_version().unwrap();
+        assert_eq!(
+            builder.smallest_version(ErrorCorrectionLevel::Q),
+            Some(version)
+        );
+        assert_eq!(
+            builder
+                .data_codewords(version, ErrorCorrectionLevel::Q)
+                .unwrap(),
+            qr_data.get_data_codewords().unwrap(),
+            "{}",
+            content
+        );
+    }
+}
+
+#[test]
+fn mixed_segments_round_trip() {
+    let mut builder = SegmentBuilder::new();
+    builder
+        .push_eci(26)
+        .unwrap()
+        .push_alphanumeric("SN:")
+        .unwrap()
+        .push_numeric("0012345678")
+        .unwrap()
+        // Shift JIS for the two characters of "点茗".
+        .push_kanji(&[0x93, 0x5F, 0xE4, 0xAA])
+        .unwrap()
+        .push_bytes(b"/ok")
+        .unwrap();
+
+    let matrix = builder
+        .generate(ErrorCorrectionLevel::M, None, None)
+        .unwrap();
+    let decoded = decode_matrix(&matrix).unwrap();
+    assert_eq!(
+        decoded.content,
+        [&b"SN:0012345678"[..], &[0x93, 0x5F, 0xE4, 0xAA], b"/ok"].concat()
+    );
+    assert_eq!(decoded.eci, vec![26]);
+}
+
+#[test]
+fn bit_length_grows_with_each_push() {
+    let mut builder = SegmentBuilder::new();
+    assert_eq!(builder.bit_len(1), Some(0));
+
+    // 4 + 10 + 10 + 4 bits for four digits.
+    builder.push_numeric("1234").unwrap();
+    assert_eq!(builder.bit_len(1), Some(28));
+    // Versions 10 and up widen the numeric count to 12 bits.
+    assert_eq!(builder.bit_len(10), Some(30));
+
+    // 4 + 8 + 8 * 20 bits.
+    builder.push_bytes(&[0xA5; 20]).unwrap();
+    assert_eq!(builder.bit_len(1), Some(28 + 172));
+    assert_eq!(builder.smallest_version(ErrorCorrectionLevel::L), Some(2));
+    assert!(!builder.fits(1, ErrorCorrectionLevel::L));
+}
+
+#[test]
+fn pushes_reject_characters_outside_the_mode() {
+    let mut builder = SegmentBuilder::new();
+    assert!(builder.push_numeric("12a").is_err());
+    assert!(builder.push_alphanumeric("hello").is_err());
+    assert!(builder.push_kanji(&[0x93]).is_err());
+    assert!(builder.push_kanji(&[0x41, 0x42]).is_err());
+    assert!(builder.push_eci(1_000_000).is_err());
+    assert!(builder.push_bytes(&[]).is_err());
+    assert!(builder.is_empty());
+}
+
+#[test]
+fn push_text_moves_long_alphanumeric_runs_out_of_byte_mode() {
+    let text = "HTTPS://EXAMPLE.COM/path?id=ABCDEFGHIJKLMNOP&x=AB";
+    let mut split = SegmentBuilder::new();
+    split.push_text(text).unwrap();
+    let mut bytes = SegmentBuilder::new();
+    bytes.push_bytes(text.as_bytes()).unwrap();
+
+    // 20 + 16 characters at 5.5 bits instead of 8, less two alphanumeric
+    // headers and one extra byte header.
+    assert_eq!(
+        split.bit_len(1).unwrap(),
+        bytes.bit_len(1).unwrap() - 90 + 26 + 12
+    );
+    let matrix = split.generate(ErrorCorrectionLevel::M, None, None).unwrap();
+    assert_eq!(decode_matrix(&matrix).unwrap().content, text.as_bytes());
+
+    // Short runs and non-ASCII text stay in byte mode.
+    let mut short = SegmentBuilder::new();
+    short.push_text("é ABCDE é").unwrap();
+    let mut whole = SegmentBuilder::new();
+    whole.push_bytes("é ABCDE é".as_bytes()).unwrap();
+    assert_eq!(short, whole);
+}
+
+#[test]
+fn kanji_rejects_invalid_trail_bytes() {
+    // 0x8200 would pack to the same 13 bits as 0x8240.
+    for code in [
+        [0x82, 0x00],
+        [0x81, 0x3F],
+        [0x88, 0x7F],
+        [0x9F, 0xFD],
+        [0xE0, 0xFF],
+    ] {
+        assert!(
+            SegmentBuilder::new().push_kanji(&code).is_err(),
+            "{:02X?}",
+            code
+        );
+    }
+
+    let valid = [0x82, 0x40, 0x88, 0x80, 0x9F, 0xFC, 0xEB, 0xBF];
+    let mut builder = SegmentBuilder::new();
+    builder.push_kanji(&valid).unwrap();
+    let matrix = builder
+        .generate(ErrorCorrectionLevel::M, None, None)
+        .unwrap();
+    assert_eq!(decode_matrix(&matrix).unwrap().content, valid);
+}
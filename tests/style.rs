@@ -0,0 +1,154 @@
+#![cfg(feature = "std")]
+
+use qrcodegenerator::render::{to_svg, FinderShape, ModuleShape, RenderOptions, Style};
+use qrcodegenerator::{QRData, QRMatrix};
+
+fn hello_world() -> QRMatrix {
+    let mut qr_data = QRData::new();
+    qr_data.set_content("HELLO WORLD").unwrap();
+    qr_data.generate(None).unwrap()
+}
+
+fn styled(module: ModuleShape, finder_ring: FinderShape, finder_eye: FinderShape) -> String {
+    let options = RenderOptions {
+        style: Style {
+            module,
+            finder_ring,
+            finder_eye,
+        },
+        ..RenderOptions::default()
+    };
+    to_svg(&hello_world(), &options)
+}
+
+/// The `d` attribute of every path, in document order.
+fn paths(svg: &str) -> Vec<&str> {
+    svg.split("<path d=\"")
+        .skip(1)
+        .map(|rest| &rest[..rest.find('"').unwrap()])
+        .collect()
+}
+
+#[test]
+fn shapes_parse_with_optional_radii() {
+    assert_eq!(
+        "square".parse::<ModuleShape>().unwrap(),
+        ModuleShape::Square
+    );
+    assert_eq!(
+        "rounded".parse::<ModuleShape>().unwrap(),
+        ModuleShape::Rounded { radius: 0.3 }
+    );
+    assert_eq!(
+        "Rounded:0.25".parse::<ModuleShape>().unwrap(),
+        ModuleShape::Rounded { radius: 0.25 }
+    );
+    assert_eq!("dot".parse::<ModuleShape>().unwrap(), ModuleShape::Circle);
+    assert_eq!(
+        "connected".parse::<ModuleShape>().unwrap(),
+        ModuleShape::Connected { radius: 0.5 }
+    );
+    assert!("hexagon".parse::<ModuleShape>().is_err());
+    assert!("rounded:-1".parse::<ModuleShape>().is_err());
+    assert!("rounded:x".parse::<ModuleShape>().is_err());
+
+    assert_eq!(
+        "rounded".parse::<FinderShape>().unwrap(),
+        FinderShape::Rounded { radius: 1.0 }
+    );
+    assert_eq!(
+        "circle".parse::<FinderShape>().unwrap(),
+        FinderShape::Circle
+    );
+    assert!("connected".parse::<FinderShape>().is_err());
+}
+
+#[test]
+fn square_modules_keep_crisp_edges() {
+    let svg = styled(
+        ModuleShape::Square,
+        FinderShape::Square,
+        FinderShape::Square,
+    );
+    assert!(svg.contains(" shape-rendering=\"crispEdges\""));
+    let drawn = paths(&svg);
+    assert_eq!(drawn.len(), 1);
+    // The top-left finder corner is drawn with the other modules.
+    assert!(drawn[0].starts_with("M4,4h1v1h-1z"));
+    assert!(!drawn[0].contains('A') && !drawn[0].contains('a'));
+}
+
+#[test]
+fn data_modules_take_the_shape_and_function_patterns_stay_square() {
+    // (8, 6) is a dark timing module, 4 modules into the quiet zone.
+    let timing = "M12,10h1v1h-1z";
+
+    for (shape, arc) in [
+        (ModuleShape::Rounded { radius: 0.3 }, "A0.3,0.3 0 0 1 "),
+        (ModuleShape::Circle, "a0.5,0.5 0 1 0 1,0"),
+        (ModuleShape::Connected { radius: 0.5 }, "A0.5,0.5 0 0 1 "),
+    ] {
+        let svg = styled(shape, FinderShape::Square, FinderShape::Square);
+        assert!(!svg.contains("crispEdges"));
+        let drawn = paths(&svg);
+        assert_eq!(drawn.len(), 1);
+        assert!(drawn[0].contains(timing), "{:?}", shape);
+        assert!(drawn[0].contains("M4,4h1v1h-1z"), "{:?}", shape);
+        assert!(drawn[0].contains(arc), "{:?}", shape);
+    }
+}
+
+#[test]
+fn connected_modules_only_round_outer_corners() {
+    let rounded = styled(
+        ModuleShape::Rounded { radius: 0.5 },
+        FinderShape::Square,
+        FinderShape::Square,
+    );
+    let connected = styled(
+        ModuleShape::Connected { radius: 0.5 },
+        FinderShape::Square,
+        FinderShape::Square,
+    );
+    let arcs = |svg: &str| paths(svg)[0].matches('A').count();
+    assert!(arcs(&connected) > 0);
+    assert!(arcs(&connected) < arcs(&rounded));
+    // Every rounded module has four arcs, and data modules are the only ones
+    // styled.
+    assert_eq!(arcs(&rounded) % 4, 0);
+}
+
+#[test]
+fn finders_are_drawn_apart_with_their_own_shapes() {
+    let svg = styled(
+        ModuleShape::Square,
+        FinderShape::Circle,
+        FinderShape::Rounded { radius: 1.0 },
+    );
+    let drawn = paths(&svg);
+    assert_eq!(drawn.len(), 2);
+    assert!(!drawn[0].contains("M4,4h1v1h-1z"));
+    assert!(svg.contains("fill-rule=\"evenodd\""));
+
+    let finders = drawn[1];
+    // Outer ring, ring hole and eye of the top-left finder.
+    assert!(finders.starts_with(concat!(
+        "M4,7.5a3.5,3.5 0 1 0 7,0a3.5,3.5 0 1 0 -7,0z ",
+        "M5,7.5a2.5,2.5 0 1 0 5,0a2.5,2.5 0 1 0 -5,0z ",
+        "M7,6H8A1,1 0 0 1 9,7V8A1,1 0 0 1 8,9H7A1,1 0 0 1 6,8V7A1,1 0 0 1 7,6z"
+    )));
+    // The top-right and bottom-left finders start 14 modules further on.
+    assert!(finders.contains("M18,7.5a3.5,3.5 "));
+    assert!(finders.contains("M4,21.5a3.5,3.5 "));
+
+    // A rounded ring keeps the hole concentric by shrinking its radius.
+    let svg = styled(
+        ModuleShape::Square,
+        FinderShape::Rounded { radius: 1.5 },
+        FinderShape::Square,
+    );
+    let finders = paths(&svg)[1];
+    assert!(finders.starts_with("M5.5,4H9.5A1.5,1.5 0 0 1 11,5.5"));
+    assert!(finders.contains("M5.5,5H9.5A0.5,0.5 0 0 1 10,5.5"));
+    assert!(finders.contains("M6,6H9V9H6V6z"));
+}